@@ -0,0 +1,371 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::Simulation;
+use crate::grid::Grid;
+
+/// How many generations back we look for a displaced copy of an object
+const MAX_PERIOD: usize = 16;
+/// Objects larger than this are assumed to not be ships, which keeps big soups cheap to analyze
+const MAX_OBJECT_CELLS: usize = 128;
+/// Cells this close to each other belong to the same object. Some phases of common ships are not 8-connected.
+const OBJECT_RADIUS: usize = 2;
+
+/// Cells of an object relative to its bounding box's upper left corner, with their states. Two objects with the same
+/// key have the same shape.
+type Shape = Vec<(usize, usize, u8)>;
+
+/// Displacement of an object over a single period, in cells
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Velocity {
+    pub rows: i32,
+    pub columns: i32,
+    pub period: usize,
+}
+
+impl Display for Velocity {
+    /// Formats the velocity in the usual c-notation, e.g. `c/4 diagonal` or `(2,1)c/6 oblique`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rows = self.rows.unsigned_abs() as usize;
+        let columns = self.columns.unsigned_abs() as usize;
+
+        let divisor = gcd(gcd(rows, columns), self.period);
+        let (rows, columns, period) = (rows / divisor, columns / divisor, self.period / divisor);
+        let (major, minor) = (rows.max(columns), rows.min(columns));
+
+        if minor != 0 && minor != major {
+            return write!(f, "({},{})c/{} oblique", major, minor, period);
+        }
+
+        let direction = if minor == 0 { "orthogonal" } else { "diagonal" };
+        let speed = if major == 1 {
+            "c".to_string()
+        } else {
+            format!("{}c", major)
+        };
+
+        if period == 1 {
+            write!(f, "{} {}", speed, direction)
+        } else {
+            write!(f, "{}/{} {}", speed, period, direction)
+        }
+    }
+}
+
+/// A moving object found on the grid, along with its bounding box
+#[derive(Clone, PartialEq, Debug)]
+pub struct Ship {
+    pub row: usize,
+    pub column: usize,
+    pub height: usize,
+    pub width: usize,
+    pub velocity: Velocity,
+}
+
+/// A group of nearby non-empty cells
+struct Object {
+    row: usize,
+    column: usize,
+    height: usize,
+    width: usize,
+    shape: Shape,
+}
+
+/// Tracks objects across generations and reports the ones that reappear with the same shape in a different place
+pub struct ShipTracker {
+    /// Shapes seen in previous generations (most recent first), mapped to the upper left corners of the objects
+    history: VecDeque<HashMap<Shape, Vec<(usize, usize)>>>,
+    ships: Vec<Ship>,
+}
+
+impl Default for ShipTracker {
+    fn default() -> Self {
+        ShipTracker::new()
+    }
+}
+
+impl ShipTracker {
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::with_capacity(MAX_PERIOD),
+            ships: Vec::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = ShipTracker::new();
+    }
+
+    /// Ships found by the last call to [ShipTracker::update]
+    pub fn ships(&self) -> &[Ship] {
+        &self.ships
+    }
+
+    /// Analyze the next generation of the simulation. Should be called once per generation.
+    pub fn update(&mut self, simulation: &dyn Simulation) {
+        self.ships.clear();
+
+        let mut generation: HashMap<Shape, Vec<(usize, usize)>> = HashMap::new();
+
+        for object in find_objects(simulation) {
+            if let Some(velocity) = self.find_velocity(&object) {
+                self.ships.push(Ship {
+                    row: object.row,
+                    column: object.column,
+                    height: object.height,
+                    width: object.width,
+                    velocity,
+                });
+            }
+
+            generation
+                .entry(object.shape)
+                .or_default()
+                .push((object.row, object.column));
+        }
+
+        self.history.push_front(generation);
+        self.history.truncate(MAX_PERIOD);
+    }
+
+    /// Look for the most recent generation that had an object with the same shape close enough to have turned into
+    /// this one. Objects that reappear in the same place are still lifes or oscillators and are not reported.
+    fn find_velocity(&self, object: &Object) -> Option<Velocity> {
+        for (age, generation) in self.history.iter().enumerate() {
+            let period = age + 1;
+
+            let Some(origins) = generation.get(&object.shape) else {
+                continue;
+            };
+
+            let mut closest: Option<(i32, i32)> = None;
+            for &(row, column) in origins {
+                let rows = object.row as i32 - row as i32;
+                let columns = object.column as i32 - column as i32;
+                let distance = rows.abs().max(columns.abs());
+
+                if distance == 0 {
+                    // back where it was
+                    return None;
+                }
+
+                // nothing travels faster than light
+                if distance as usize > period {
+                    continue;
+                }
+
+                if closest.is_none_or(|(r, c)| distance < r.abs().max(c.abs())) {
+                    closest = Some((rows, columns));
+                }
+            }
+
+            if let Some((rows, columns)) = closest {
+                return Some(Velocity {
+                    rows,
+                    columns,
+                    period,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+/// Split the non-empty cells on the grid into objects of cells that are at most [OBJECT_RADIUS] apart
+fn find_objects(simulation: &dyn Simulation) -> Vec<Object> {
+    let (rows, columns) = (simulation.rows(), simulation.columns());
+    let mut visited = Grid::new(rows, columns, false);
+    let mut objects = Vec::new();
+    let mut stack = Vec::new();
+
+    for r in 0..rows {
+        for c in 0..columns {
+            if visited[r][c] || simulation.state(r, c) == 0 {
+                continue;
+            }
+
+            // flood fill the object starting at this cell
+            let mut cells = Vec::new();
+            visited[r][c] = true;
+            stack.push((r, c));
+
+            while let Some((row, column)) = stack.pop() {
+                cells.push((row, column, simulation.state(row, column)));
+
+                for neighbor_row in
+                    row.saturating_sub(OBJECT_RADIUS)..=(row + OBJECT_RADIUS).min(rows - 1)
+                {
+                    for neighbor_column in column.saturating_sub(OBJECT_RADIUS)
+                        ..=(column + OBJECT_RADIUS).min(columns - 1)
+                    {
+                        if !visited[neighbor_row][neighbor_column]
+                            && simulation.state(neighbor_row, neighbor_column) != 0
+                        {
+                            visited[neighbor_row][neighbor_column] = true;
+                            stack.push((neighbor_row, neighbor_column));
+                        }
+                    }
+                }
+            }
+
+            if cells.len() > MAX_OBJECT_CELLS {
+                continue;
+            }
+
+            let top = cells.iter().map(|&(row, ..)| row).min().unwrap_or(r);
+            let bottom = cells.iter().map(|&(row, ..)| row).max().unwrap_or(r);
+            let left = cells
+                .iter()
+                .map(|&(_, column, _)| column)
+                .min()
+                .unwrap_or(c);
+            let right = cells
+                .iter()
+                .map(|&(_, column, _)| column)
+                .max()
+                .unwrap_or(c);
+
+            let mut shape: Shape = cells
+                .into_iter()
+                .map(|(row, column, cell)| (row - top, column - left, cell))
+                .collect();
+            shape.sort_by_key(|&(row, column, _)| (row, column));
+
+            objects.push(Object {
+                row: top,
+                column: left,
+                height: bottom - top + 1,
+                width: right - left + 1,
+                shape,
+            });
+        }
+    }
+
+    objects
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::{Automaton, Runner};
+    use crate::simulations::brain::BriansBrain;
+    use crate::simulations::conway::ConwaysLife;
+    use crate::util::Rng;
+    use crate::CellState;
+
+    fn run<A: Automaton<State = CellState>>(
+        automaton: A,
+        cells: &[(usize, usize, CellState)],
+        generations: usize,
+    ) -> Vec<Ship> {
+        let mut runner = Runner::new(automaton, 256, 256);
+        for &(r, c, cell) in cells {
+            runner.grid_mut()[r][c] = cell;
+        }
+
+        let mut tracker = ShipTracker::new();
+        for _ in 0..generations {
+            runner.step(&mut Rng::new(0));
+            tracker.update(&runner);
+        }
+
+        tracker.ships().to_vec()
+    }
+
+    /// Live cells at the given positions
+    fn alive(positions: &[(usize, usize)]) -> Vec<(usize, usize, CellState)> {
+        positions
+            .iter()
+            .map(|&(r, c)| (r, c, CellState::Alive))
+            .collect()
+    }
+
+    #[test]
+    fn velocity_uses_c_notation() {
+        let format = |rows, columns, period| {
+            Velocity {
+                rows,
+                columns,
+                period,
+            }
+            .to_string()
+        };
+
+        assert_eq!(format(1, 1, 4), "c/4 diagonal");
+        assert_eq!(format(-2, 0, 4), "c/2 orthogonal");
+        assert_eq!(format(0, 1, 1), "c orthogonal");
+        assert_eq!(format(2, 0, 5), "2c/5 orthogonal");
+        assert_eq!(format(-1, 2, 6), "(2,1)c/6 oblique");
+    }
+
+    #[test]
+    fn detects_gliders() {
+        let glider = alive(&[(10, 11), (11, 12), (12, 10), (12, 11), (12, 12)]);
+
+        let ships = run(ConwaysLife, &glider, 20);
+
+        assert_eq!(ships.len(), 1);
+        assert_eq!(ships[0].velocity.to_string(), "c/4 diagonal");
+        assert_eq!((ships[0].height, ships[0].width), (3, 3));
+    }
+
+    #[test]
+    fn detects_lightweight_spaceships() {
+        let lwss = alive(&[
+            (10, 21),
+            (10, 24),
+            (11, 20),
+            (12, 20),
+            (12, 24),
+            (13, 20),
+            (13, 21),
+            (13, 22),
+            (13, 23),
+        ]);
+
+        let ships = run(ConwaysLife, &lwss, 20);
+
+        assert_eq!(ships.len(), 1);
+        assert_eq!(ships[0].velocity.to_string(), "c/2 orthogonal");
+    }
+
+    #[test]
+    fn ignores_still_lifes_and_oscillators() {
+        let block_and_blinker = alive(&[
+            (10, 10),
+            (10, 11),
+            (11, 10),
+            (11, 11),
+            (20, 20),
+            (20, 21),
+            (20, 22),
+        ]);
+
+        assert!(run(ConwaysLife, &block_and_blinker, 20).is_empty());
+    }
+
+    #[test]
+    fn detects_brians_brain_ships() {
+        let ship = [
+            (50, 50, CellState::Alive),
+            (51, 50, CellState::Alive),
+            (50, 51, CellState::Dying),
+            (51, 51, CellState::Dying),
+        ];
+
+        let ships = run(BriansBrain, &ship, 10);
+
+        assert_eq!(ships.len(), 1);
+        assert_eq!(ships[0].velocity.to_string(), "c orthogonal");
+    }
+}
@@ -0,0 +1,621 @@
+use std::fmt::Debug;
+
+use crate::grid::Grid;
+use crate::neighborhood::Neighborhood;
+use crate::patterns::Pattern;
+use crate::util::Rng;
+
+/// A color as red, green, blue and alpha components, so that rules can come with a palette without depending on a
+/// graphics library
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    /// Colors of the given number of states with hues spread evenly around the color wheel, for rules where every
+    /// state is as important as the others
+    pub fn rainbow(count: usize) -> Vec<Self> {
+        (0..count)
+            .map(|idx| {
+                // fully saturated, with one of red, green and blue always at full brightness
+                let hue = idx as f32 / count as f32 * 6.;
+                let up = (hue % 1. * 255.).round() as u8;
+                let down = 255 - up;
+                match hue as usize {
+                    0 => Rgba::rgb(255, up, 0),
+                    1 => Rgba::rgb(down, 255, 0),
+                    2 => Rgba::rgb(0, 255, up),
+                    3 => Rgba::rgb(0, down, 255),
+                    4 => Rgba::rgb(up, 0, 255),
+                    _ => Rgba::rgb(255, 0, down),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A synchronous cellular automaton: every generation, each cell's next state is computed from the current states of
+/// the cells around it. Implement this to add a rule, and add it to a [crate::registry::Registry] to make it
+/// selectable at runtime.
+pub trait Automaton {
+    /// What a single cell holds. States are numbered from 0, the empty background state, when they are saved in
+    /// patterns and sessions or painted with the mouse.
+    type State: Copy + PartialEq + Debug + From<u8> + Into<u8>;
+
+    /// Human readable name, shown in the mode menu
+    fn name(&self) -> String;
+
+    /// The rule in a notation that the registry can parse back, used when saving and exporting runs
+    fn rulestring(&self) -> String;
+
+    /// Number of states a cell can be in
+    fn state_count(&self) -> usize;
+
+    /// What the cell at the given position turns into in the next generation
+    fn next_state(&self, grid: &Grid<Self::State>, row: usize, column: usize) -> Self::State;
+
+    /// Compute the whole next generation into `next`. The default calls [Automaton::next_state] for every cell, rules
+    /// that can share work between cells can do better.
+    fn next_generation(&self, grid: &Grid<Self::State>, next: &mut Grid<Self::State>) {
+        for (r, row) in next.iter_rows_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                *cell = self.next_state(grid, r, c);
+            }
+        }
+    }
+
+    /// Cells that the transition function looks at
+    fn neighborhood(&self) -> Neighborhood {
+        Neighborhood::default()
+    }
+
+    /// Whether cells in this state count as alive, for statistics and analysis
+    fn is_alive(&self, state: Self::State) -> bool {
+        state.into() == 1
+    }
+
+    /// Colors of the states, in order. Rules without their own palette are drawn in the colors of the theme.
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        None
+    }
+
+    /// Whether new runs start from an empty grid rather than a random soup, for rules where soups are meaningless.
+    /// See [Automaton::reset] for rules that start from something other than an empty grid.
+    fn starts_empty(&self) -> bool {
+        false
+    }
+
+    /// Set the grid to where runs of the rule start from, when cleared. Empty by default.
+    fn reset(&self, grid: &mut Grid<Self::State>) {
+        grid.fill(Self::State::from(0));
+    }
+
+    /// Named patterns to build with, such as the wires and gates of a circuit
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        Vec::new()
+    }
+
+    /// State of a cell in a random soup, where `density` is the fraction of cells that are not empty
+    fn random_state(&self, rng: &mut Rng, density: f64) -> Self::State {
+        Self::State::from(u8::from(rng.next_f64() < density))
+    }
+
+    /// Number of live cells in the neighborhood of the cell
+    fn count_neighbors(&self, grid: &Grid<Self::State>, row: usize, column: usize) -> u32 {
+        self.neighborhood()
+            .cells(grid.rows(), grid.columns(), row, column)
+            .filter(|&(row, column)| self.is_alive(grid[row][column]))
+            .count() as u32
+    }
+}
+
+/// How many cells are in each state after a generation, and how many changed state
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct GenerationCounts {
+    pub alive: usize,
+    /// Cells that are neither alive nor empty, such as the dying cells in Brian's Brain
+    pub dying: usize,
+    pub births: usize,
+    pub deaths: usize,
+}
+
+/// The direction an agent faces
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Heading {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    const ALL: [Heading; 4] = [Heading::North, Heading::East, Heading::South, Heading::West];
+
+    /// Offset of the cell in front, as (rows, columns)
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Heading::North => (-1, 0),
+            Heading::East => (0, 1),
+            Heading::South => (1, 0),
+            Heading::West => (0, -1),
+        }
+    }
+
+    /// Turn clockwise by the given number of quarter turns
+    pub fn turn(&self, quarter_turns: u8) -> Self {
+        Heading::ALL[(*self as usize + quarter_turns as usize) % 4]
+    }
+
+    /// The heading with the given name, e.g. `North`, as written by its Debug implementation
+    pub fn parse(name: &str) -> Option<Self> {
+        Heading::ALL
+            .into_iter()
+            .find(|heading| format!("{:?}", heading).eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// Something that moves over the grid instead of being a cell of it, such as an ant
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Agent {
+    pub row: usize,
+    pub column: usize,
+    pub heading: Heading,
+}
+
+/// A number that can be changed while a simulation runs, see [Simulation::parameters]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub value: f64,
+    /// Whether the parameter only takes whole numbers, like a radius, so that it is changed a step at a time
+    pub integer: bool,
+}
+
+impl Parameter {
+    pub fn real(name: &'static str, value: f64) -> Self {
+        Self {
+            name,
+            value,
+            integer: false,
+        }
+    }
+
+    pub fn integer(name: &'static str, value: usize) -> Self {
+        Self {
+            name,
+            value: value as f64,
+            integer: true,
+        }
+    }
+}
+
+/// A running simulation on a grid of a fixed size. Unlike [Automaton], the type of the cells is hidden, so that
+/// different kinds of simulations can be swapped at runtime. Cell states are passed around as their numbers.
+///
+/// Past the cells, some kinds of simulations have agents that move over the grid, parameters that can be changed
+/// while they run, measurements to chart, state that a saved session has to keep, or cells that hold levels rather
+/// than states. Each of these comes with a default for the simulations that don't have it.
+pub trait Simulation {
+    fn name(&self) -> String;
+
+    /// See [Automaton::rulestring]
+    fn rulestring(&self) -> String;
+
+    fn rows(&self) -> usize;
+
+    fn columns(&self) -> usize;
+
+    fn state_count(&self) -> usize;
+
+    /// Advance the simulation by one generation. Stochastic simulations draw their chances from the given random
+    /// number generator, so that a run can be reproduced from its seed.
+    fn step(&mut self, rng: &mut Rng) -> GenerationCounts;
+
+    fn state(&self, row: usize, column: usize) -> u8;
+
+    /// Panics if the state is not one of the states of the simulation
+    fn set_state(&mut self, row: usize, column: usize, state: u8) {
+        assert!(
+            (state as usize) < self.state_count(),
+            "{} has no state {}",
+            self.name(),
+            state
+        );
+
+        self.write_state(row, column, state);
+    }
+
+    /// Set the cell to a state that [Simulation::set_state] has made sure the simulation has
+    fn write_state(&mut self, row: usize, column: usize, state: u8);
+
+    /// Set every cell to the empty state, or to where runs of the rule start from, see [Automaton::reset]
+    fn clear(&mut self);
+
+    /// Replace the grid with a random soup
+    fn randomize(&mut self, rng: &mut Rng, density: f64);
+
+    fn is_alive(&self, row: usize, column: usize) -> bool;
+
+    /// What the cell will turn into in the next generation
+    fn next_state(&self, row: usize, column: usize) -> u8;
+
+    /// Number of neighbors the rule takes into account when updating the cell
+    fn count_neighbors(&self, row: usize, column: usize) -> u32;
+
+    /// Human readable name of a state
+    fn state_name(&self, state: u8) -> String;
+
+    /// See [Automaton::palette]
+    fn palette(&self) -> Option<Vec<Rgba>>;
+
+    /// See [Automaton::starts_empty]
+    fn starts_empty(&self) -> bool;
+
+    /// See [Automaton::presets]
+    fn presets(&self) -> Vec<(String, Pattern)>;
+
+    /// Agents that move over the grid, drawn on top of the cells. Cellular automata have none.
+    fn agents(&self) -> Vec<Agent> {
+        Vec::new()
+    }
+
+    /// Put a new agent facing north on the cell. Returns false if the simulation has no agents.
+    fn add_agent(&mut self, _row: usize, _column: usize) -> bool {
+        false
+    }
+
+    /// Labelled values that the chart and the history record instead of [GenerationCounts], such as the number of
+    /// susceptible, infected and recovered cells of an epidemic, or the mean concentrations of a reaction-diffusion
+    /// model. Empty for everything else.
+    fn measurements(&self) -> Vec<(&'static str, f32)> {
+        Vec::new()
+    }
+
+    /// Numbers that can be changed while the simulation runs, such as the probabilities of a stochastic simulation
+    /// or the kernel radius of a continuous one, with their names
+    fn parameters(&self) -> Vec<Parameter> {
+        Vec::new()
+    }
+
+    /// Change the parameter at the given index in [Simulation::parameters]. Values are clamped to what the
+    /// parameter allows.
+    fn set_parameter(&mut self, _idx: usize, _value: f64) {}
+
+    /// Whatever a saved session needs besides the cell states to carry on exactly where the simulation was, such as
+    /// the ants of a turmite, as `key = value` lines. Empty for simulations that are all in their cell states.
+    fn session_state(&self) -> String {
+        String::new()
+    }
+
+    /// Pick up the state from [Simulation::session_state] again, after the cells have been restored
+    fn restore_session_state(&mut self, state: &str) -> Result<(), String> {
+        match state.trim() {
+            "" => Ok(()),
+            _ => Err(format!("{} has no session state to restore", self.name())),
+        }
+    }
+
+    /// Whether cells hold levels of a continuous value from 0 to 1 rather than distinct states, so that they are
+    /// drawn with a colormap instead of a palette
+    fn is_continuous(&self) -> bool {
+        false
+    }
+}
+
+/// Runs an [Automaton] on a grid, double buffered so that every cell is updated from the same generation
+pub struct Runner<A: Automaton> {
+    automaton: A,
+    grid: Grid<A::State>,
+    buffer: Grid<A::State>,
+}
+
+impl<A: Automaton> Runner<A> {
+    /// Create a runner with every cell in the empty state
+    pub fn new(automaton: A, rows: usize, columns: usize) -> Self {
+        let empty = A::State::from(0);
+
+        Self {
+            automaton,
+            grid: Grid::new(rows, columns, empty),
+            buffer: Grid::new(rows, columns, empty),
+        }
+    }
+
+    pub fn automaton(&self) -> &A {
+        &self.automaton
+    }
+
+    pub fn grid(&self) -> &Grid<A::State> {
+        &self.grid
+    }
+
+    pub fn grid_mut(&mut self) -> &mut Grid<A::State> {
+        &mut self.grid
+    }
+}
+
+impl<A: Automaton> Simulation for Runner<A> {
+    fn name(&self) -> String {
+        self.automaton.name()
+    }
+
+    fn rulestring(&self) -> String {
+        self.automaton.rulestring()
+    }
+
+    fn rows(&self) -> usize {
+        self.grid.rows()
+    }
+
+    fn columns(&self) -> usize {
+        self.grid.columns()
+    }
+
+    fn state_count(&self) -> usize {
+        self.automaton.state_count()
+    }
+
+    fn step(&mut self, _rng: &mut Rng) -> GenerationCounts {
+        // write the next generation to the buffer
+        self.automaton.next_generation(&self.grid, &mut self.buffer);
+
+        let empty = A::State::from(0);
+        let mut counts = GenerationCounts::default();
+        for (row, next_row) in self.grid.iter_rows().zip(self.buffer.iter_rows()) {
+            for (&cell, &next_cell) in row.iter().zip(next_row.iter()) {
+                let (was_alive, is_alive) = (
+                    self.automaton.is_alive(cell),
+                    self.automaton.is_alive(next_cell),
+                );
+
+                if is_alive {
+                    counts.alive += 1;
+                } else if next_cell != empty {
+                    counts.dying += 1;
+                }
+
+                if is_alive && !was_alive {
+                    counts.births += 1;
+                } else if was_alive && !is_alive {
+                    counts.deaths += 1;
+                }
+            }
+        }
+
+        std::mem::swap(&mut self.grid, &mut self.buffer);
+
+        counts
+    }
+
+    fn state(&self, row: usize, column: usize) -> u8 {
+        self.grid[row][column].into()
+    }
+
+    fn write_state(&mut self, row: usize, column: usize, state: u8) {
+        self.grid[row][column] = A::State::from(state);
+    }
+
+    fn clear(&mut self) {
+        self.automaton.reset(&mut self.grid);
+    }
+
+    fn randomize(&mut self, rng: &mut Rng, density: f64) {
+        for row in self.grid.iter_rows_mut() {
+            for cell in row.iter_mut() {
+                *cell = self.automaton.random_state(rng, density);
+            }
+        }
+    }
+
+    fn is_alive(&self, row: usize, column: usize) -> bool {
+        self.automaton.is_alive(self.grid[row][column])
+    }
+
+    fn next_state(&self, row: usize, column: usize) -> u8 {
+        self.automaton.next_state(&self.grid, row, column).into()
+    }
+
+    fn count_neighbors(&self, row: usize, column: usize) -> u32 {
+        self.automaton.count_neighbors(&self.grid, row, column)
+    }
+
+    fn state_name(&self, state: u8) -> String {
+        format!("{:?}", A::State::from(state))
+    }
+
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        self.automaton.palette()
+    }
+
+    fn starts_empty(&self) -> bool {
+        self.automaton.starts_empty()
+    }
+
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        self.automaton.presets()
+    }
+}
+
+/// A simulation shown under a name of its own, e.g. for a preset of a rule that is only known by its rulestring
+pub struct Named {
+    name: String,
+    simulation: Box<dyn Simulation>,
+}
+
+impl Named {
+    pub fn new(name: String, simulation: Box<dyn Simulation>) -> Self {
+        Self { name, simulation }
+    }
+}
+
+impl Simulation for Named {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn rulestring(&self) -> String {
+        self.simulation.rulestring()
+    }
+
+    fn rows(&self) -> usize {
+        self.simulation.rows()
+    }
+
+    fn columns(&self) -> usize {
+        self.simulation.columns()
+    }
+
+    fn state_count(&self) -> usize {
+        self.simulation.state_count()
+    }
+
+    fn step(&mut self, rng: &mut Rng) -> GenerationCounts {
+        self.simulation.step(rng)
+    }
+
+    fn state(&self, row: usize, column: usize) -> u8 {
+        self.simulation.state(row, column)
+    }
+
+    fn write_state(&mut self, row: usize, column: usize, state: u8) {
+        self.simulation.write_state(row, column, state)
+    }
+
+    fn clear(&mut self) {
+        self.simulation.clear()
+    }
+
+    fn randomize(&mut self, rng: &mut Rng, density: f64) {
+        self.simulation.randomize(rng, density)
+    }
+
+    fn is_alive(&self, row: usize, column: usize) -> bool {
+        self.simulation.is_alive(row, column)
+    }
+
+    fn next_state(&self, row: usize, column: usize) -> u8 {
+        self.simulation.next_state(row, column)
+    }
+
+    fn count_neighbors(&self, row: usize, column: usize) -> u32 {
+        self.simulation.count_neighbors(row, column)
+    }
+
+    fn state_name(&self, state: u8) -> String {
+        self.simulation.state_name(state)
+    }
+
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        self.simulation.palette()
+    }
+
+    fn starts_empty(&self) -> bool {
+        self.simulation.starts_empty()
+    }
+
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        self.simulation.presets()
+    }
+
+    fn agents(&self) -> Vec<Agent> {
+        self.simulation.agents()
+    }
+
+    fn add_agent(&mut self, row: usize, column: usize) -> bool {
+        self.simulation.add_agent(row, column)
+    }
+
+    fn measurements(&self) -> Vec<(&'static str, f32)> {
+        self.simulation.measurements()
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        self.simulation.parameters()
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        self.simulation.set_parameter(idx, value)
+    }
+
+    fn session_state(&self) -> String {
+        self.simulation.session_state()
+    }
+
+    fn restore_session_state(&mut self, state: &str) -> Result<(), String> {
+        self.simulation.restore_session_state(state)
+    }
+
+    fn is_continuous(&self) -> bool {
+        self.simulation.is_continuous()
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulations::conway::ConwaysLife;
+    use crate::CellState;
+
+    #[test]
+    fn runner_steps_and_counts_changes() {
+        // a blinker turning from horizontal to vertical
+        let mut runner = Runner::new(ConwaysLife, 5, 5);
+        for column in 1..4 {
+            runner.set_state(2, column, 1);
+        }
+
+        let counts = runner.step(&mut Rng::new(0));
+
+        assert_eq!(
+            counts,
+            GenerationCounts {
+                alive: 3,
+                dying: 0,
+                births: 2,
+                deaths: 2,
+            }
+        );
+        assert_eq!(runner.grid()[1][2], CellState::Alive);
+        assert_eq!(runner.grid()[2][1], CellState::Dead);
+        assert_eq!(runner.count_neighbors(2, 1), 3);
+        assert_eq!(runner.state_name(runner.next_state(2, 1)), "Alive");
+    }
+
+    #[test]
+    fn runner_randomizes_with_density() {
+        let mut runner = Runner::new(ConwaysLife, 100, 100);
+
+        runner.randomize(&mut Rng::new(42), 0.25);
+        let alive = runner
+            .grid()
+            .iter_rows()
+            .flatten()
+            .filter(|&&cell| cell == CellState::Alive)
+            .count();
+
+        assert!((2000..3000).contains(&alive), "{}", alive);
+    }
+
+    #[test]
+    #[should_panic(expected = "Blinker has no state 2")]
+    fn named_simulations_check_states() {
+        let mut named = Named::new(
+            "Blinker".to_string(),
+            Box::new(Runner::new(ConwaysLife, 3, 3)),
+        );
+        named.set_state(1, 1, 1);
+        assert_eq!(named.state(1, 1), 1);
+        assert_eq!(named.rulestring(), "B3/S23");
+
+        named.set_state(1, 1, 2);
+    }
+}
@@ -1,43 +1,59 @@
 use std::collections::VecDeque;
 
-use macroquad::color::{Color, LIGHTGRAY, WHITE};
+use macroquad::color::Color;
 use macroquad::shapes::{draw_line, draw_rectangle};
-use macroquad::text::draw_text;
+use macroquad::text::{draw_text, measure_text};
 
-const BUCKET_SIZE_MILLISECONDS: i32 = 100;
-const CHART_WINDOW_SECONDS: usize = 10;
-const BUCKET_COUNT: usize = CHART_WINDOW_SECONDS * (1000 / BUCKET_SIZE_MILLISECONDS) as usize;
+use crate::theme::Theme;
+
+pub const DEFAULT_BUCKET_SIZE_GENERATIONS: u64 = 5;
+pub const DEFAULT_CHART_WINDOW_GENERATIONS: u64 = 200;
 const CHART_HEIGHT: f32 = 50.;
-const CHART_WIDTH: f32 = BUCKET_COUNT as f32;
-// 1 pixel per bucket
-const CHART_BORDER_COLOR: Color = LIGHTGRAY;
+const CHART_WIDTH: f32 = 200.;
 const CHART_BORDER_THICCNESS: f32 = 1.;
-const CHART_LINE_COLOR: Color = WHITE;
+// opacity of the band between the min and max value of each bucket
+const CHART_BAND_ALPHA: f32 = 0.3;
 const CHART_LINE_THICCNESS: f32 = 2.;
 const CHART_LEGEND_FONT_SIZE: f32 = 14.;
+const CHART_TICK_LENGTH: f32 = 4.;
+// roughly how many ticks to draw along the x-axis
+const CHART_TICK_COUNT: u64 = 4;
 
 pub struct DataPoint {
-    timestamp_millis: i32,
+    generation: u64,
     value: f32,
 }
 
 impl DataPoint {
-    pub fn new(timestamp_millis: i32, value: f32) -> Self {
-        Self {
-            timestamp_millis,
-            value,
-        }
+    pub fn new(generation: u64, value: f32) -> Self {
+        Self { generation, value }
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct DataPointBucket {
-    timestamp_millis: i32,
+    generation: u64,
     sum: f32,
     count: i32,
+    min: f32,
+    max: f32,
 }
 
 impl DataPointBucket {
+    /// Create a bucket holding a single point. The bucket starts at the point's generation rounded down to the nearest
+    /// multiple of the bucket size.
+    fn new(data_point: DataPoint, bucket_size: u64) -> Self {
+        let DataPoint { generation, value } = data_point;
+
+        Self {
+            generation: (generation / bucket_size) * bucket_size,
+            sum: value,
+            count: 1,
+            min: value,
+            max: value,
+        }
+    }
+
     fn avg(&self) -> f32 {
         self.sum / (self.count as f32)
     }
@@ -45,69 +61,87 @@ impl DataPointBucket {
     fn store(&mut self, point: &DataPoint) {
         self.count += 1;
         self.sum += &point.value;
+        self.min = self.min.min(point.value);
+        self.max = self.max.max(point.value);
     }
 }
 
-impl From<DataPoint> for DataPointBucket {
-    fn from(data_point: DataPoint) -> Self {
-        let DataPoint {
-            timestamp_millis,
-            value,
-        } = data_point;
+/// Maps values to heights on the chart
+#[derive(Debug, PartialEq)]
+struct YAxis {
+    min: f32,
+    max: f32,
+    log_scale: bool,
+}
 
-        // round down to nearest bucket size in milliseconds
-        let timestamp_millis =
-            (timestamp_millis / BUCKET_SIZE_MILLISECONDS) * BUCKET_SIZE_MILLISECONDS;
+impl YAxis {
+    /// Height of the value above the bottom of the chart, in pixels
+    fn height(&self, value: f32) -> f32 {
+        let scale = |value: f32| {
+            if self.log_scale {
+                // shifted by one so that zero stays at the bottom of the chart
+                (value.max(0.) + 1.).log10()
+            } else {
+                value
+            }
+        };
 
-        Self {
-            timestamp_millis,
-            sum: value,
-            count: 1,
+        let (min, max) = (scale(self.min), scale(self.max));
+        if max <= min {
+            return 0.;
         }
+
+        ((scale(value) - min) / (max - min)).clamp(0., 1.) * CHART_HEIGHT
     }
 }
 
 pub struct TimeSeries {
     series: VecDeque<DataPointBucket>,
+    bucket_size: u64,
+    bucket_count: usize,
 }
 
 impl TimeSeries {
-    pub fn new() -> Self {
+    /// Create a series that averages points over buckets of `bucket_size` generations and keeps the last
+    /// `window_generations` worth of buckets
+    pub fn new(bucket_size: u64, window_generations: u64) -> Self {
+        assert!(bucket_size > 0, "Bucket size must be at least 1 generation");
+
         Self {
             series: VecDeque::new(),
+            bucket_size,
+            bucket_count: window_generations.div_ceil(bucket_size).max(1) as usize,
         }
     }
 
     pub fn reset(&mut self) {
-        *self = TimeSeries::new();
+        self.series.clear();
     }
 
     pub fn record(&mut self, data_point: DataPoint) {
         let last_bucket = self.series.iter_mut().last();
         if last_bucket.is_none() {
             // no elements added yet
-            self.series.push_back(DataPointBucket::from(data_point));
+            self.series
+                .push_back(DataPointBucket::new(data_point, self.bucket_size));
             return;
         }
 
-        // check if new ts is within X millis of the first value in the bucket
+        // check if the new point is within bucket_size generations of the first value in the bucket
         let last_bucket = last_bucket.expect("Bucket should not be None at this point");
 
-        let bucket_start_ts = last_bucket.timestamp_millis;
+        let bucket_start = last_bucket.generation;
 
-        let DataPoint {
-            timestamp_millis, ..
-        } = data_point;
+        let DataPoint { generation, .. } = data_point;
         assert!(
-            timestamp_millis >= bucket_start_ts,
+            generation >= bucket_start,
             "Only chronologically ordered points are supported."
         );
 
-        let time_diff = timestamp_millis - bucket_start_ts;
-
         // if the new point falls outside the last bucket, append a new bucket
-        if time_diff >= BUCKET_SIZE_MILLISECONDS {
-            self.series.push_back(DataPointBucket::from(data_point));
+        if generation - bucket_start >= self.bucket_size {
+            self.series
+                .push_back(DataPointBucket::new(data_point, self.bucket_size));
         }
         // otherwise store the new point in the last bucket
         else {
@@ -115,38 +149,207 @@ impl TimeSeries {
         }
 
         // remove old buckets
-        while self.series.len() > BUCKET_COUNT {
+        while self.series.len() > self.bucket_count {
             self.series.pop_front();
         }
     }
 
-    /// Draw a simple line chart for the average values of the points in the time series. x, y is the upper left corner.
-    pub fn display(&self, x: f32, y: f32, current_val_label: &str) {
-        let mut points: [Option<f32>; BUCKET_COUNT] = [None; BUCKET_COUNT];
-        let mut max_val = 0.; // used for scaling the chart later
+    /// Generation at the left edge of the chart
+    fn window_start(&self) -> u64 {
+        self.series.front().map_or(0, |bucket| bucket.generation)
+    }
+
+    fn window_generations(&self) -> u64 {
+        self.bucket_count as u64 * self.bucket_size
+    }
 
+    /// Draw the average values of the points in the time series as a line, over a band that spans the min and max
+    /// value of each bucket. window_start is at the left edge of the chart. x, y is the upper left corner of the chart.
+    fn draw(&self, x: f32, y: f32, window_start: u64, y_axis: &YAxis, color: Color) {
         assert!(
-            self.series.len() <= BUCKET_COUNT,
+            self.series.len() <= self.bucket_count,
             "Expected stored buckets to not exceed the max bucket count"
         );
 
+        let x_scale = CHART_WIDTH / self.window_generations() as f32;
+        let band_width = (self.bucket_size as f32 * x_scale).max(1.);
+        let band_color = Color::new(color.r, color.g, color.b, color.a * CHART_BAND_ALPHA);
+
+        let mut prev_x = 0.;
+        let mut prev_y = 0.;
         for (idx, bucket) in self.series.iter().enumerate() {
-            let bucket_avg = bucket.avg();
+            let point_x = x + bucket.generation.saturating_sub(window_start) as f32 * x_scale;
+            let point_y = y + (CHART_HEIGHT - y_axis.height(bucket.avg()));
 
-            points[idx] = Some(bucket_avg);
+            // shade the range of values that went into the average
+            let band_top = y + (CHART_HEIGHT - y_axis.height(bucket.max));
+            let band_bottom = y + (CHART_HEIGHT - y_axis.height(bucket.min));
+            if band_bottom > band_top {
+                draw_rectangle(
+                    point_x,
+                    band_top,
+                    band_width,
+                    band_bottom - band_top,
+                    band_color,
+                );
+            }
 
-            if bucket_avg > max_val {
-                max_val = bucket_avg;
+            // draw the first point as a simple rectangle
+            if idx == 0 {
+                draw_rectangle(
+                    point_x,
+                    point_y,
+                    CHART_LINE_THICCNESS,
+                    CHART_LINE_THICCNESS,
+                    color,
+                );
+
+                prev_x = point_x;
+                prev_y = point_y;
+
+                continue;
             }
+
+            // draw the remaining points as lines connected to the previous point
+            draw_line(
+                prev_x,
+                prev_y,
+                point_x,
+                point_y,
+                CHART_LINE_THICCNESS,
+                color,
+            );
+
+            prev_x = point_x;
+            prev_y = point_y;
         }
+    }
+
+    /// Smallest and largest values recorded in the window, or None if nothing has been recorded yet
+    fn range(&self) -> Option<(f32, f32)> {
+        self.series.iter().fold(None, |range, bucket| match range {
+            None => Some((bucket.min, bucket.max)),
+            Some((min, max)) => Some((min.min(bucket.min), max.max(bucket.max))),
+        })
+    }
+
+    /// The last bucket that no more points will go into, or the one being filled if it is the only one, so that the
+    /// legend describes a whole bucket
+    fn latest_bucket(&self) -> Option<&DataPointBucket> {
+        let full_buckets = self.series.len().saturating_sub(1).max(1);
+        self.series.get(full_buckets - 1)
+    }
+}
 
-        // legend for max value
+/// A time series with a label to show in the legend
+struct NamedSeries {
+    label: &'static str,
+    time_series: TimeSeries,
+}
+
+/// Several time series drawn on the same axes, with a legend
+pub struct Chart {
+    series: Vec<NamedSeries>,
+    /// Scale the y-axis logarithmically, so that small changes in large values remain visible
+    log_scale: bool,
+    /// Start the y-axis at the smallest recorded value instead of zero
+    zoom_to_data: bool,
+    bucket_size: u64,
+    window_generations: u64,
+}
+
+impl Chart {
+    /// Create a chart with one empty series per label. Points are averaged over buckets of `bucket_size` generations
+    /// and the last `window_generations` are shown.
+    pub fn new(labels: &[&'static str], bucket_size: u64, window_generations: u64) -> Self {
+        Self {
+            series: labels
+                .iter()
+                .map(|&label| NamedSeries {
+                    label,
+                    time_series: TimeSeries::new(bucket_size, window_generations),
+                })
+                .collect(),
+            log_scale: false,
+            zoom_to_data: false,
+            bucket_size,
+            window_generations,
+        }
+    }
+
+    /// Replace the series with empty ones with the given labels, keeping how the chart is scaled
+    pub fn set_labels(&mut self, labels: &[&'static str]) {
+        self.series = labels
+            .iter()
+            .map(|&label| NamedSeries {
+                label,
+                time_series: TimeSeries::new(self.bucket_size, self.window_generations),
+            })
+            .collect();
+    }
+
+    pub fn toggle_log_scale(&mut self) {
+        self.log_scale = !self.log_scale;
+    }
+
+    pub fn toggle_zoom_to_data(&mut self) {
+        self.zoom_to_data = !self.zoom_to_data;
+    }
+
+    /// Range of the y-axis, covering every value recorded in the window
+    fn y_axis(&self) -> YAxis {
+        let (min, max) = self
+            .series
+            .iter()
+            .filter_map(|series| series.time_series.range())
+            .fold((f32::MAX, 0_f32), |(min, max), (series_min, series_max)| {
+                (min.min(series_min), max.max(series_max))
+            });
+
+        YAxis {
+            min: if self.zoom_to_data { min.min(max) } else { 0. },
+            max,
+            log_scale: self.log_scale,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for series in self.series.iter_mut() {
+            series.time_series.reset();
+        }
+    }
+
+    /// Record a data point in the series with the given label
+    pub fn record(&mut self, label: &str, data_point: DataPoint) {
+        self.series
+            .iter_mut()
+            .find(|series| series.label == label)
+            .unwrap_or_else(|| panic!("Unknown chart series: {}", label))
+            .time_series
+            .record(data_point);
+    }
+
+    /// Draw a simple line chart for the average values of the points in every series, followed by a legend with the
+    /// latest min, average and max value of each series. All series share the same scale. x, y is the upper left
+    /// corner. Series are colored in order using the theme's chart colors.
+    pub fn display(&self, x: f32, y: f32, theme: &Theme) {
+        // used for scaling the chart
+        let y_axis = self.y_axis();
+
+        // legend for the range of the y-axis
+        let mut range_text = format!("Max: {}", format_value(y_axis.max));
+        if y_axis.min > 0. {
+            range_text += format!(" Min: {}", format_value(y_axis.min)).as_str();
+        }
+        if y_axis.log_scale {
+            range_text += " (log)";
+        }
         draw_text(
-            format!("Max: {}", max_val as i32).as_str(),
+            range_text.as_str(),
             x,
             y - CHART_LEGEND_FONT_SIZE / 2.,
             CHART_LEGEND_FONT_SIZE,
-            CHART_BORDER_COLOR,
+            theme.chart_border,
         );
 
         // left border
@@ -156,7 +359,7 @@ impl TimeSeries {
             x,
             y + CHART_HEIGHT + CHART_LINE_THICCNESS,
             CHART_BORDER_THICCNESS,
-            CHART_BORDER_COLOR,
+            theme.chart_border,
         );
 
         // bottom border
@@ -166,65 +369,115 @@ impl TimeSeries {
             x + CHART_WIDTH,
             y + CHART_HEIGHT + CHART_LINE_THICCNESS,
             CHART_BORDER_THICCNESS,
-            CHART_BORDER_COLOR,
+            theme.chart_border,
         );
 
-        let mut last_point = 0.;
-        let mut prev_x = 0.;
-        let mut prev_y = 0.;
-        for (idx, point) in points.iter().enumerate() {
-            if point.is_none() {
-                // no more valid points in the array
-                break;
-            };
+        let Some(first_series) = self.series.first() else {
+            return;
+        };
+        // all series are recorded together, so they share the same window
+        let window_start = first_series.time_series.window_start();
+        let window_generations = first_series.time_series.window_generations();
+
+        for (idx, series) in self.series.iter().enumerate() {
+            series
+                .time_series
+                .draw(x, y, window_start, &y_axis, theme.chart_series_color(idx));
+        }
 
-            let point = point.unwrap();
-            let point_height = point / max_val * CHART_HEIGHT;
+        // generation ticks along the bottom border
+        let axis_y = y + CHART_HEIGHT + CHART_LINE_THICCNESS;
+        let tick_step = tick_step(window_generations);
+        let mut tick = window_start.div_ceil(tick_step) * tick_step;
+        while tick <= window_start + window_generations {
+            let tick_x = x + (tick - window_start) as f32 * CHART_WIDTH / window_generations as f32;
+            let label = tick.to_string();
+            let label_width = measure_text(&label, None, CHART_LEGEND_FONT_SIZE as u16, 1.).width;
 
-            let point_x = x + idx as f32;
-            let point_y = y + (CHART_HEIGHT - point_height);
+            draw_line(
+                tick_x,
+                axis_y,
+                tick_x,
+                axis_y + CHART_TICK_LENGTH,
+                CHART_BORDER_THICCNESS,
+                theme.chart_border,
+            );
+            draw_text(
+                &label,
+                tick_x - label_width / 2.,
+                axis_y + CHART_TICK_LENGTH + CHART_LEGEND_FONT_SIZE,
+                CHART_LEGEND_FONT_SIZE,
+                theme.chart_border,
+            );
 
-            last_point = point;
+            tick += tick_step;
+        }
 
-            // draw the first point as a simple rectangle
-            if idx == 0 {
-                draw_rectangle(
-                    point_x,
-                    point_y,
-                    CHART_LINE_THICCNESS,
-                    CHART_LINE_THICCNESS,
-                    CHART_LINE_COLOR,
-                );
+        // legend with a color swatch and the latest values for each series
+        let mut legend_y = axis_y + CHART_TICK_LENGTH + CHART_LEGEND_FONT_SIZE * 2.;
+        for (idx, series) in self.series.iter().enumerate() {
+            let color = theme.chart_series_color(idx);
+            draw_rectangle(
+                x,
+                legend_y - CHART_LEGEND_FONT_SIZE / 2.,
+                CHART_LEGEND_FONT_SIZE / 2.,
+                CHART_LEGEND_FONT_SIZE / 2.,
+                color,
+            );
+            draw_text(
+                legend_text(series).as_str(),
+                x + CHART_LEGEND_FONT_SIZE,
+                legend_y,
+                CHART_LEGEND_FONT_SIZE,
+                color,
+            );
+            legend_y += CHART_LEGEND_FONT_SIZE;
+        }
+    }
+}
 
-                prev_x = point_x;
-                prev_y = point_y;
+/// Describes the latest bucket of the series, e.g. `42 cells alive (min 40, max 45)`
+fn legend_text(series: &NamedSeries) -> String {
+    match series.time_series.latest_bucket() {
+        Some(bucket) => format!(
+            "{} {} (min {}, max {})",
+            format_value(bucket.avg()),
+            series.label,
+            format_value(bucket.min),
+            format_value(bucket.max)
+        ),
+        None => format!("0 {}", series.label),
+    }
+}
 
-                continue;
-            }
+/// Values from 10 up are cell counts, and are shown as whole numbers. Smaller ones can be fractions, such as mean
+/// concentrations, and keep up to 3 decimals.
+fn format_value(value: f32) -> String {
+    if value.abs() >= 10. {
+        return (value as i32).to_string();
+    }
 
-            // draw the remaining points as lines connected to the previous point
-            draw_line(
-                prev_x,
-                prev_y,
-                point_x,
-                point_y,
-                CHART_LINE_THICCNESS,
-                CHART_LINE_COLOR,
-            );
+    let value = format!("{:.3}", value);
+    value
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
 
-            prev_x = point_x;
-            prev_y = point_y;
-        }
+/// Distance between x-axis ticks, rounded to a "nice" number of generations (1, 2 or 5 times a power of 10)
+fn tick_step(window_generations: u64) -> u64 {
+    let rough_step = (window_generations / CHART_TICK_COUNT).max(1);
 
-        // draw the legend for the last point
-        draw_text(
-            format!("{} {}", last_point as i32, current_val_label).as_str(),
-            x + CHART_WIDTH + 1.0,
-            prev_y,
-            CHART_LEGEND_FONT_SIZE,
-            CHART_LINE_COLOR,
-        );
+    let mut magnitude = 1;
+    while magnitude * 10 <= rough_step {
+        magnitude *= 10;
     }
+
+    [5, 2, 1]
+        .into_iter()
+        .map(|multiplier| multiplier * magnitude)
+        .find(|&step| step <= rough_step)
+        .unwrap_or(magnitude)
 }
 
 // tests
@@ -234,7 +487,7 @@ mod tests {
 
     #[test]
     fn time_series_records_points() {
-        let mut ts = TimeSeries::new();
+        let mut ts = TimeSeries::new(100, 10_000);
         ts.record(DataPoint::new(0, 1.));
         ts.record(DataPoint::new(100, 2.));
         ts.record(DataPoint::new(200, 3.));
@@ -243,19 +496,25 @@ mod tests {
             ts.series,
             VecDeque::from(vec![
                 DataPointBucket {
-                    timestamp_millis: 0,
+                    generation: 0,
                     sum: 1.,
                     count: 1,
+                    min: 1.,
+                    max: 1.,
                 },
                 DataPointBucket {
-                    timestamp_millis: 100,
+                    generation: 100,
                     sum: 2.,
                     count: 1,
+                    min: 2.,
+                    max: 2.,
                 },
                 DataPointBucket {
-                    timestamp_millis: 200,
+                    generation: 200,
                     sum: 3.,
                     count: 1,
+                    min: 3.,
+                    max: 3.,
                 },
             ])
         );
@@ -263,7 +522,7 @@ mod tests {
 
     #[test]
     fn time_series_handles_same_bucket_points() {
-        let mut ts = TimeSeries::new();
+        let mut ts = TimeSeries::new(100, 10_000);
         ts.record(DataPoint::new(0, 1.));
         ts.record(DataPoint::new(50, 2.));
         ts.record(DataPoint::new(75, 3.));
@@ -271,21 +530,146 @@ mod tests {
         assert_eq!(
             ts.series,
             VecDeque::from(vec![DataPointBucket {
-                timestamp_millis: 0,
+                generation: 0,
                 sum: 6.,
                 count: 3,
+                min: 1.,
+                max: 3.,
             },])
         );
     }
 
     #[test]
     fn time_series_is_finite() {
-        let mut ts = TimeSeries::new();
+        let mut ts = TimeSeries::new(10, 1_000);
 
-        for idx in 0..(BUCKET_COUNT + 1000) {
-            ts.record(DataPoint::new(idx as i32 * BUCKET_SIZE_MILLISECONDS, 1.));
+        for generation in 0..(1_000 + 10_000) {
+            ts.record(DataPoint::new(generation, 1.));
         }
 
-        assert_eq!(ts.series.len(), BUCKET_COUNT);
+        assert_eq!(ts.series.len(), 100);
+        assert_eq!(ts.window_start(), 10_000);
+    }
+
+    #[test]
+    fn time_series_window_rounds_up_to_whole_buckets() {
+        let ts = TimeSeries::new(30, 100);
+
+        assert_eq!(ts.bucket_count, 4);
+        assert_eq!(ts.window_generations(), 120);
+    }
+
+    #[test]
+    fn legend_describes_the_latest_full_bucket() {
+        let mut series = NamedSeries {
+            label: "cells alive",
+            time_series: TimeSeries::new(5, 100),
+        };
+        assert_eq!(legend_text(&series), "0 cells alive");
+
+        for (generation, value) in [(0, 40.), (1, 45.)] {
+            series.time_series.record(DataPoint::new(generation, value));
+        }
+        assert_eq!(legend_text(&series), "42 cells alive (min 40, max 45)");
+
+        // the bucket that is still being filled only shows up once it is full
+        series.time_series.record(DataPoint::new(5, 50.));
+        assert_eq!(legend_text(&series), "42 cells alive (min 40, max 45)");
+    }
+
+    #[test]
+    fn formats_counts_and_fractions() {
+        assert_eq!(format_value(1234.5), "1234");
+        assert_eq!(format_value(3.), "3");
+        assert_eq!(format_value(2.5), "2.5");
+        assert_eq!(format_value(0.12345), "0.123");
+        assert_eq!(format_value(0.), "0");
+    }
+
+    #[test]
+    fn tick_steps_are_round_numbers() {
+        assert_eq!(tick_step(3), 1);
+        assert_eq!(tick_step(200), 50);
+        assert_eq!(tick_step(1_000), 200);
+        assert_eq!(tick_step(3_000), 500);
+    }
+
+    #[test]
+    fn chart_records_points_by_label() {
+        let mut chart = Chart::new(&["alive", "dying"], 100, 10_000);
+        chart.record("alive", DataPoint::new(0, 5.));
+        chart.record("dying", DataPoint::new(0, 2.));
+        chart.record("alive", DataPoint::new(100, 7.));
+
+        assert_eq!(chart.series[0].time_series.series.len(), 2);
+        assert_eq!(
+            chart.series[0]
+                .time_series
+                .series
+                .back()
+                .map(DataPointBucket::avg),
+            Some(7.)
+        );
+        assert_eq!(
+            chart.series[1]
+                .time_series
+                .latest_bucket()
+                .map(DataPointBucket::avg),
+            Some(2.)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown chart series")]
+    fn chart_rejects_unknown_labels() {
+        let mut chart = Chart::new(&["alive"], 100, 10_000);
+        chart.record("births", DataPoint::new(0, 1.));
+    }
+
+    #[test]
+    fn chart_relabels_series() {
+        let mut chart = Chart::new(&["alive"], 1, 100);
+        chart.toggle_log_scale();
+        chart.record("alive", DataPoint::new(0, 5.));
+
+        chart.set_labels(&["susceptible", "infected"]);
+        chart.record("infected", DataPoint::new(1, 3.));
+
+        assert_eq!(chart.series.len(), 2);
+        assert!(chart.series[0].time_series.latest_bucket().is_none());
+        assert!(chart.log_scale);
+    }
+
+    #[test]
+    fn chart_y_axis_zooms_to_data() {
+        let mut chart = Chart::new(&["alive", "dying"], 1, 100);
+        chart.record("alive", DataPoint::new(0, 500.));
+        chart.record("alive", DataPoint::new(1, 520.));
+        chart.record("dying", DataPoint::new(0, 450.));
+
+        assert_eq!(
+            chart.y_axis(),
+            YAxis {
+                min: 0.,
+                max: 520.,
+                log_scale: false,
+            }
+        );
+
+        chart.toggle_zoom_to_data();
+        assert_eq!(chart.y_axis().min, 450.);
+    }
+
+    #[test]
+    fn y_axis_scales_logarithmically() {
+        let y_axis = YAxis {
+            min: 0.,
+            max: 9999.,
+            log_scale: true,
+        };
+
+        assert_eq!(y_axis.height(0.), 0.);
+        assert_eq!(y_axis.height(99.), CHART_HEIGHT / 2.);
+        assert_eq!(y_axis.height(9999.), CHART_HEIGHT);
     }
 }
@@ -0,0 +1,199 @@
+use std::fmt::{Display, Formatter};
+
+use macroquad::color::Color;
+use macroquad::shapes::draw_rectangle;
+use macroquad::text::draw_text;
+
+use automata::automaton::Simulation;
+use automata::grid::Grid;
+
+use crate::theme::{gradient, Theme};
+
+/// Cells that have been alive for this many generations get the last color of the age palette
+const MAX_COLORED_AGE: u32 = 100;
+const LEGEND_WIDTH: f32 = 200.;
+const LEGEND_HEIGHT: f32 = 10.;
+const LEGEND_FONT_SIZE: f32 = 14.;
+
+/// What the color of each cell on the grid represents
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ColorMode {
+    /// The current state of the cell
+    #[default]
+    State,
+    /// How many generations a live cell has been alive for
+    Age,
+    /// How often the cell has been alive since the grid was reset
+    Heatmap,
+}
+
+impl ColorMode {
+    pub fn next(&self) -> Self {
+        match self {
+            ColorMode::State => ColorMode::Age,
+            ColorMode::Age => ColorMode::Heatmap,
+            ColorMode::Heatmap => ColorMode::State,
+        }
+    }
+}
+
+impl Display for ColorMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorMode::State => write!(f, "State"),
+            ColorMode::Age => write!(f, "Age"),
+            ColorMode::Heatmap => write!(f, "Activity Heatmap"),
+        }
+    }
+}
+
+/// Per-cell statistics accumulated over the generations of a run
+pub struct CellActivity {
+    /// How many consecutive generations each cell has been alive for
+    ages: Grid<u32>,
+    /// How many generations each cell has been alive for in total
+    alive_counts: Grid<u32>,
+    max_alive_count: u32,
+}
+
+impl CellActivity {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            ages: Grid::new(rows, columns, 0),
+            alive_counts: Grid::new(rows, columns, 0),
+            max_alive_count: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.ages.fill(0);
+        self.alive_counts.fill(0);
+        self.max_alive_count = 0;
+    }
+
+    /// Account for the next generation of the simulation. Should be called once per generation.
+    pub fn update(&mut self, simulation: &dyn Simulation) {
+        for r in 0..simulation.rows() {
+            for c in 0..simulation.columns() {
+                if simulation.is_alive(r, c) {
+                    self.ages[r][c] += 1;
+                    self.alive_counts[r][c] += 1;
+                    self.max_alive_count = self.max_alive_count.max(self.alive_counts[r][c]);
+                } else {
+                    self.ages[r][c] = 0;
+                }
+            }
+        }
+    }
+
+    pub fn age(&self, row: usize, column: usize) -> u32 {
+        self.ages[row][column]
+    }
+
+    /// Color of the cell in the given color mode, where `state_color` is the color of its current state
+    pub fn cell_color(
+        &self,
+        mode: ColorMode,
+        theme: &Theme,
+        state_color: Color,
+        alive: bool,
+        row: usize,
+        column: usize,
+    ) -> Color {
+        match mode {
+            ColorMode::Age if alive => {
+                let age = self.ages[row][column].clamp(1, MAX_COLORED_AGE);
+                // most of the interesting changes happen in the first few generations, so use a log scale
+                gradient(
+                    &theme.age_palette,
+                    (age as f32).ln() / (MAX_COLORED_AGE as f32).ln(),
+                )
+            }
+            ColorMode::Heatmap => {
+                if self.max_alive_count == 0 {
+                    return theme.heatmap_palette[0];
+                }
+
+                gradient(
+                    &theme.heatmap_palette,
+                    self.alive_counts[row][column] as f32 / self.max_alive_count as f32,
+                )
+            }
+            _ => state_color,
+        }
+    }
+
+    /// Draw the palette of the color mode as a gradient bar with labels for both ends. x, y is the upper left corner.
+    pub fn draw_legend(&self, mode: ColorMode, theme: &Theme, x: f32, y: f32) {
+        let (palette, low_label, high_label) = match mode {
+            ColorMode::State => return,
+            ColorMode::Age => (
+                &theme.age_palette,
+                "age 1".to_string(),
+                format!("{}+ generations", MAX_COLORED_AGE),
+            ),
+            ColorMode::Heatmap => (
+                &theme.heatmap_palette,
+                "never alive".to_string(),
+                format!("alive {} generations", self.max_alive_count),
+            ),
+        };
+
+        let steps = LEGEND_WIDTH as usize;
+        for step in 0..steps {
+            draw_rectangle(
+                x + step as f32,
+                y,
+                1.,
+                LEGEND_HEIGHT,
+                gradient(palette, step as f32 / (steps - 1) as f32),
+            );
+        }
+
+        let label_y = y + LEGEND_HEIGHT + LEGEND_FONT_SIZE;
+        draw_text(&low_label, x, label_y, LEGEND_FONT_SIZE, theme.chart_border);
+        draw_text(
+            &high_label,
+            x + LEGEND_WIDTH / 2.,
+            label_y,
+            LEGEND_FONT_SIZE,
+            theme.chart_border,
+        );
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use automata::automaton::Runner;
+    use automata::simulations::brain::BriansBrain;
+    use macroquad::color::{WHITE, YELLOW};
+
+    #[test]
+    fn activity_tracks_ages_and_counts() {
+        let mut simulation = Runner::new(BriansBrain, 16, 16);
+        let mut activity = CellActivity::new(16, 16);
+        let theme = Theme::default();
+
+        simulation.set_state(1, 1, 1);
+        activity.update(&simulation);
+        activity.update(&simulation);
+        simulation.set_state(1, 1, 2);
+        simulation.set_state(2, 2, 1);
+        activity.update(&simulation);
+
+        assert_eq!(activity.age(1, 1), 0);
+        assert_eq!(activity.age(2, 2), 1);
+        assert_eq!(activity.alive_counts[1][1], 2);
+        assert_eq!(activity.max_alive_count, 2);
+        assert_eq!(
+            activity.cell_color(ColorMode::Heatmap, &theme, theme.dying, false, 1, 1),
+            WHITE
+        );
+        assert_eq!(
+            activity.cell_color(ColorMode::Age, &theme, theme.alive, true, 2, 2),
+            YELLOW
+        );
+    }
+}
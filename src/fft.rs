@@ -0,0 +1,222 @@
+use std::f32::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// An iterative radix-2 fast Fourier transform of a fixed size, which must be a power of two. The twiddle factors and
+/// the bit reversed order are computed once, so that transforming many rows of the same size is cheap.
+pub struct Fft {
+    size: usize,
+    /// exp(-2πik / size) for k below size / 2
+    twiddles: Vec<Complex>,
+    /// Index that each element is swapped with before the butterflies
+    reversed: Vec<usize>,
+}
+
+impl Fft {
+    pub fn new(size: usize) -> Self {
+        assert!(
+            size.is_power_of_two(),
+            "FFT size must be a power of two, got {}",
+            size
+        );
+
+        let bits = size.trailing_zeros();
+        Self {
+            size,
+            twiddles: (0..size / 2)
+                .map(|k| {
+                    let angle = -2. * PI * k as f32 / size as f32;
+                    Complex::new(angle.cos(), angle.sin())
+                })
+                .collect(),
+            reversed: (0..size)
+                .map(|idx| match bits {
+                    0 => 0,
+                    bits => idx.reverse_bits() >> (usize::BITS - bits),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Transform the data in place. The inverse transform is scaled by 1 / size, so that it undoes the forward one.
+    pub fn transform(&self, data: &mut [Complex], inverse: bool) {
+        assert_eq!(data.len(), self.size, "Expected {} values", self.size);
+
+        for (idx, &reversed) in self.reversed.iter().enumerate() {
+            if idx < reversed {
+                data.swap(idx, reversed);
+            }
+        }
+
+        let mut half = 1;
+        while half < self.size {
+            // twiddles for this level are every stride-th one of the full table
+            let stride = self.size / (half * 2);
+            for block in data.chunks_exact_mut(half * 2) {
+                let (low, high) = block.split_at_mut(half);
+                for (k, (a, b)) in low.iter_mut().zip(high.iter_mut()).enumerate() {
+                    let twiddle = self.twiddles[k * stride];
+                    let twiddle = if inverse { twiddle.conj() } else { twiddle };
+
+                    let product = *b * twiddle;
+                    (*a, *b) = (*a + product, *a - product);
+                }
+            }
+            half *= 2;
+        }
+
+        if inverse {
+            let scale = 1. / self.size as f32;
+            for value in data.iter_mut() {
+                *value = Complex::new(value.re * scale, value.im * scale);
+            }
+        }
+    }
+}
+
+/// A two-dimensional FFT over a grid stored row by row: every row is transformed, then every column
+pub struct Fft2d {
+    rows: Fft,
+    columns: Fft,
+}
+
+impl Fft2d {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            rows: Fft::new(rows),
+            columns: Fft::new(columns),
+        }
+    }
+
+    pub fn transform(&self, data: &mut [Complex], inverse: bool) {
+        let (rows, columns) = (self.rows.size(), self.columns.size());
+        assert_eq!(
+            data.len(),
+            rows * columns,
+            "Expected {}x{} values",
+            rows,
+            columns
+        );
+
+        for row in data.chunks_exact_mut(columns) {
+            self.columns.transform(row, inverse);
+        }
+
+        let mut column_values = vec![Complex::default(); rows];
+        for column in 0..columns {
+            for (row, value) in column_values.iter_mut().enumerate() {
+                *value = data[row * columns + column];
+            }
+            self.rows.transform(&mut column_values, inverse);
+            for (row, value) in column_values.iter().enumerate() {
+                data[row * columns + column] = *value;
+            }
+        }
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[Complex], expected: &[Complex]) {
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!(
+                (actual.re - expected.re).abs() < 1e-4 && (actual.im - expected.im).abs() < 1e-4,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn matches_the_discrete_fourier_transform() {
+        let input: Vec<Complex> = (0..8)
+            .map(|idx| Complex::new(idx as f32, (idx % 3) as f32))
+            .collect();
+
+        // straight from the definition
+        let expected: Vec<Complex> = (0..8)
+            .map(|k| {
+                input
+                    .iter()
+                    .enumerate()
+                    .fold(Complex::default(), |sum, (n, &value)| {
+                        let angle = -2. * PI * (k * n) as f32 / 8.;
+                        sum + value * Complex::new(angle.cos(), angle.sin())
+                    })
+            })
+            .collect();
+
+        let fft = Fft::new(8);
+        let mut data = input.clone();
+        fft.transform(&mut data, false);
+        assert_close(&data, &expected);
+
+        fft.transform(&mut data, true);
+        assert_close(&data, &input);
+    }
+
+    #[test]
+    fn transforms_grids() {
+        let fft = Fft2d::new(4, 8);
+        let mut data = vec![Complex::default(); 32];
+        data[0] = Complex::new(1., 0.);
+
+        // an impulse has a flat spectrum
+        fft.transform(&mut data, false);
+        assert_close(&data, &[Complex::new(1., 0.); 32]);
+
+        fft.transform(&mut data, true);
+        assert_eq!(data[0], Complex::new(1., 0.));
+        assert_close(&data[1..], &[Complex::default(); 31]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::ops::{Index, IndexMut};
+use std::slice::{Chunks, ChunksMut};
+
+/// A rectangular grid of cells stored row by row. Indexing with a row number returns the row as a slice, so cells
+/// can be accessed with `grid[row][column]`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Grid<T> {
+    rows: usize,
+    columns: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Copy> Grid<T> {
+    /// Create a grid with every cell set to the given value
+    pub fn new(rows: usize, columns: usize, value: T) -> Self {
+        assert!(
+            rows > 0 && columns > 0,
+            "Grid must have at least one row and column"
+        );
+
+        Self {
+            rows,
+            columns,
+            cells: vec![value; rows * columns],
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+
+    /// Move every row up by one, dropping the top row, and fill the bottom row with the given value
+    pub fn scroll_up(&mut self, value: T) {
+        self.cells.rotate_left(self.columns);
+        let bottom = self.rows - 1;
+        self[bottom].fill(value);
+    }
+
+    pub fn iter_rows(&self) -> Chunks<'_, T> {
+        self.cells.chunks(self.columns)
+    }
+
+    pub fn iter_rows_mut(&mut self) -> ChunksMut<'_, T> {
+        self.cells.chunks_mut(self.columns)
+    }
+}
+
+impl<T> Index<usize> for Grid<T> {
+    type Output = [T];
+
+    fn index(&self, row: usize) -> &[T] {
+        &self.cells[row * self.columns..(row + 1) * self.columns]
+    }
+}
+
+impl<T> IndexMut<usize> for Grid<T> {
+    fn index_mut(&mut self, row: usize) -> &mut [T] {
+        &mut self.cells[row * self.columns..(row + 1) * self.columns]
+    }
+}
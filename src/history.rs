@@ -0,0 +1,249 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Once a run has this many rows, every other row is dropped and the downsampling stride is doubled
+const MAX_HISTORY_ROWS: usize = 100_000;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct HistoryRow {
+    pub generation: u64,
+    pub timestamp_millis: u64,
+    /// One value per label
+    pub values: Vec<f32>,
+}
+
+/// The full history of the values recorded for a run, optionally downsampled to every Nth generation. Unlike
+/// the time series in the chart, nothing is averaged away, so the history can be exported for later analysis.
+pub struct History {
+    labels: Vec<&'static str>,
+    rows: Vec<HistoryRow>,
+    stride: u64,
+    initial_stride: u64,
+}
+
+impl History {
+    /// Create a history with one column per label that keeps every `stride`th generation
+    pub fn new(labels: &[&'static str], stride: u64) -> Self {
+        assert!(stride > 0, "History stride must be at least 1 generation");
+
+        Self {
+            labels: labels.to_vec(),
+            rows: Vec::new(),
+            stride,
+            initial_stride: stride,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.rows.clear();
+        self.stride = self.initial_stride;
+    }
+
+    /// Record the values of every series for a generation, in the same order as the labels
+    pub fn record(&mut self, generation: u64, timestamp_millis: u64, values: &[f32]) {
+        assert_eq!(
+            values.len(),
+            self.labels.len(),
+            "Expected one value per history column"
+        );
+
+        if !generation.is_multiple_of(self.stride) {
+            return;
+        }
+
+        self.rows.push(HistoryRow {
+            generation,
+            timestamp_millis,
+            values: values.to_vec(),
+        });
+
+        // keep memory bounded on very long runs
+        if self.rows.len() >= MAX_HISTORY_ROWS {
+            self.stride *= 2;
+            let stride = self.stride;
+            self.rows
+                .retain(|row| row.generation.is_multiple_of(stride));
+        }
+    }
+
+    pub fn rows(&self) -> &[HistoryRow] {
+        &self.rows
+    }
+
+    /// Current downsampling stride, which grows on long runs
+    pub fn stride(&self) -> u64 {
+        self.stride
+    }
+
+    /// Replace the recorded rows, e.g. with ones from a saved session
+    pub fn restore(&mut self, stride: u64, rows: Vec<HistoryRow>) -> Result<(), String> {
+        if stride == 0 {
+            return Err("history stride must be at least 1 generation".to_string());
+        }
+
+        if let Some(row) = rows
+            .iter()
+            .find(|row| row.values.len() != self.labels.len())
+        {
+            return Err(format!(
+                "history row for generation {} has {} values, expected {}",
+                row.generation,
+                row.values.len(),
+                self.labels.len()
+            ));
+        }
+
+        self.stride = stride;
+        self.rows = rows;
+
+        Ok(())
+    }
+
+    pub fn to_csv(&self, rule: &str, seed: u64) -> String {
+        let mut csv = String::from("generation,timestamp_millis,rule,seed");
+        for label in self.labels.iter() {
+            csv.push(',');
+            csv.push_str(&escape_csv(label));
+        }
+        csv.push('\n');
+
+        let rule = escape_csv(rule);
+        for row in self.rows.iter() {
+            let _ = write!(
+                csv,
+                "{},{},{},{}",
+                row.generation, row.timestamp_millis, rule, seed
+            );
+            for value in row.values.iter() {
+                let _ = write!(csv, ",{}", value);
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// Formats the history as a JSON array with one object per row, using the same fields as the CSV columns
+    pub fn to_json(&self, rule: &str, seed: u64) -> String {
+        let rule = escape_json(rule);
+        let labels: Vec<String> = self.labels.iter().map(|label| escape_json(label)).collect();
+
+        let mut json = String::from("[");
+        for (idx, row) in self.rows.iter().enumerate() {
+            if idx > 0 {
+                json.push(',');
+            }
+
+            let _ = write!(
+                json,
+                "\n  {{\"generation\": {}, \"timestamp_millis\": {}, \"rule\": {}, \"seed\": {}",
+                row.generation, row.timestamp_millis, rule, seed
+            );
+            for (label, value) in labels.iter().zip(row.values.iter()) {
+                let _ = write!(json, ", {}: {}", label, value);
+            }
+            json.push('}');
+        }
+        json.push_str("\n]\n");
+
+        json
+    }
+
+    /// Write the history to `<path>.csv` and `<path>.json`, returning the paths written
+    pub fn export(&self, path: &Path, rule: &str, seed: u64) -> io::Result<[PathBuf; 2]> {
+        let csv_path = path.with_extension("csv");
+        let json_path = path.with_extension("json");
+
+        fs::write(&csv_path, self.to_csv(rule, seed))?;
+        fs::write(&json_path, self.to_json(rule, seed))?;
+
+        Ok([csv_path, json_path])
+    }
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Quote a string for use in JSON
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            character if character.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", character as u32);
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped.push('"');
+
+    escaped
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_exports_csv() {
+        let mut history = History::new(&["cells alive", "births"], 1);
+        history.record(1, 16, &[10., 3.]);
+        history.record(2, 33, &[12., 2.5]);
+
+        assert_eq!(
+            history.to_csv("B3/S23", 42),
+            "generation,timestamp_millis,rule,seed,cells alive,births\n\
+             1,16,B3/S23,42,10,3\n\
+             2,33,B3/S23,42,12,2.5\n"
+        );
+    }
+
+    #[test]
+    fn history_exports_json() {
+        let mut history = History::new(&["cells alive"], 1);
+        history.record(1, 16, &[10.]);
+
+        assert_eq!(
+            history.to_json("B3/S23", 42),
+            "[\n  {\"generation\": 1, \"timestamp_millis\": 16, \"rule\": \"B3/S23\", \"seed\": 42, \
+             \"cells alive\": 10}\n]\n"
+        );
+    }
+
+    #[test]
+    fn history_is_downsampled() {
+        let mut history = History::new(&["cells alive"], 10);
+        for generation in 0..100 {
+            history.record(generation, 0, &[1.]);
+        }
+
+        assert_eq!(history.rows.len(), 10);
+        assert!(history.rows.iter().all(|row| row.generation % 10 == 0));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = History::new(&["cells alive"], 1);
+        for generation in 0..(MAX_HISTORY_ROWS as u64 * 3) {
+            history.record(generation, 0, &[1.]);
+        }
+
+        assert!(history.rows.len() < MAX_HISTORY_ROWS);
+        assert_eq!(history.stride, 4);
+        assert_eq!(
+            history.rows.last().map(|row| row.generation),
+            Some(MAX_HISTORY_ROWS as u64 * 3 - 4)
+        );
+    }
+}
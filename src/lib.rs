@@ -0,0 +1,47 @@
+//! The simulation engine: the grid, the rules and how to step them, pattern and session files, and analysis of
+//! runs. Nothing in here depends on macroquad, which is only used by the visualizer in the `automata` binary.
+
+use crate::grid::Grid;
+
+pub mod analysis;
+pub mod automaton;
+pub mod fft;
+pub mod grid;
+pub mod history;
+pub mod neighborhood;
+pub mod patterns;
+pub mod registry;
+pub mod session;
+pub mod simulations;
+pub mod util;
+
+/// Cell states of the built-in Life-like rules
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CellState {
+    Alive,
+    Dying,
+    Dead,
+}
+
+impl From<u8> for CellState {
+    /// States are numbered like in Golly's Generations rules: 0 is dead, 1 alive and 2 dying
+    fn from(state: u8) -> Self {
+        match state {
+            1 => CellState::Alive,
+            2 => CellState::Dying,
+            _ => CellState::Dead,
+        }
+    }
+}
+
+impl From<CellState> for u8 {
+    fn from(state: CellState) -> Self {
+        match state {
+            CellState::Dead => 0,
+            CellState::Alive => 1,
+            CellState::Dying => 2,
+        }
+    }
+}
+
+pub type SimulationState = Grid<CellState>;
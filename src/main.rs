@@ -1,292 +1,1051 @@
+use macroquad::miniquad::date;
 use macroquad::prelude::*;
-use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
 
-use crate::charts::{DataPoint, TimeSeries};
-use crate::simulations::brain::get_brain_next_cell_state;
-use crate::simulations::conway::get_conway_next_cell_state;
-use crate::simulations::highlife::get_highlife_next_cell_state;
-use crate::simulations::seeds::get_seeds_next_cell_state;
+use automata::analysis::ShipTracker;
+use automata::automaton::{Parameter, Simulation};
+use automata::history::History;
+use automata::patterns::Pattern;
+use automata::registry::Registry;
+use automata::session::{Camera, Session};
+use automata::util::Rng;
+
+use crate::charts::{Chart, DataPoint};
+use crate::coloring::{CellActivity, ColorMode};
+use crate::settings::{Settings, USAGE};
+use crate::theme::Theme;
+use crate::viewport::Viewport;
 
 mod charts;
-mod simulations;
-mod util;
+mod coloring;
+mod settings;
+mod theme;
+mod viewport;
 
-const ROWS: usize = 256;
-const COLUMNS: usize = 256;
-const FONT_SIZE: f32 = 24.;
 const TEXT_PADDING: f32 = 25.;
-const FONT_COLOR: Color = WHITE;
-const INSTRUCTIONS: [&str; 9] = [
-    "Controls:",
-    "R -> Clear",
-    "A -> Randomize",
-    "B -> Brian's Brain",
-    "C -> Conway's Game of Life",
-    "H -> HighLife",
-    "S -> Seeds",
-    "LMB -> Spawn Live Cells",
+const CHART_ALIVE_LABEL: &str = "cells alive";
+const CHART_DYING_LABEL: &str = "cells dying";
+const CHART_BIRTHS_LABEL: &str = "births";
+const CHART_DEATHS_LABEL: &str = "deaths";
+const SERIES_LABELS: [&str; 4] = [
+    CHART_ALIVE_LABEL,
+    CHART_DYING_LABEL,
+    CHART_BIRTHS_LABEL,
+    CHART_DEATHS_LABEL,
+];
+// record every generation in the exported history
+const HISTORY_STRIDE: u64 = 1;
+const SHIP_FONT_SIZE: f32 = 16.;
+// neighbor counts are only drawn once cells are big enough to fit them
+const NEIGHBOR_COUNT_MIN_CELL_SIZE: f32 = 12.;
+// zoom factor per mouse wheel notch
+const ZOOM_STEP: f32 = 1.25;
+// how far the arrow keys pan the grid per frame, in pixels
+const PAN_STEP: f32 = 10.;
+// levels that Tab cycles the brush through for continuous rules, from full to empty
+const CONTINUOUS_BRUSH_STATES: [u8; 5] = [255, 191, 127, 63, 0];
+// continuous rules look at kernels many cells wide, so they are painted with a disc of this radius
+const CONTINUOUS_BRUSH_RADIUS: isize = 4;
+// factor that - and = change the selected parameter by, and the significant digits it is rounded to. Whole number
+// parameters go up and down by one instead.
+const PARAMETER_STEP: f64 = 1.25;
+const PARAMETER_DIGITS: i32 = 2;
+// what - and = raise a parameter at 0 to, and lower a parameter below it to 0
+const PARAMETER_MIN_VALUE: f64 = 0.0001;
+// the keys that select a mode are listed after these, followed by the rest of the controls
+const MODE_INSTRUCTIONS: [&str; 3] = ["Controls:", "R -> Clear", "A -> Randomize"];
+const INSTRUCTIONS: [&str; 18] = [
+    "[ / ] -> Previous / Next Mode",
+    "D -> Toggle Ship Detection",
+    "E -> Export History",
+    "F5 / F9 -> Save / Load Session",
+    "L -> Toggle Chart Log Scale",
+    "Z -> Toggle Chart Zoom",
+    "N -> Toggle Neighbor Counts",
+    "M -> Cycle Color Mode",
+    "T -> Cycle Theme",
+    "Space -> Pause",
+    "LMB -> Paint Cells",
+    "Tab -> Cycle Brush State",
+    "O / P -> Next Preset / Place Preset",
+    "K -> Add Ant",
+    "G -> Next Parameter",
+    "- / = -> Lower / Raise Parameter",
+    "Wheel / RMB / Arrows -> Zoom / Pan",
     "ESC -> Quit",
 ];
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-enum CellState {
-    Alive,
-    Dying,
-    Dead,
+/// Controls to show on screen, with a line for every mode in the registry that has a shortcut
+fn instructions(registry: &Registry) -> Vec<String> {
+    let modes = registry.modes().iter().filter_map(|mode| {
+        mode.shortcut
+            .map(|shortcut| format!("{} -> {}", shortcut, mode.name))
+    });
+
+    MODE_INSTRUCTIONS
+        .iter()
+        .map(|line| line.to_string())
+        .chain(modes)
+        .chain(INSTRUCTIONS.iter().map(|line| line.to_string()))
+        .collect()
 }
 
-impl CellState {
-    fn color(&self) -> Color {
-        match self {
-            CellState::Alive => LIME,
-            CellState::Dying => LIGHTGRAY,
-            CellState::Dead => BLACK,
-        }
-    }
+/// The key for a mode shortcut, if macroquad has one for it
+fn shortcut_key(shortcut: char) -> Option<KeyCode> {
+    let key = match shortcut.to_ascii_uppercase() {
+        'A' => KeyCode::A,
+        'B' => KeyCode::B,
+        'C' => KeyCode::C,
+        'D' => KeyCode::D,
+        'E' => KeyCode::E,
+        'F' => KeyCode::F,
+        'G' => KeyCode::G,
+        'H' => KeyCode::H,
+        'I' => KeyCode::I,
+        'J' => KeyCode::J,
+        'K' => KeyCode::K,
+        'L' => KeyCode::L,
+        'M' => KeyCode::M,
+        'N' => KeyCode::N,
+        'O' => KeyCode::O,
+        'P' => KeyCode::P,
+        'Q' => KeyCode::Q,
+        'R' => KeyCode::R,
+        'S' => KeyCode::S,
+        'T' => KeyCode::T,
+        'U' => KeyCode::U,
+        'V' => KeyCode::V,
+        'W' => KeyCode::W,
+        'X' => KeyCode::X,
+        'Y' => KeyCode::Y,
+        'Z' => KeyCode::Z,
+        '0' => KeyCode::Key0,
+        '1' => KeyCode::Key1,
+        '2' => KeyCode::Key2,
+        '3' => KeyCode::Key3,
+        '4' => KeyCode::Key4,
+        '5' => KeyCode::Key5,
+        '6' => KeyCode::Key6,
+        '7' => KeyCode::Key7,
+        '8' => KeyCode::Key8,
+        '9' => KeyCode::Key9,
+        _ => return None,
+    };
+
+    Some(key)
 }
 
-#[derive(Default)]
-enum SimulationMode {
-    #[default]
-    ConwaysLife,
-    BriansBrain,
-    HighLife,
-    Seeds,
+/// Labels of the values recorded for the simulation: its own measurements if it has any, such as the cells in each
+/// compartment of a population model, and [SERIES_LABELS] for everything else
+fn series_labels(simulation: &dyn Simulation) -> Vec<&'static str> {
+    let measurements = simulation.measurements();
+
+    if measurements.is_empty() {
+        SERIES_LABELS.to_vec()
+    } else {
+        measurements.iter().map(|&(label, _)| label).collect()
+    }
 }
 
-impl SimulationMode {
-    fn cell_state_fn(&self) -> CellStateGenerator {
-        match self {
-            SimulationMode::BriansBrain => get_brain_next_cell_state,
-            SimulationMode::ConwaysLife => get_conway_next_cell_state,
-            SimulationMode::HighLife => get_highlife_next_cell_state,
-            SimulationMode::Seeds => get_seeds_next_cell_state,
-        }
+/// States that Tab cycles the brush through: every state but the empty one, then the empty one to erase with
+fn brush_states(simulation: &dyn Simulation) -> Vec<u8> {
+    if simulation.is_continuous() {
+        CONTINUOUS_BRUSH_STATES.to_vec()
+    } else {
+        (1..simulation.state_count())
+            .chain([0])
+            .map(|state| state as u8)
+            .collect()
     }
 }
 
-impl Display for SimulationMode {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            SimulationMode::ConwaysLife => write!(f, "Conway's Game of Life"),
-            SimulationMode::BriansBrain => write!(f, "Brian's Brain"),
-            SimulationMode::HighLife => write!(f, "HighLife"),
-            SimulationMode::Seeds => write!(f, "Seeds"),
-        }
+/// The value a parameter goes to when it is raised or lowered, rounded so that it stays readable in the rulestring
+fn step_parameter(parameter: &Parameter, raise: bool) -> f64 {
+    let value = match (parameter.integer, raise) {
+        (true, true) => return parameter.value + 1.,
+        (true, false) => return (parameter.value - 1.).max(0.),
+        (false, true) => parameter.value * PARAMETER_STEP,
+        (false, false) => parameter.value / PARAMETER_STEP,
+    };
+    if value < PARAMETER_MIN_VALUE {
+        return if raise { PARAMETER_MIN_VALUE } else { 0. };
     }
+
+    let scale = 10_f64.powi(PARAMETER_DIGITS - 1 - value.log10().floor() as i32);
+    (value * scale).round() / scale
 }
 
-type SimulationState = [[CellState; COLUMNS]; ROWS];
+/// Start the random number generator over from a seed drawn from it, so that the seed exported with the history of
+/// the next run reproduces its soup and its chances
+fn reseed(rng: &mut Rng) -> u64 {
+    let seed = rng.next_u64();
+    *rng = Rng::new(seed);
 
-type CellStateGenerator = fn(&SimulationState, usize, usize) -> CellState;
+    seed
+}
 
-/// Given the starting simulation state, update each cell in the buffer using the supplied update func
-fn get_next_state(
-    state: &SimulationState,
-    buffer: &mut SimulationState,
-    update_func: CellStateGenerator,
-) {
-    for r in 0..state.len() {
-        for c in 0..state[r].len() {
-            buffer[r][c] = update_func(state, r, c);
+/// Everything recorded about the current run. Cleared whenever the grid is reset.
+struct RunStats {
+    /// What the chart and the history have a series for
+    labels: Vec<&'static str>,
+    generation: u64,
+    started_at_secs: f64,
+    chart: Chart,
+    history: History,
+    ship_tracker: ShipTracker,
+    activity: CellActivity,
+}
+
+impl RunStats {
+    fn new(settings: &Settings, labels: Vec<&'static str>) -> Self {
+        Self {
+            generation: 0,
+            started_at_secs: date::now(),
+            chart: Chart::new(&labels, settings.chart_bucket, settings.chart_window),
+            history: History::new(&labels, HISTORY_STRIDE),
+            labels,
+            ship_tracker: ShipTracker::new(),
+            activity: CellActivity::new(settings.rows, settings.columns),
         }
     }
+
+    fn reset(&mut self) {
+        self.generation = 0;
+        self.started_at_secs = date::now();
+        self.chart.reset();
+        self.history.reset();
+        self.ship_tracker.reset();
+        self.activity.reset();
+    }
+
+    /// Start recording other values, e.g. after switching to a mode with measurements of its own
+    fn relabel(&mut self, labels: Vec<&'static str>) {
+        self.chart.set_labels(&labels);
+        self.history = History::new(&labels, HISTORY_STRIDE);
+        self.labels = labels;
+    }
 }
 
-fn get_clean_state() -> (SimulationState, SimulationState) {
-    let state = [[CellState::Dead; COLUMNS]; ROWS];
-    let buffer = [[CellState::Dead; COLUMNS]; ROWS];
-    assert_eq!(
-        std::mem::size_of_val(&state),
-        std::mem::size_of_val(&buffer),
-        "State and buffer matrices are not of the same size"
-    );
+fn reset_sim_state(simulation: &mut dyn Simulation, stats: &mut RunStats) {
+    stats.reset();
+    simulation.clear();
+}
 
-    (state, buffer)
+/// Replace the grid with a random soup, where `density` is the probability of a cell not being empty
+fn randomize_sim_state(
+    simulation: &mut dyn Simulation,
+    stats: &mut RunStats,
+    rng: &mut Rng,
+    density: f64,
+) {
+    stats.reset();
+    simulation.randomize(rng, density);
 }
 
-fn reset_sim_state(
-    state: &mut SimulationState,
-    buffer: &mut SimulationState,
-    time_series: &mut TimeSeries,
+/// Start over from a random soup, or from an empty grid for rules where soups are meaningless
+fn restart_sim_state(
+    simulation: &mut dyn Simulation,
+    stats: &mut RunStats,
+    rng: &mut Rng,
+    density: f64,
 ) {
-    time_series.reset();
-    (*state, *buffer) = get_clean_state()
+    if simulation.starts_empty() {
+        reset_sim_state(simulation, stats);
+    } else {
+        randomize_sim_state(simulation, stats, rng, density);
+    }
 }
 
-/// Randomly sets cells in the starting state to [CellState::Alive]
-fn randomize_sim_state(
-    state: &mut SimulationState,
-    buffer: &mut SimulationState,
-    time_series: &mut TimeSeries,
+/// Switch to the mode at the given index in the registry, on a grid of the same size, and remember it as the
+/// current mode
+fn select_sim_mode(
+    registry: &Registry,
+    mode_idx: usize,
+    current_mode: &mut Option<usize>,
+    simulation: &mut Box<dyn Simulation>,
+    stats: &mut RunStats,
+    rng: &mut Rng,
+    density: f64,
 ) {
-    reset_sim_state(state, buffer, time_series);
-    for r in 0..state.len() {
-        for c in 0..state.len() {
-            if rand::gen_range(0, 10) == 0 {
-                state[r][c] = CellState::Alive;
+    *simulation = registry.create(mode_idx, simulation.rows(), simulation.columns());
+    *current_mode = Some(mode_idx);
+    restart_sim_state(simulation.as_mut(), stats, rng, density);
+}
+
+/// Advance the simulation by one generation and record how many cells are in each state, and how many changed
+/// state, in the chart and the history. Simulations with measurements of their own record those instead.
+fn step_sim_state(simulation: &mut dyn Simulation, stats: &mut RunStats, rng: &mut Rng) {
+    let counts = simulation.step(rng);
+
+    stats.generation += 1;
+    stats.activity.update(simulation);
+
+    let measurements = simulation.measurements();
+    let values: Vec<f32> = if measurements.is_empty() {
+        // same order as SERIES_LABELS
+        vec![
+            counts.alive as f32,
+            counts.dying as f32,
+            counts.births as f32,
+            counts.deaths as f32,
+        ]
+    } else {
+        measurements.iter().map(|&(_, value)| value).collect()
+    };
+
+    // the mode may have changed since the last generation
+    let labels = series_labels(simulation);
+    if labels != stats.labels {
+        stats.relabel(labels);
+    }
+
+    for (label, &value) in stats.labels.iter().zip(values.iter()) {
+        stats
+            .chart
+            .record(label, DataPoint::new(stats.generation, value));
+    }
+
+    let timestamp_millis = ((date::now() - stats.started_at_secs) * 1000.) as u64;
+    stats
+        .history
+        .record(stats.generation, timestamp_millis, &values);
+}
+
+/// The simulation to start with, and the pattern to start from if one was given. A rule given in the settings takes
+/// precedence over the one the pattern was made for.
+fn initial_simulation_and_pattern(
+    registry: &Registry,
+    settings: &Settings,
+) -> Result<(Box<dyn Simulation>, Option<Pattern>), String> {
+    let pattern = match &settings.pattern {
+        Some(path) => {
+            let pattern = Pattern::from_file(path)?;
+            pattern.check_fits(settings.rows, settings.columns)?;
+            Some(pattern)
+        }
+        None => None,
+    };
+
+    let rulestring = settings
+        .rule
+        .as_deref()
+        .or_else(|| pattern.as_ref().and_then(|pattern| pattern.rule.as_deref()));
+    let simulation = match rulestring {
+        Some(rulestring) => registry.parse(rulestring, settings.rows, settings.columns)?,
+        None => registry.create(0, settings.rows, settings.columns),
+    };
+
+    Ok((simulation, pattern))
+}
+
+/// Start a new run from the pattern, or from scratch if there is none
+fn start_sim_state(
+    simulation: &mut dyn Simulation,
+    stats: &mut RunStats,
+    rng: &mut Rng,
+    pattern: Option<&Pattern>,
+    density: f64,
+) -> Result<(), String> {
+    match pattern {
+        Some(pattern) => {
+            reset_sim_state(simulation, stats);
+            pattern.place(simulation)
+        }
+        None => {
+            restart_sim_state(simulation, stats, rng, density);
+            Ok(())
+        }
+    }
+}
+
+/// Everything that makes up a run in progress
+struct Run {
+    simulation: Box<dyn Simulation>,
+    stats: RunStats,
+    /// Seed the run was started with, so that it can be reproduced
+    seed: u64,
+    rng: Rng,
+    /// Where the camera was when the run was saved
+    camera: Option<Camera>,
+}
+
+impl Run {
+    /// Start a new run as described by the settings, or resume the saved session if asked to
+    fn start(registry: &Registry, settings: &mut Settings) -> Result<Self, String> {
+        if settings.resume_session {
+            let session = Session::load(&settings.session_path)?;
+            return Run::resume(registry, session, settings);
+        }
+
+        let seed = settings
+            .seed
+            .unwrap_or_else(|| (date::now() * 1000.) as u64);
+        let mut rng = Rng::new(seed);
+        let (mut simulation, pattern) = initial_simulation_and_pattern(registry, settings)?;
+
+        let mut stats = RunStats::new(settings, series_labels(simulation.as_ref()));
+        start_sim_state(
+            simulation.as_mut(),
+            &mut stats,
+            &mut rng,
+            pattern.as_ref(),
+            settings.density,
+        )?;
+
+        Ok(Self {
+            simulation,
+            stats,
+            seed,
+            rng,
+            camera: None,
+        })
+    }
+
+    /// Pick up a saved session where it was left off. The grid size of the session replaces the one in the settings,
+    /// but only once the whole session has been restored, so that a session that fails to load changes nothing.
+    fn resume(
+        registry: &Registry,
+        session: Session,
+        settings: &mut Settings,
+    ) -> Result<Self, String> {
+        let (rows, columns) = (session.grid.rows, session.grid.columns);
+        let mut simulation = registry.parse(&session.rule, rows, columns)?;
+        session.restore(simulation.as_mut())?;
+
+        let resumed = Settings {
+            rows,
+            columns,
+            ..settings.clone()
+        };
+        let mut stats = RunStats::new(&resumed, series_labels(simulation.as_ref()));
+        stats.generation = session.generation;
+        stats.started_at_secs = date::now() - session.elapsed_millis as f64 / 1000.;
+        // the chart only shows recent generations, so it can be rebuilt from the history
+        for row in session.history.iter() {
+            for (label, value) in stats.labels.iter().zip(row.values.iter()) {
+                stats
+                    .chart
+                    .record(label, DataPoint::new(row.generation, *value));
             }
         }
+        stats
+            .history
+            .restore(session.history_stride, session.history)?;
+        *settings = resumed;
+
+        Ok(Self {
+            simulation,
+            stats,
+            seed: session.seed,
+            rng: session.rng,
+            camera: Some(session.camera),
+        })
     }
 }
 
-fn select_sim_mode(
-    state: &mut SimulationState,
-    buffer: &mut SimulationState,
-    time_series: &mut TimeSeries,
-    old_mode: &mut SimulationMode,
-    new_mode: SimulationMode,
-) {
-    randomize_sim_state(state, buffer, time_series);
-    *old_mode = new_mode;
+/// Run the simulation for a fixed number of generations and export its history
+fn run_headless(mut settings: Settings) -> Result<(), String> {
+    let registry = Registry::with_builtins();
+    let Run {
+        mut simulation,
+        mut stats,
+        seed,
+        mut rng,
+        ..
+    } = Run::start(&registry, &mut settings)?;
+
+    for _ in 0..settings.generations {
+        step_sim_state(simulation.as_mut(), &mut stats, &mut rng);
+    }
+
+    let paths = stats
+        .history
+        .export(&settings.export_path, &simulation.rulestring(), seed)
+        .map_err(|err| format!("Failed to export history: {}", err))?;
+    for path in paths {
+        println!("Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Themes to cycle through in the visualizer. A custom theme from the settings is selected first.
+fn load_themes(settings: &Settings) -> Result<Vec<Theme>, String> {
+    let mut themes = Theme::presets();
+
+    if let Some(path) = &settings.theme {
+        themes.insert(0, Theme::from_file(path)?);
+    }
+
+    Ok(themes)
+}
+
+fn exit_with_error(err: String) -> ! {
+    eprintln!("{}", err);
+    std::process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut settings = Settings::from_args(&args).unwrap_or_else(|err| exit_with_error(err));
+
+    if settings.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if settings.headless {
+        if let Err(err) = run_headless(settings) {
+            exit_with_error(err);
+        }
+        return;
+    }
+
+    // report invalid rules, patterns, sessions and themes before opening the window
+    let themes = load_themes(&settings).unwrap_or_else(|err| exit_with_error(err));
+    let registry = Registry::with_builtins();
+    let run = Run::start(&registry, &mut settings).unwrap_or_else(|err| exit_with_error(err));
+
+    macroquad::Window::new("Automata", run_visualizer(settings, themes, registry, run));
 }
 
-#[macroquad::main("Automata")]
-async fn main() {
+async fn run_visualizer(mut settings: Settings, themes: Vec<Theme>, registry: Registry, run: Run) {
     // set window size
-    request_new_screen_size(1024., 1024.);
+    request_new_screen_size(settings.window_width, settings.window_height);
     next_frame().await;
 
-    // create initial state and a buffer to hold updated state between frames
-    let (mut state, mut buffer) = get_clean_state();
+    let Run {
+        mut simulation,
+        mut stats,
+        mut seed,
+        mut rng,
+        camera,
+    } = run;
 
-    let mut simulation_mode = SimulationMode::ConwaysLife;
+    // index of the mode that is running, if any. Its rulestring stops matching the mode once a parameter is changed.
+    let mut current_mode = registry.find(&simulation.rulestring());
 
-    let cell_width: f32 = screen_width() / COLUMNS as f32;
+    let mut viewport = Viewport::new(
+        screen_width(),
+        screen_height(),
+        settings.rows,
+        settings.columns,
+    );
+    if let Some(camera) = camera {
+        viewport.set_camera(camera);
+    }
+    let mut last_mouse_position = mouse_position();
+    let mut neighbor_counts_enabled = false;
+    let mut color_mode = ColorMode::default();
+    let mut theme_idx = 0;
+    let instructions = instructions(&registry);
+    let mode_keys: Vec<(usize, KeyCode)> = registry
+        .modes()
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, mode)| Some((idx, shortcut_key(mode.shortcut?)?)))
+        .collect();
 
-    let mut time_series = TimeSeries::new();
-    let mut timestamp_secs = 0.;
+    let mut paused = false;
+    // state that the left mouse button paints, and the preset of the current mode that P places
+    let mut brush: u8 = 1;
+    let mut preset_idx = 0;
+    // parameter of the simulation that - and = change
+    let mut parameter_idx = 0;
+    let mut ship_detection_enabled = true;
+    // result of the last action worth reporting, such as an export
+    let mut status_text = String::new();
 
     // main simulation loop
     loop {
+        let theme = &themes[theme_idx];
+        clear_background(theme.background);
+
         // exit (if not wasm)
         if is_key_pressed(KeyCode::Escape) && !cfg!(target_arch = "wasm32") {
             break;
         }
 
-        // spawn live cells on mouse click
-        if is_mouse_button_down(MouseButton::Left) {
-            let (x, y) = mouse_position();
-            let row = (y / cell_width) as usize;
-            let column = (x / cell_width) as usize;
+        let (mouse_x, mouse_y) = mouse_position();
+        let hovered_cell = viewport.cell_at(mouse_x, mouse_y);
 
-            // bounds check
-            if (row > 0 && row < ROWS - 1) && (column > 0 && column < COLUMNS - 1) {
+        // paint cells on mouse click
+        if let (true, Some((row, column))) = (is_mouse_button_down(MouseButton::Left), hovered_cell)
+        {
+            // continuous values are painted with a round brush, while rules that are built cell by cell, like
+            // circuits, need a finer one
+            if simulation.is_continuous() {
+                let (rows, columns) = (simulation.rows() as isize, simulation.columns() as isize);
+                let radius = CONTINUOUS_BRUSH_RADIUS;
+                for r in row as isize - radius..=row as isize + radius {
+                    for c in column as isize - radius..=column as isize + radius {
+                        let (dr, dc) = (r - row as isize, c - column as isize);
+                        if dr * dr + dc * dc <= radius * radius
+                            && (0..rows).contains(&r)
+                            && (0..columns).contains(&c)
+                        {
+                            simulation.set_state(r as usize, c as usize, brush);
+                        }
+                    }
+                }
+            } else if simulation.starts_empty() {
+                simulation.set_state(row, column, brush);
+            } else if (row > 0 && row < simulation.rows() - 1)
+                && (column > 0 && column < simulation.columns() - 1)
+            {
                 // spawn a square around the mouse pointer - works well for the supported sims
-                state[row][column] = CellState::Alive;
-                state[row + 1][column] = CellState::Alive;
-                state[row][column + 1] = CellState::Alive;
-                state[row + 1][column + 1] = CellState::Alive;
+                for (r, c) in [
+                    (row, column),
+                    (row + 1, column),
+                    (row, column + 1),
+                    (row + 1, column + 1),
+                ] {
+                    simulation.set_state(r, c, brush);
+                }
+            }
+        }
+
+        // zoom with the mouse wheel and pan by dragging with the right mouse button or with the arrow keys
+        let (_, wheel_y) = mouse_wheel();
+        if wheel_y != 0. {
+            let factor = if wheel_y > 0. {
+                ZOOM_STEP
+            } else {
+                1. / ZOOM_STEP
+            };
+            viewport.zoom(factor, mouse_x, mouse_y);
+        }
+
+        if is_mouse_button_down(MouseButton::Right) {
+            viewport.pan(
+                mouse_x - last_mouse_position.0,
+                mouse_y - last_mouse_position.1,
+            );
+        }
+        last_mouse_position = (mouse_x, mouse_y);
+
+        for (key, dx, dy) in [
+            (KeyCode::Left, PAN_STEP, 0.),
+            (KeyCode::Right, -PAN_STEP, 0.),
+            (KeyCode::Up, 0., PAN_STEP),
+            (KeyCode::Down, 0., -PAN_STEP),
+        ] {
+            if is_key_down(key) {
+                viewport.pan(dx, dy);
             }
         }
 
         // reset the state
         if is_key_pressed(KeyCode::R) {
-            reset_sim_state(&mut state, &mut buffer, &mut time_series);
+            seed = reseed(&mut rng);
+            reset_sim_state(simulation.as_mut(), &mut stats);
         }
 
         // randomize the state
         if is_key_pressed(KeyCode::A) {
-            randomize_sim_state(&mut state, &mut buffer, &mut time_series);
+            seed = reseed(&mut rng);
+            randomize_sim_state(simulation.as_mut(), &mut stats, &mut rng, settings.density);
         }
 
-        // select conway's game of life
-        if is_key_pressed(KeyCode::C) {
-            select_sim_mode(
-                &mut state,
-                &mut buffer,
-                &mut time_series,
-                &mut simulation_mode,
-                SimulationMode::ConwaysLife,
-            );
+        // select a mode by its shortcut, or step through all of them in order
+        let mut selected_mode = mode_keys
+            .iter()
+            .find(|(_, key)| is_key_pressed(*key))
+            .map(|&(idx, _)| idx);
+        let mode_count = registry.modes().len();
+        if is_key_pressed(KeyCode::RightBracket) {
+            selected_mode = Some(current_mode.map_or(0, |idx| (idx + 1) % mode_count));
+        }
+        if is_key_pressed(KeyCode::LeftBracket) {
+            selected_mode = Some(current_mode.map_or(0, |idx| (idx + mode_count - 1) % mode_count));
         }
 
-        // select brian's brain
-        if is_key_pressed(KeyCode::B) {
+        if let Some(mode_idx) = selected_mode {
+            seed = reseed(&mut rng);
             select_sim_mode(
-                &mut state,
-                &mut buffer,
-                &mut time_series,
-                &mut simulation_mode,
-                SimulationMode::BriansBrain,
+                &registry,
+                mode_idx,
+                &mut current_mode,
+                &mut simulation,
+                &mut stats,
+                &mut rng,
+                settings.density,
             );
         }
 
-        if is_key_pressed(KeyCode::H) {
-            select_sim_mode(
-                &mut state,
-                &mut buffer,
-                &mut time_series,
-                &mut simulation_mode,
-                SimulationMode::HighLife,
-            );
+        // modes differ in their number of states and presets
+        let brushes = brush_states(simulation.as_ref());
+        if !brushes.contains(&brush) {
+            brush = brushes[0];
+        }
+        let presets = simulation.presets();
+        if preset_idx >= presets.len() {
+            preset_idx = 0;
+        }
+        let parameters = simulation.parameters();
+        if parameter_idx >= parameters.len() {
+            parameter_idx = 0;
         }
 
-        if is_key_pressed(KeyCode::S) {
-            select_sim_mode(
-                &mut state,
-                &mut buffer,
-                &mut time_series,
-                &mut simulation_mode,
-                SimulationMode::Seeds,
-            );
+        // cycle through the states to paint, with the empty state last to erase
+        if is_key_pressed(KeyCode::Tab) {
+            let idx = brushes
+                .iter()
+                .position(|&state| state == brush)
+                .unwrap_or(0);
+            brush = brushes[(idx + 1) % brushes.len()];
         }
 
-        // write updated cell state for the next frame to buffer, based on the currently selected simulation mode
-        get_next_state(&state, &mut buffer, simulation_mode.cell_state_fn());
+        // pick a preset, and stamp it with its upper left corner at the mouse pointer
+        if is_key_pressed(KeyCode::O) && !presets.is_empty() {
+            preset_idx = (preset_idx + 1) % presets.len();
+        }
 
-        // keep track of how many cells are alive
-        let mut live_cell_count = 0;
+        if is_key_pressed(KeyCode::P) {
+            status_text = match (presets.get(preset_idx), hovered_cell) {
+                (Some((_, pattern)), Some((row, column))) => pattern
+                    .place_at(simulation.as_mut(), row, column)
+                    .map_or_else(|err| err, |()| String::new()),
+                (Some(_), None) => "Point at where the preset goes".to_string(),
+                (None, _) => format!("{} has no presets", simulation.name()),
+            };
+        }
 
-        // render the cell state and store buffer in the state
-        for r in 0..buffer.len() {
-            for c in 0..buffer[r].len() {
-                let cell = buffer[r][c];
+        // put another ant where the mouse is
+        if is_key_pressed(KeyCode::K) {
+            if let Some((row, column)) = hovered_cell {
+                if !simulation.add_agent(row, column) {
+                    status_text = format!("{} has no ants", simulation.name());
+                }
+            }
+        }
+
+        // pick a parameter of the simulation, and change it while the simulation runs
+        if is_key_pressed(KeyCode::G) && !parameters.is_empty() {
+            parameter_idx = (parameter_idx + 1) % parameters.len();
+        }
 
-                if cell == CellState::Alive {
-                    live_cell_count += 1;
+        for (key, raise) in [(KeyCode::Minus, false), (KeyCode::Equal, true)] {
+            if !is_key_pressed(key) {
+                continue;
+            }
+
+            match parameters.get(parameter_idx) {
+                Some(parameter) => {
+                    simulation.set_parameter(parameter_idx, step_parameter(parameter, raise))
                 }
+                None => status_text = format!("{} has no parameters", simulation.name()),
+            }
+        }
+
+        // toggle ship detection
+        if is_key_pressed(KeyCode::D) {
+            ship_detection_enabled = !ship_detection_enabled;
+            stats.ship_tracker.reset();
+        }
+
+        // toggle the neighbor count overlay
+        if is_key_pressed(KeyCode::N) {
+            neighbor_counts_enabled = !neighbor_counts_enabled;
+        }
+
+        // cycle through the ways of coloring cells
+        if is_key_pressed(KeyCode::M) {
+            color_mode = color_mode.next();
+        }
+
+        // cycle through the color themes
+        if is_key_pressed(KeyCode::T) {
+            theme_idx = (theme_idx + 1) % themes.len();
+        }
+
+        // change how the chart is scaled
+        if is_key_pressed(KeyCode::L) {
+            stats.chart.toggle_log_scale();
+        }
 
-                // update state
-                state[r][c] = cell;
+        if is_key_pressed(KeyCode::Z) {
+            stats.chart.toggle_zoom_to_data();
+        }
+
+        // pause or resume the simulation
+        if is_key_pressed(KeyCode::Space) {
+            paused = !paused;
+        }
+
+        // export the history of the run
+        if is_key_pressed(KeyCode::E) {
+            status_text = if cfg!(target_arch = "wasm32") {
+                "Export is not supported in the browser".to_string()
+            } else {
+                let path = PathBuf::from(format!("automata-{}-{}", seed, stats.generation));
+                match stats.history.export(&path, &simulation.rulestring(), seed) {
+                    Ok([csv_path, json_path]) => format!(
+                        "Exported {} and {}",
+                        csv_path.display(),
+                        json_path.display()
+                    ),
+                    Err(err) => format!("Export failed: {}", err),
+                }
+            };
+        }
+
+        // save the session, or go back to the last saved one
+        if is_key_pressed(KeyCode::F5) {
+            let session = Session {
+                rule: simulation.rulestring(),
+                grid: Pattern::from_simulation(simulation.as_ref(), None),
+                state: simulation.session_state(),
+                generation: stats.generation,
+                seed,
+                rng,
+                elapsed_millis: ((date::now() - stats.started_at_secs) * 1000.) as u64,
+                camera: viewport.camera(),
+                history_stride: stats.history.stride(),
+                history: stats.history.rows().to_vec(),
+            };
+
+            status_text = if cfg!(target_arch = "wasm32") {
+                "Sessions are not supported in the browser".to_string()
+            } else {
+                match session.save(&settings.session_path) {
+                    Ok(()) => format!("Saved {}", settings.session_path.display()),
+                    Err(err) => err,
+                }
+            };
+        }
+
+        if is_key_pressed(KeyCode::F9) {
+            let run = if cfg!(target_arch = "wasm32") {
+                Err("Sessions are not supported in the browser".to_string())
+            } else {
+                Session::load(&settings.session_path)
+                    .and_then(|session| Run::resume(&registry, session, &mut settings))
+            };
+
+            status_text = match run {
+                Ok(run) => {
+                    let camera;
+                    Run {
+                        simulation,
+                        stats,
+                        seed,
+                        rng,
+                        camera,
+                    } = run;
+                    current_mode = registry.find(&simulation.rulestring());
+
+                    viewport = Viewport::new(
+                        screen_width(),
+                        screen_height(),
+                        settings.rows,
+                        settings.columns,
+                    );
+                    if let Some(camera) = camera {
+                        viewport.set_camera(camera);
+                    }
+
+                    format!("Loaded {}", settings.session_path.display())
+                }
+                Err(err) => err,
+            };
+        }
+
+        if !paused {
+            step_sim_state(simulation.as_mut(), &mut stats, &mut rng);
+
+            if ship_detection_enabled {
+                stats.ship_tracker.update(simulation.as_ref());
+            }
+        }
+
+        // render the visible part of the cell state
+        let palette = if simulation.is_continuous() {
+            theme.colormap_palette(simulation.state_count())
+        } else {
+            theme.palette(simulation.palette(), simulation.state_count())
+        };
+        let cell_width = viewport.cell_size();
+        let (visible_rows, visible_columns) = viewport.visible_cells();
+        for r in visible_rows.clone() {
+            for c in visible_columns.clone() {
+                let (x, y) = viewport.cell_position(r, c);
 
                 // size - 1 px to create a nice juicy border
                 let cell_size = cell_width - 1.;
 
-                draw_rectangle(
-                    c as f32 * cell_width + 0.5,
-                    r as f32 * cell_width + 0.5,
-                    cell_size,
-                    cell_size,
-                    cell.color(),
+                let color = stats.activity.cell_color(
+                    color_mode,
+                    theme,
+                    palette[simulation.state(r, c) as usize],
+                    simulation.is_alive(r, c),
+                    r,
+                    c,
+                );
+                draw_rectangle(x + 0.5, y + 0.5, cell_size, cell_size, color);
+            }
+        }
+
+        // draw ants as triangles pointing where they are heading
+        for agent in simulation.agents() {
+            if !visible_rows.contains(&agent.row) || !visible_columns.contains(&agent.column) {
+                continue;
+            }
+
+            let (x, y) = viewport.cell_position(agent.row, agent.column);
+            let center = vec2(x + cell_width / 2., y + cell_width / 2.);
+            let (row_offset, column_offset) = agent.heading.offset();
+            let forward = vec2(column_offset as f32, row_offset as f32) * cell_width / 2.;
+            let side = vec2(-forward.y, forward.x) * 0.8;
+            draw_triangle(
+                center + forward,
+                center - forward + side,
+                center - forward - side,
+                theme.ship,
+            );
+        }
+
+        // label cells with the number of neighbors the current rule sees, when zoomed in far enough to read them
+        if neighbor_counts_enabled && cell_width >= NEIGHBOR_COUNT_MIN_CELL_SIZE {
+            let font_size = cell_width * 0.75;
+            for r in visible_rows {
+                for c in visible_columns.clone() {
+                    let count = simulation.count_neighbors(r, c);
+                    if count == 0 {
+                        continue;
+                    }
+
+                    let (x, y) = viewport.cell_position(r, c);
+                    draw_text(
+                        count.to_string().as_str(),
+                        x + cell_width * 0.3,
+                        y + cell_width * 0.75,
+                        font_size,
+                        theme.neighbor_count,
+                    );
+                }
+            }
+        }
+
+        // highlight moving objects along with their speed
+        if ship_detection_enabled {
+            for ship in stats.ship_tracker.ships() {
+                let (x, y) = viewport.cell_position(ship.row, ship.column);
+
+                draw_rectangle_lines(
+                    x - 1.,
+                    y - 1.,
+                    ship.width as f32 * cell_width + 2.,
+                    ship.height as f32 * cell_width + 2.,
+                    2.,
+                    theme.ship,
+                );
+                draw_text(
+                    ship.velocity.to_string().as_str(),
+                    x,
+                    y - 3.,
+                    SHIP_FONT_SIZE,
+                    theme.ship,
                 );
             }
         }
 
+        // describe the cell under the mouse pointer and what the current rule will do with it
+        let inspector_text = match hovered_cell {
+            Some((row, column)) => {
+                let (x, y) = viewport.cell_position(row, column);
+                draw_rectangle_lines(x, y, cell_width, cell_width, 2., theme.inspector);
+
+                format!(
+                    "Cell ({}, {}): {} (age {}), {} neighbors -> {}",
+                    row,
+                    column,
+                    simulation.state_name(simulation.state(row, column)),
+                    stats.activity.age(row, column),
+                    simulation.count_neighbors(row, column),
+                    simulation.state_name(simulation.next_state(row, column))
+                )
+            }
+            None => String::new(),
+        };
+
         let mut text_y = 25.;
 
-        let mode_text = format!("Current mode: {}", simulation_mode);
+        let mode_text = format!("Current mode: {}", simulation.name());
+        // agents move one step per generation
+        let ant_count = simulation.agents().len();
+        let generation_text = match ant_count {
+            0 => format!("Generation: {}", stats.generation),
+            1 => format!("Steps: {} (1 ant)", stats.generation),
+            _ => format!("Steps: {} ({} ants)", stats.generation, ant_count),
+        };
+        let generation_text = if paused {
+            format!("{} (paused)", generation_text)
+        } else {
+            generation_text
+        };
         let fps_text = format!("FPS: {}", get_fps());
-        let additional_instructions = [mode_text.as_str(), fps_text.as_str()];
+        let brush_text = match presets.get(preset_idx) {
+            Some((name, _)) => format!("Brush: {}, preset: {}", simulation.state_name(brush), name),
+            None => format!("Brush: {}", simulation.state_name(brush)),
+        };
+        // the selected parameter is in brackets
+        let parameters_text = simulation
+            .parameters()
+            .iter()
+            .enumerate()
+            .map(|(idx, parameter)| {
+                if idx == parameter_idx {
+                    format!("[{} = {}]", parameter.name, parameter.value)
+                } else {
+                    format!("{} = {}", parameter.name, parameter.value)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let parameters_text = if parameters_text.is_empty() {
+            String::new()
+        } else {
+            format!("Parameters: {}", parameters_text)
+        };
+        let color_mode_text = format!("Color mode: {}, theme: {}", color_mode, theme.name);
+        let ships_text = if ship_detection_enabled {
+            format!("Ships: {}", stats.ship_tracker.ships().len())
+        } else {
+            "Ships: detection off".to_string()
+        };
+        let additional_instructions = [
+            mode_text.as_str(),
+            generation_text.as_str(),
+            fps_text.as_str(),
+            brush_text.as_str(),
+            parameters_text.as_str(),
+            color_mode_text.as_str(),
+            ships_text.as_str(),
+            inspector_text.as_str(),
+            status_text.as_str(),
+        ];
 
         // print all the text
-        for idx in 0..INSTRUCTIONS.len() + additional_instructions.len() {
-            let line = if idx < INSTRUCTIONS.len() {
-                INSTRUCTIONS[idx]
-            } else {
-                additional_instructions[idx - INSTRUCTIONS.len()]
-            };
-            draw_text(line, TEXT_PADDING, text_y, FONT_SIZE, FONT_COLOR);
-            text_y += FONT_SIZE + 5.;
+        let lines = instructions
+            .iter()
+            .map(String::as_str)
+            .chain(additional_instructions);
+        for line in lines {
+            draw_text(line, TEXT_PADDING, text_y, settings.font_size, theme.text);
+            text_y += settings.font_size + 5.;
         }
 
         // draw a pretty chart
-        timestamp_secs += get_frame_time();
-        time_series.record(DataPoint::new(
-            (timestamp_secs * 1000.) as i32,
-            live_cell_count as f32,
-        ));
-        time_series.display(TEXT_PADDING, text_y, "cells alive");
+        stats.chart.display(TEXT_PADDING, text_y, theme);
+
+        // explain what the colors mean in the bottom left corner
+        stats.activity.draw_legend(
+            color_mode,
+            theme,
+            TEXT_PADDING,
+            screen_height() - TEXT_PADDING * 2.,
+        );
 
         next_frame().await
     }
@@ -0,0 +1,268 @@
+use std::fmt::{Display, Formatter};
+
+/// Offsets of the 8 cells that share an edge or a corner with a cell, as (rows, columns)
+pub const MOORE: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Offsets of the 4 cells that share an edge with a cell, as (rows, columns)
+pub const VON_NEUMANN: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Largest radius of the extended neighborhoods in B/S rulestrings
+pub const MAX_RADIUS: usize = 9;
+
+/// The cells around a cell that a rule looks at
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Neighborhood {
+    /// Cells at most the radius away in both directions, the 8 cells around the cell for radius 1
+    Moore(usize),
+    /// Cells at most the radius away in Manhattan distance, the 4 cells sharing an edge with the cell for radius 1
+    VonNeumann(usize),
+    /// The 6 neighbors of a cell on a hexagonal grid, emulated on the square grid like in Golly by leaving out the
+    /// upper right and lower left corners of the Moore neighborhood
+    Hexagonal,
+    /// Any set of offsets from the cell, as (rows, columns)
+    Custom(Vec<(isize, isize)>),
+}
+
+impl Default for Neighborhood {
+    fn default() -> Self {
+        Neighborhood::Moore(1)
+    }
+}
+
+impl Neighborhood {
+    /// Offsets of the neighbors from the cell, as (rows, columns)
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        self.iter_offsets().collect()
+    }
+
+    /// Same as [Neighborhood::offsets], without collecting them, so that counting neighbors doesn't allocate
+    fn iter_offsets(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
+        // every built-in neighborhood is part of a square around the cell, custom ones are a list of offsets
+        let (radius, custom) = match self {
+            Neighborhood::Moore(radius) | Neighborhood::VonNeumann(radius) => (*radius, &[][..]),
+            Neighborhood::Hexagonal => (1, &[][..]),
+            Neighborhood::Custom(offsets) => (0, &offsets[..]),
+        };
+
+        let radius = radius as isize;
+        (-radius..=radius)
+            .flat_map(move |row| (-radius..=radius).map(move |column| (row, column)))
+            .filter(move |&(row, column)| {
+                (row, column) != (0, 0)
+                    && match self {
+                        Neighborhood::VonNeumann(radius) => {
+                            row.unsigned_abs() + column.unsigned_abs() <= *radius
+                        }
+                        Neighborhood::Hexagonal => {
+                            (row, column) != (-1, 1) && (row, column) != (1, -1)
+                        }
+                        _ => true,
+                    }
+            })
+            .chain(custom.iter().copied())
+    }
+
+    /// Number of cells in the neighborhood, which is also the highest neighbor count a cell can have
+    pub fn size(&self) -> usize {
+        self.iter_offsets().count()
+    }
+
+    /// Positions of the neighbors of a cell on a grid of the given size. Neighbors beyond the edges are left out.
+    pub fn cells(
+        &self,
+        rows: usize,
+        columns: usize,
+        row: usize,
+        column: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.iter_offsets()
+            .filter_map(move |(row_offset, column_offset)| {
+                let neighbor_row = row.checked_add_signed(row_offset)?;
+                let neighbor_column = column.checked_add_signed(column_offset)?;
+
+                (neighbor_row < rows && neighbor_column < columns)
+                    .then_some((neighbor_row, neighbor_column))
+            })
+    }
+
+    /// Parse the neighborhood suffix of a B/S rulestring:
+    /// - nothing for the Moore neighborhood, or `M` followed by a radius for a larger one
+    /// - `V` for the von Neumann neighborhood, optionally followed by a radius
+    /// - `H` for the hexagonal neighborhood
+    /// - `@` followed by a custom mask in hex, see [Neighborhood::parse_mask]
+    pub fn parse_suffix(suffix: &str) -> Result<Self, String> {
+        let Some(first) = suffix.chars().next() else {
+            return Ok(Neighborhood::default());
+        };
+
+        let rest = &suffix[first.len_utf8()..];
+        let radius = || -> Result<usize, String> {
+            if rest.is_empty() {
+                return Ok(1);
+            }
+
+            match rest.parse() {
+                Ok(radius) if (1..=MAX_RADIUS).contains(&radius) => Ok(radius),
+                _ => Err(format!(
+                    "the neighborhood radius must be between 1 and {}",
+                    MAX_RADIUS
+                )),
+            }
+        };
+
+        match first.to_ascii_uppercase() {
+            'M' => Ok(Neighborhood::Moore(radius()?)),
+            'V' => Ok(Neighborhood::VonNeumann(radius()?)),
+            'H' if rest.is_empty() => Ok(Neighborhood::Hexagonal),
+            '@' => Neighborhood::parse_mask(rest),
+            _ => Err(format!("unknown neighborhood `{}`", suffix)),
+        }
+    }
+
+    /// Parse a custom neighborhood given as a square mask of an odd size, centered on the cell. The mask is read
+    /// row by row, one bit per cell with the most significant bit first, and written in hex with the last digit
+    /// padded with zero bits. The bit of the cell itself is ignored. For example, `550` is the 3x3 mask
+    /// `010 101 010`, the von Neumann neighborhood.
+    pub fn parse_mask(hex: &str) -> Result<Self, String> {
+        let bits = hex
+            .chars()
+            .map(|digit| digit.to_digit(16))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("`{}` is not a neighborhood mask in hex", hex))?
+            .into_iter()
+            .flat_map(|digit| (0..4).rev().map(move |bit| (digit >> bit) & 1 == 1))
+            .collect::<Vec<_>>();
+
+        // the largest square that fits in the bits, which leaves less than a digit of padding
+        let mut size = 1;
+        while (size + 2) * (size + 2) <= bits.len() {
+            size += 2;
+        }
+        if size == 1 || bits.len() - size * size >= 4 {
+            return Err(format!(
+                "`{}` is not a neighborhood mask of a square with an odd size",
+                hex
+            ));
+        }
+
+        let radius = (size / 2) as isize;
+        let offsets = (0..size * size)
+            .filter(|&idx| bits[idx])
+            .map(|idx| {
+                (
+                    (idx / size) as isize - radius,
+                    (idx % size) as isize - radius,
+                )
+            })
+            .filter(|&offset| offset != (0, 0))
+            .collect();
+
+        Ok(Neighborhood::Custom(offsets))
+    }
+
+    /// The mask of a custom neighborhood in hex, see [Neighborhood::parse_mask]
+    fn mask(offsets: &[(isize, isize)]) -> String {
+        let radius = offsets
+            .iter()
+            .map(|(row, column)| row.abs().max(column.abs()))
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let bits = (-radius..=radius)
+            .flat_map(|row| (-radius..=radius).map(move |column| (row, column)))
+            .map(|offset| offsets.contains(&offset))
+            .collect::<Vec<_>>();
+
+        bits.chunks(4)
+            .map(|chunk| {
+                let digit = (0..4).fold(0, |digit, idx| {
+                    (digit << 1) | u32::from(chunk.get(idx).copied().unwrap_or(false))
+                });
+                char::from_digit(digit, 16).unwrap().to_ascii_uppercase()
+            })
+            .collect()
+    }
+}
+
+impl Display for Neighborhood {
+    /// The rulestring suffix of the neighborhood, see [Neighborhood::parse_suffix]
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Neighborhood::Moore(1) => Ok(()),
+            Neighborhood::Moore(radius) => write!(f, "M{}", radius),
+            Neighborhood::VonNeumann(1) => write!(f, "V"),
+            Neighborhood::VonNeumann(radius) => write!(f, "V{}", radius),
+            Neighborhood::Hexagonal => write!(f, "H"),
+            Neighborhood::Custom(offsets) => write!(f, "@{}", Neighborhood::mask(offsets)),
+        }
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moore_neighborhood_stops_at_edges() {
+        let moore = Neighborhood::default();
+
+        assert_eq!(moore.offsets(), MOORE);
+        assert_eq!(moore.cells(10, 10, 5, 5).count(), 8);
+        assert_eq!(
+            moore.cells(10, 10, 0, 0).collect::<Vec<_>>(),
+            [(0, 1), (1, 0), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn neighborhood_sizes() {
+        assert_eq!(Neighborhood::Moore(2).size(), 24);
+        assert_eq!(Neighborhood::VonNeumann(1).size(), 4);
+        assert_eq!(Neighborhood::VonNeumann(2).size(), 12);
+        assert_eq!(Neighborhood::Hexagonal.size(), 6);
+    }
+
+    #[test]
+    fn parses_suffixes() {
+        for (suffix, neighborhood) in [
+            ("", Neighborhood::Moore(1)),
+            ("M3", Neighborhood::Moore(3)),
+            ("V", Neighborhood::VonNeumann(1)),
+            ("v2", Neighborhood::VonNeumann(2)),
+            ("H", Neighborhood::Hexagonal),
+        ] {
+            assert_eq!(Neighborhood::parse_suffix(suffix), Ok(neighborhood.clone()));
+            assert_eq!(neighborhood.to_string(), suffix.to_uppercase());
+        }
+
+        assert!(Neighborhood::parse_suffix("M10").is_err());
+        assert!(Neighborhood::parse_suffix("X").is_err());
+        assert!(Neighborhood::parse_suffix("é").is_err());
+        assert!(Neighborhood::parse_suffix("Vé").is_err());
+    }
+
+    #[test]
+    fn parses_custom_masks() {
+        let von_neumann = Neighborhood::parse_mask("5D0").unwrap();
+        assert_eq!(von_neumann.offsets(), Neighborhood::VonNeumann(1).offsets());
+        assert_eq!(von_neumann.to_string(), "@550");
+
+        // the 5x5 ring around the cell
+        let ring = Neighborhood::parse_mask("FC631F8").unwrap();
+        assert_eq!(ring.size(), 16);
+        assert_eq!(ring.to_string(), "@FC631F8");
+
+        assert!(Neighborhood::parse_mask("5D").is_err());
+        assert!(Neighborhood::parse_mask("ZZZ").is_err());
+    }
+}
@@ -0,0 +1,455 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::automaton::Simulation;
+
+const MAX_RLE_LINE_LENGTH: usize = 70;
+/// Largest width or height a pattern can declare, far more than any grid that fits in memory
+const MAX_PATTERN_SIZE: usize = 65_536;
+
+/// A finite arrangement of cells that can be placed on the grid, e.g. a glider loaded from an RLE file
+#[derive(Clone, PartialEq, Debug)]
+pub struct Pattern {
+    pub rows: usize,
+    pub columns: usize,
+    /// Non-empty cells, as (row, column, state) relative to the upper left corner of the pattern
+    pub cells: Vec<(usize, usize, u8)>,
+    /// Rule the pattern was made for, if the file specifies one
+    pub rule: Option<String>,
+}
+
+impl Pattern {
+    /// Load a pattern from a `.cells` (plaintext) or `.rle` file. Files with other extensions are assumed to be RLE.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read pattern {}: {}", path.display(), err))?;
+
+        let pattern = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("cells") => Pattern::parse_plaintext(&contents),
+            _ => Pattern::parse_rle(&contents),
+        };
+
+        pattern.map_err(|err| format!("Invalid pattern {}: {}", path.display(), err))
+    }
+
+    /// Parse a pattern in the run length encoded format used by Golly and LifeViewer. Both the two-state (`b`/`o`)
+    /// and the multi-state (`.`, `A` to `X`, and `pA` to `yO` for states above 24) cell tags are supported.
+    pub fn parse_rle(contents: &str) -> Result<Self, String> {
+        let mut lines = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        let header = lines.next().ok_or("missing `x = .., y = ..` header")?;
+        let mut rule = None;
+        // the rule comes last, and may contain commas itself, e.g. `rule = R5,C0,M1,S34..58,B34..45,NM`
+        let header = match header.find("rule") {
+            Some(idx) => {
+                let (key, value) = header[idx..]
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid header field `{}`", &header[idx..]))?;
+                if key.trim() != "rule" {
+                    return Err(format!("unknown header field `{}`", key.trim()));
+                }
+
+                rule = Some(value.trim().to_string());
+                header[..idx].trim_end().trim_end_matches(',')
+            }
+            None => header,
+        };
+
+        let (mut header_rows, mut header_columns) = (None, None);
+        for field in header.split(',').filter(|field| !field.trim().is_empty()) {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| format!("invalid header field `{}`", field.trim()))?;
+
+            let size = || {
+                value
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|&size| size <= MAX_PATTERN_SIZE)
+                    .ok_or_else(|| format!("invalid size `{}`", value.trim()))
+            };
+
+            match key.trim() {
+                "x" => header_columns = Some(size()?),
+                "y" => header_rows = Some(size()?),
+                other => return Err(format!("unknown header field `{}`", other)),
+            }
+        }
+        let (Some(header_rows), Some(header_columns)) = (header_rows, header_columns) else {
+            return Err("missing `x = .., y = ..` header".to_string());
+        };
+
+        let mut cells = Vec::new();
+        let (mut row, mut column): (usize, usize) = (0, 0);
+        let mut run_count: Option<usize> = None;
+        // states above 24 are written as two letters, e.g. `pA` for 25
+        let mut state_prefix: Option<char> = None;
+
+        'body: for line in lines {
+            for tag in line.chars() {
+                if let Some(digit) = tag.to_digit(10) {
+                    run_count = run_count
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|count| count.checked_add(digit as usize))
+                        .filter(|&count| count <= MAX_PATTERN_SIZE)
+                        .map(Some)
+                        .ok_or("run count is too large")?;
+                    continue;
+                }
+
+                if ('p'..='y').contains(&tag) {
+                    state_prefix = Some(tag);
+                    continue;
+                }
+
+                let count = run_count.take().unwrap_or(1);
+                let state = match (state_prefix.take(), tag) {
+                    (None, 'b' | '.') => 0,
+                    (None, 'o') => 1,
+                    (prefix, 'A'..='X') => {
+                        let high = prefix.map_or(0, |prefix| (prefix as u32 - 'p' as u32 + 1) * 24);
+                        let state = high + (tag as u32 - 'A' as u32) + 1;
+                        u8::try_from(state)
+                            .map_err(|_| format!("state {} is out of range", state))?
+                    }
+                    (None, '$') => {
+                        row = row.saturating_add(count);
+                        column = 0;
+                        continue;
+                    }
+                    (None, '!') => break 'body,
+                    (None, tag) if tag.is_whitespace() => continue,
+                    (Some(prefix), tag) => {
+                        return Err(format!("unsupported cell tag `{}{}`", prefix, tag))
+                    }
+                    (None, tag) => return Err(format!("unsupported cell tag `{}`", tag)),
+                };
+
+                // the size in the header may include empty space around the cells, but it can't cut them off
+                if row >= header_rows || column + count > header_columns {
+                    return Err(format!(
+                        "cells on row {} go past the {}x{} size in the header",
+                        row.saturating_add(1),
+                        header_columns,
+                        header_rows
+                    ));
+                }
+
+                if state != 0 {
+                    cells.extend((column..column + count).map(|column| (row, column, state)));
+                }
+                column += count;
+            }
+        }
+
+        Ok(Self {
+            rows: header_rows,
+            columns: header_columns,
+            cells,
+            rule,
+        })
+    }
+
+    /// Parse a pattern in the plaintext format, where `.` is a dead cell, `O` a live one and `!` starts a comment
+    pub fn parse_plaintext(contents: &str) -> Result<Self, String> {
+        let mut cells = Vec::new();
+
+        let lines = contents.lines().filter(|line| !line.starts_with('!'));
+        for (row, line) in lines.enumerate() {
+            for (column, tag) in line.trim_end().chars().enumerate() {
+                match tag {
+                    '.' => {}
+                    'O' | '*' => cells.push((row, column, 1)),
+                    tag => return Err(format!("unsupported cell `{}` on row {}", tag, row + 1)),
+                }
+            }
+        }
+
+        Ok(Pattern::from_cells(cells, None))
+    }
+
+    /// Every non-empty cell of the simulation, keeping the size of the grid as the size of the pattern
+    pub fn from_simulation(simulation: &dyn Simulation, rule: Option<String>) -> Self {
+        let mut cells = Vec::new();
+        for row in 0..simulation.rows() {
+            for column in 0..simulation.columns() {
+                let state = simulation.state(row, column);
+                if state != 0 {
+                    cells.push((row, column, state));
+                }
+            }
+        }
+
+        Self {
+            rows: simulation.rows(),
+            columns: simulation.columns(),
+            cells,
+            rule,
+        }
+    }
+
+    /// Format the pattern as RLE, see [Pattern::parse_rle]. The multi-state cell tags are only used if some cells
+    /// are in states other than 0 and 1.
+    pub fn to_rle(&self) -> String {
+        let multi_state = self.cells.iter().any(|&(.., state)| state > 1);
+        let tag = |state: u8| -> String {
+            match (state, multi_state) {
+                (0, false) => "b".to_string(),
+                (1, false) => "o".to_string(),
+                (0, true) => ".".to_string(),
+                (state, _) => {
+                    let (high, low) = ((state - 1) / 24, (state - 1) % 24);
+                    let low = char::from(b'A' + low);
+                    match high {
+                        0 => low.to_string(),
+                        high => format!("{}{}", char::from(b'p' + high - 1), low),
+                    }
+                }
+            }
+        };
+
+        let mut runs: Vec<(usize, String)> = Vec::new();
+        let mut push_run = |count: usize, tag: String| match runs.last_mut() {
+            _ if count == 0 => {}
+            Some((last_count, last_tag)) if *last_tag == tag => *last_count += count,
+            _ => runs.push((count, tag)),
+        };
+
+        let mut cells = self.cells.clone();
+        cells.sort_by_key(|&(row, column, _)| (row, column));
+
+        let (mut row, mut column) = (0, 0);
+        for (cell_row, cell_column, cell) in cells {
+            if cell_row > row {
+                push_run(cell_row - row, "$".to_string());
+                (row, column) = (cell_row, 0);
+            }
+
+            push_run(cell_column - column, tag(0));
+            push_run(1, tag(cell));
+            column = cell_column + 1;
+        }
+        push_run(1, "!".to_string());
+
+        let mut rle = format!("x = {}, y = {}", self.columns, self.rows);
+        if let Some(rule) = &self.rule {
+            let _ = write!(rle, ", rule = {}", rule);
+        }
+        rle.push('\n');
+
+        // keep lines short, as most tools expect
+        let mut line_length = 0;
+        for (count, tag) in runs {
+            let run = match count {
+                1 => tag,
+                count => format!("{}{}", count, tag),
+            };
+
+            if line_length + run.len() > MAX_RLE_LINE_LENGTH {
+                rle.push('\n');
+                line_length = 0;
+            }
+            line_length += run.len();
+            rle.push_str(&run);
+        }
+        rle.push('\n');
+
+        rle
+    }
+
+    fn from_cells(cells: Vec<(usize, usize, u8)>, rule: Option<String>) -> Self {
+        let rows = cells.iter().map(|&(row, ..)| row + 1).max().unwrap_or(0);
+        let columns = cells
+            .iter()
+            .map(|&(_, column, _)| column + 1)
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            rows,
+            columns,
+            cells,
+            rule,
+        }
+    }
+
+    /// Check that the pattern fits on a grid of the given size
+    pub fn check_fits(&self, rows: usize, columns: usize) -> Result<(), String> {
+        if self.rows > rows || self.columns > columns {
+            return Err(format!(
+                "Pattern is {}x{}, which does not fit on the {}x{} grid",
+                self.columns, self.rows, columns, rows
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Place the pattern in the middle of the grid
+    pub fn place(&self, simulation: &mut dyn Simulation) -> Result<(), String> {
+        self.check_fits(simulation.rows(), simulation.columns())?;
+
+        let top = (simulation.rows() - self.rows) / 2;
+        let left = (simulation.columns() - self.columns) / 2;
+        self.place_at(simulation, top, left)
+    }
+
+    /// Place the pattern with its upper left corner at the given cell, e.g. to stamp it where the mouse is
+    pub fn place_at(
+        &self,
+        simulation: &mut dyn Simulation,
+        top: usize,
+        left: usize,
+    ) -> Result<(), String> {
+        if top + self.rows > simulation.rows() || left + self.columns > simulation.columns() {
+            return Err(format!(
+                "Pattern is {}x{}, which does not fit on the grid at ({}, {})",
+                self.columns, self.rows, top, left
+            ));
+        }
+
+        if let Some(&(.., state)) = self
+            .cells
+            .iter()
+            .find(|&&(.., state)| state as usize >= simulation.state_count())
+        {
+            return Err(format!(
+                "Pattern has cells in state {}, but {} only has {} states",
+                state,
+                simulation.name(),
+                simulation.state_count()
+            ));
+        }
+
+        for &(row, column, state) in self.cells.iter() {
+            simulation.set_state(top + row, left + column, state);
+        }
+
+        Ok(())
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::Runner;
+    use crate::simulations::brain::BriansBrain;
+    use crate::simulations::conway::ConwaysLife;
+
+    const GLIDER: [(usize, usize, u8); 5] = [(0, 1, 1), (1, 2, 1), (2, 0, 1), (2, 1, 1), (2, 2, 1)];
+
+    #[test]
+    fn parses_rle() {
+        let pattern = Pattern::parse_rle(
+            "#N Glider\n\
+             x = 3, y = 3, rule = B3/S23\n\
+             bob$2bo$3o!\n",
+        )
+        .unwrap();
+
+        assert_eq!(pattern.cells, GLIDER);
+        assert_eq!((pattern.rows, pattern.columns), (3, 3));
+        assert_eq!(pattern.rule.as_deref(), Some("B3/S23"));
+
+        let pattern =
+            Pattern::parse_rle("x = 2, y = 1, rule = R5,C0,M1,S34..58,B34..45,NM\n2o!").unwrap();
+        assert_eq!(pattern.columns, 2);
+        assert_eq!(pattern.rule.as_deref(), Some("R5,C0,M1,S34..58,B34..45,NM"));
+    }
+
+    #[test]
+    fn parses_multi_state_rle() {
+        let pattern = Pattern::parse_rle("x = 3, y = 2, rule = B2/S/C3\nAB$\nA.pC!").unwrap();
+
+        assert_eq!(pattern.cells, [(0, 0, 1), (0, 1, 2), (1, 0, 1), (1, 2, 27)]);
+        assert_eq!(
+            Pattern::parse_rle(&pattern.to_rle()).map(|parsed| parsed.cells),
+            Ok(pattern.cells)
+        );
+    }
+
+    #[test]
+    fn parses_plaintext() {
+        let pattern = Pattern::parse_plaintext("!Name: Glider\n.O.\n..O\nOOO\n").unwrap();
+
+        assert_eq!(pattern.cells, GLIDER);
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert_eq!(
+            Pattern::parse_rle("x = 1, y = 1\n2zA!"),
+            Err("unsupported cell tag `z`".to_string())
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 1, y = 1\npo!"),
+            Err("unsupported cell tag `po`".to_string())
+        );
+        assert!(Pattern::parse_rle("").is_err());
+        assert_eq!(
+            Pattern::parse_rle("x = 3, y = 1\n4o!"),
+            Err("cells on row 1 go past the 3x1 size in the header".to_string())
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 3, y = 1\no$o!"),
+            Err("cells on row 2 go past the 3x1 size in the header".to_string())
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 3, y = 1\n99999999999o!"),
+            Err("run count is too large".to_string())
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 3, y = 1\n999999999999999999999o!"),
+            Err("run count is too large".to_string())
+        );
+        assert_eq!(
+            Pattern::parse_rle("x = 99999999999, y = 1\no!"),
+            Err("invalid size `99999999999`".to_string())
+        );
+        assert!(Pattern::parse_rle("y = 1\no!").is_err());
+    }
+
+    #[test]
+    fn places_pattern_in_the_middle() {
+        let pattern = Pattern::parse_plaintext("O\n.O").unwrap();
+        let mut simulation = Runner::new(ConwaysLife, 4, 4);
+
+        pattern.place(&mut simulation).unwrap();
+
+        assert_eq!(simulation.state(1, 1), 1);
+        assert_eq!(simulation.state(2, 2), 1);
+        assert!(pattern.place(&mut Runner::new(ConwaysLife, 1, 1)).is_err());
+        assert!(pattern.place_at(&mut simulation, 3, 0).is_err());
+        assert!(Pattern::parse_rle("x = 1, y = 1\nB!")
+            .unwrap()
+            .place(&mut simulation)
+            .is_err());
+    }
+
+    #[test]
+    fn writes_rle() {
+        let mut simulation = Runner::new(BriansBrain, 4, 5);
+        for (row, column, state) in GLIDER {
+            simulation.set_state(row + 1, column + 1, state);
+        }
+
+        let pattern = Pattern::from_simulation(&simulation, Some("B3/S23".to_string()));
+        let rle = pattern.to_rle();
+
+        assert_eq!(rle, "x = 5, y = 4, rule = B3/S23\n$2bo$3bo$b3o!\n");
+        assert_eq!(Pattern::parse_rle(&rle), Ok(pattern));
+
+        simulation.set_state(0, 0, 2);
+        assert_eq!(
+            Pattern::from_simulation(&simulation, None).to_rle(),
+            "x = 5, y = 4\nB$2.A$3.A$.3A!\n"
+        );
+    }
+}
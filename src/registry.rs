@@ -0,0 +1,352 @@
+use crate::automaton::{Automaton, Named, Runner, Simulation};
+use crate::simulations::brain::BriansBrain;
+use crate::simulations::conway::ConwaysLife;
+use crate::simulations::cyclic::CyclicRule;
+use crate::simulations::elementary::ElementaryRule;
+use crate::simulations::epidemic::Epidemic;
+use crate::simulations::forest_fire::ForestFire;
+use crate::simulations::golly::GollyRule;
+use crate::simulations::gray_scott::GrayScott;
+use crate::simulations::greenberg_hastings::GreenbergHastings;
+use crate::simulations::highlife::HighLife;
+use crate::simulations::isotropic::IsotropicRule;
+use crate::simulations::larger_than_life::LargerThanLife;
+use crate::simulations::lenia::Lenia;
+use crate::simulations::lifelike::LifeLikeRule;
+use crate::simulations::map::MapRule;
+use crate::simulations::seeds::Seeds;
+use crate::simulations::smoothlife::SmoothLife;
+use crate::simulations::turmite::Turmite;
+use crate::simulations::wireworld::Wireworld;
+
+/// Creates a simulation with the given number of rows and columns
+pub type Constructor = Box<dyn Fn(usize, usize) -> Box<dyn Simulation>>;
+
+/// Creates a simulation with the given number of rows and columns from a rulestring. Returns `None` if the
+/// rulestring is not in the notation the parser understands, so that the next parser can have a go at it.
+pub type RuleParser =
+    Box<dyn Fn(&str, usize, usize) -> Option<Result<Box<dyn Simulation>, String>>>;
+
+/// A way of writing rules as rulestrings, e.g. `B3/S23` for Life-like rules, and how to run the rules
+pub trait Notation: Clone + 'static {
+    /// Well known rules in the notation, as their names and rulestrings, which get a mode of their own. Rulestrings
+    /// are written the way the rule writes itself, so that parsing them finds the mode.
+    const PRESETS: &'static [(&'static str, &'static str)] = &[];
+
+    /// Whether the rulestring is meant to be in this notation, so that its errors are the ones worth reporting
+    fn matches(rulestring: &str) -> bool;
+
+    fn parse(rulestring: &str) -> Result<Self, String>;
+
+    /// Run the rule on a grid of the given size
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation>;
+}
+
+/// A rule that can be selected from the mode menu
+pub struct Mode {
+    pub name: String,
+    pub rulestring: String,
+    /// Key that selects the mode in the visualizer
+    pub shortcut: Option<char>,
+    constructor: Constructor,
+}
+
+/// The rules available at runtime, both as named modes and as notations that rulestrings can be written in
+#[derive(Default)]
+pub struct Registry {
+    modes: Vec<Mode>,
+    parsers: Vec<RuleParser>,
+}
+
+impl Registry {
+    /// An empty registry, without even the built-in rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The rules that come with this crate
+    pub fn with_builtins() -> Self {
+        let mut registry = Registry::new();
+
+        registry.register(ConwaysLife, Some('C'));
+        registry.register(BriansBrain, Some('B'));
+        registry.register(HighLife, Some('H'));
+        registry.register(Seeds, Some('S'));
+        registry.register(Wireworld, Some('W'));
+
+        registry.register_notation::<GollyRule>();
+        registry.register_notation::<GreenbergHastings>();
+        // before Larger than Life, whose rules also start with R and a digit
+        registry.register_notation::<CyclicRule>();
+        registry.register_notation::<LargerThanLife>();
+        registry.register_notation::<IsotropicRule>();
+        registry.register_notation::<MapRule>();
+        registry.register_notation::<Turmite>();
+        registry.register_notation::<ElementaryRule>();
+        registry.register_notation::<ForestFire>();
+        registry.register_notation::<Epidemic>();
+        registry.register_notation::<Lenia>();
+        registry.register_notation::<SmoothLife>();
+        registry.register_notation::<GrayScott>();
+        // last, since it takes every rulestring that no other notation does
+        registry.register_notation::<LifeLikeRule>();
+
+        registry
+    }
+
+    /// Add an automaton to the mode menu
+    pub fn register<A>(&mut self, automaton: A, shortcut: Option<char>)
+    where
+        A: Automaton + Clone + 'static,
+    {
+        let (name, rulestring) = (automaton.name(), automaton.rulestring());
+
+        self.register_mode(
+            name,
+            rulestring,
+            shortcut,
+            Box::new(move |rows, columns| Box::new(Runner::new(automaton.clone(), rows, columns))),
+        );
+    }
+
+    /// Add an automaton to the mode menu under a name of its own, for presets of rules that are named after their
+    /// rulestring
+    pub fn register_named<A>(&mut self, name: String, automaton: A, shortcut: Option<char>)
+    where
+        A: Automaton + Clone + 'static,
+    {
+        let rulestring = automaton.rulestring();
+
+        self.register_mode(
+            name.clone(),
+            rulestring,
+            shortcut,
+            Box::new(move |rows, columns| {
+                let runner = Runner::new(automaton.clone(), rows, columns);
+                Box::new(Named::new(name.clone(), Box::new(runner)))
+            }),
+        );
+    }
+
+    /// Add a simulation that is not an [Automaton] to the mode menu
+    pub fn register_mode(
+        &mut self,
+        name: String,
+        rulestring: String,
+        shortcut: Option<char>,
+        constructor: Constructor,
+    ) {
+        self.modes.push(Mode {
+            name,
+            rulestring,
+            shortcut,
+            constructor,
+        });
+    }
+
+    /// Add a notation for rulestrings, along with its presets as modes. See [RuleParser] for how notations take
+    /// turns at a rulestring.
+    pub fn register_notation<N: Notation>(&mut self) {
+        for &(name, rulestring) in N::PRESETS {
+            let rule = N::parse(rulestring).expect("presets are valid rules");
+            self.register_mode(
+                name.to_string(),
+                rulestring.to_string(),
+                None,
+                Box::new(move |rows, columns| {
+                    let simulation = rule.clone().into_simulation(rows, columns);
+                    Box::new(Named::new(name.to_string(), simulation))
+                }),
+            );
+        }
+
+        self.register_parser(Box::new(|rulestring, rows, columns| {
+            N::matches(rulestring)
+                .then(|| N::parse(rulestring).map(|rule| rule.into_simulation(rows, columns)))
+        }));
+    }
+
+    /// Add a parser for rulestrings that are not written in a [Notation], e.g. names of files to load rules from
+    pub fn register_parser(&mut self, parser: RuleParser) {
+        self.parsers.push(parser);
+    }
+
+    pub fn modes(&self) -> &[Mode] {
+        &self.modes
+    }
+
+    /// Create a simulation of the mode at the given index in [Registry::modes]
+    pub fn create(&self, idx: usize, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        (self.modes[idx].constructor)(rows, columns)
+    }
+
+    /// Index of the mode that runs the given rule, if there is one
+    pub fn find(&self, rulestring: &str) -> Option<usize> {
+        self.modes
+            .iter()
+            .position(|mode| mode.rulestring == rulestring)
+    }
+
+    /// Create a simulation from a rulestring in any of the registered notations. Rules that are equivalent to one of
+    /// the modes get the mode's name.
+    pub fn parse(
+        &self,
+        rulestring: &str,
+        rows: usize,
+        columns: usize,
+    ) -> Result<Box<dyn Simulation>, String> {
+        if let Some(idx) = self.find(rulestring) {
+            return Ok(self.create(idx, rows, columns));
+        }
+
+        let mut first_error = None;
+        for parser in self.parsers.iter() {
+            match parser(rulestring, rows, columns) {
+                Some(Ok(simulation)) => {
+                    return Ok(match self.find(&simulation.rulestring()) {
+                        Some(idx) => self.create(idx, rows, columns),
+                        None => simulation,
+                    });
+                }
+                Some(Err(err)) => {
+                    first_error.get_or_insert(err);
+                }
+                None => {}
+            }
+        }
+
+        Err(first_error.unwrap_or_else(|| format!("Unknown rule `{}`", rulestring)))
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::util::Rng;
+    use crate::CellState;
+
+    /// Every cell copies the state of its upper neighbor
+    #[derive(Clone)]
+    struct FallingSand;
+
+    impl Automaton for FallingSand {
+        type State = CellState;
+
+        fn name(&self) -> String {
+            "Falling Sand".to_string()
+        }
+
+        fn rulestring(&self) -> String {
+            "sand".to_string()
+        }
+
+        fn state_count(&self) -> usize {
+            2
+        }
+
+        fn next_state(&self, grid: &Grid<CellState>, row: usize, column: usize) -> CellState {
+            if row == 0 {
+                CellState::Dead
+            } else {
+                grid[row - 1][column]
+            }
+        }
+    }
+
+    #[test]
+    fn parses_rules_into_modes() {
+        let registry = Registry::with_builtins();
+
+        assert_eq!(registry.parse("b36/s23", 8, 8).unwrap().name(), "HighLife");
+        assert_eq!(registry.parse("B3/S238", 8, 8).unwrap().name(), "B3/S238");
+        assert_eq!(
+            registry
+                .parse("R5,C0,M1,S34..58,B34..45,NM", 8, 8)
+                .unwrap()
+                .name(),
+            "Bosco's Rule"
+        );
+        assert_eq!(
+            registry.parse("B3/S2-i34q", 8, 8).unwrap().name(),
+            "B3/S2-i34q"
+        );
+        assert_eq!(
+            registry.parse("GH/R1/T1/C5/NM", 8, 8).unwrap().name(),
+            "Greenberg-Hastings"
+        );
+        assert_eq!(
+            registry
+                .parse("lenia/R13/T10/M0.15/S0.015/B1", 8, 8)
+                .unwrap()
+                .name(),
+            "Lenia (Orbium)"
+        );
+        assert_eq!(
+            registry
+                .parse("SmoothLife/R10/B0.278..0.365/D0.267..0.445", 8, 8)
+                .unwrap()
+                .name(),
+            "SmoothLife"
+        );
+        assert_eq!(
+            registry
+                .parse("grayscott/F0.0545/K0.062/S10", 8, 8)
+                .unwrap()
+                .name(),
+            "Gray-Scott coral"
+        );
+        assert_eq!(
+            registry
+                .parse("GrayScott/F0.04/K0.06/S5", 8, 8)
+                .unwrap()
+                .name(),
+            "Gray-Scott"
+        );
+        assert!(registry.parse("B9/S23", 8, 8).is_err());
+        for rulestring in [
+            "B3/S23é",
+            "é",
+            "R5,C0,M1,é",
+            "GH/é",
+            "RPS/é",
+            "Lenia/é",
+            "GrayScott/é",
+        ] {
+            assert!(registry.parse(rulestring, 8, 8).is_err(), "{}", rulestring);
+        }
+        assert!(registry
+            .parse("R5,C0,M1,B34..45,NM", 8, 8)
+            .err()
+            .is_some_and(|err| err.contains("missing survival counts")));
+    }
+
+    #[test]
+    fn modes_run_their_rules() {
+        let registry = Registry::with_builtins();
+
+        for (idx, mode) in registry.modes().iter().enumerate() {
+            let simulation = registry.create(idx, 8, 8);
+            assert_eq!(simulation.name(), mode.name);
+            assert_eq!(simulation.rulestring(), mode.rulestring);
+        }
+    }
+
+    #[test]
+    fn registers_custom_automata() {
+        let mut registry = Registry::with_builtins();
+        registry.register(FallingSand, Some('F'));
+
+        let mode = registry.modes().last().unwrap();
+        assert_eq!(mode.name, "Falling Sand");
+        assert_eq!(mode.shortcut, Some('F'));
+
+        let mut simulation = registry.parse("sand", 3, 3).unwrap();
+        simulation.set_state(0, 1, 1);
+        simulation.step(&mut Rng::new(0));
+
+        assert_eq!(simulation.state(0, 1), 0);
+        assert_eq!(simulation.state(1, 1), 1);
+    }
+}
@@ -0,0 +1,380 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::automaton::Simulation;
+use crate::history::HistoryRow;
+use crate::patterns::Pattern;
+use crate::util::Rng;
+
+/// Bumped whenever the format changes in a way older versions can't read
+const SESSION_VERSION: u32 = 2;
+const STATE_SECTION: &str = "[state]";
+const GRID_SECTION: &str = "[grid]";
+const HISTORY_SECTION: &str = "[history]";
+
+/// What part of the grid is on screen, so that it can be saved and restored
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Camera {
+    /// Size of a single cell on screen, in pixels
+    pub cell_size: f32,
+    /// Screen position of the upper left corner of the grid
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+/// Everything needed to reopen a run exactly where it was left off
+pub struct Session {
+    pub rule: String,
+    /// Every cell on the grid, with the size of the grid as the size of the pattern
+    pub grid: Pattern,
+    /// See [Simulation::session_state]
+    pub state: String,
+    pub generation: u64,
+    /// Seed the run was started with, kept for exports
+    pub seed: u64,
+    pub rng: Rng,
+    /// Time since the start of the run, so that history timestamps carry on from where they were
+    pub elapsed_millis: u64,
+    pub camera: Camera,
+    pub history_stride: u64,
+    pub history: Vec<HistoryRow>,
+}
+
+impl Session {
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_text())
+            .map_err(|err| format!("Failed to save session {}: {}", path.display(), err))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read session {}: {}", path.display(), err))?;
+
+        Session::parse(&contents)
+            .map_err(|err| format!("Invalid session {}: {}", path.display(), err))
+    }
+
+    /// Put the saved cells and state on a new simulation of the saved rule. Cells the simulation starts with, such as
+    /// the seed of a one-dimensional rule, are blanked first, since the grid only holds the cells that are not.
+    pub fn restore(&self, simulation: &mut dyn Simulation) -> Result<(), String> {
+        self.grid
+            .check_fits(simulation.rows(), simulation.columns())?;
+        for row in 0..simulation.rows() {
+            for column in 0..simulation.columns() {
+                simulation.set_state(row, column, 0);
+            }
+        }
+
+        self.grid.place(simulation)?;
+        simulation
+            .restore_session_state(&self.state)
+            .map_err(|err| format!("Invalid session state: {}", err))
+    }
+
+    /// Format the session as `key = value` lines, followed by the state of the simulation, the grid as RLE and the
+    /// history as CSV rows
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# automata session\n");
+        let _ = writeln!(text, "version = {}", SESSION_VERSION);
+        let _ = writeln!(text, "rule = {}", self.rule);
+        let _ = writeln!(text, "generation = {}", self.generation);
+        let _ = writeln!(text, "seed = {}", self.seed);
+        let _ = writeln!(text, "rng_state = {}", self.rng.state());
+        let _ = writeln!(text, "elapsed_millis = {}", self.elapsed_millis);
+        let _ = writeln!(
+            text,
+            "camera = {}, {}, {}",
+            self.camera.cell_size, self.camera.offset_x, self.camera.offset_y
+        );
+
+        let _ = writeln!(text, "{}", STATE_SECTION);
+        for line in self.state.lines() {
+            let _ = writeln!(text, "{}", line);
+        }
+
+        let _ = writeln!(text, "{}", GRID_SECTION);
+        text.push_str(&self.grid.to_rle());
+
+        let _ = writeln!(text, "{}", HISTORY_SECTION);
+        let _ = writeln!(text, "stride = {}", self.history_stride);
+        for row in self.history.iter() {
+            let _ = write!(text, "{},{}", row.generation, row.timestamp_millis);
+            for value in row.values.iter() {
+                let _ = write!(text, ",{}", value);
+            }
+            text.push('\n');
+        }
+
+        text
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let (header, rest) = contents
+            .split_once(GRID_SECTION)
+            .ok_or_else(|| format!("missing {} section", GRID_SECTION))?;
+        let (grid, history) = rest
+            .split_once(HISTORY_SECTION)
+            .ok_or_else(|| format!("missing {} section", HISTORY_SECTION))?;
+        // sessions from before version 2 have no state
+        let (header, state) = header.split_once(STATE_SECTION).unwrap_or((header, ""));
+
+        let mut version = None;
+        let mut rule = None;
+        let mut generation = None;
+        let mut seed = None;
+        let mut rng_state = None;
+        let mut elapsed_millis = None;
+        let mut camera = None;
+
+        for (idx, line) in header.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let line_number = idx + 1;
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", line_number))?;
+            let value = value.trim();
+            let invalid = || format!("line {}: invalid {} `{}`", line_number, key.trim(), value);
+
+            match key.trim() {
+                "version" => version = Some(value.parse::<u32>().map_err(|_| invalid())?),
+                "rule" => rule = Some(value.to_string()),
+                "generation" => generation = Some(value.parse().map_err(|_| invalid())?),
+                "seed" => seed = Some(value.parse().map_err(|_| invalid())?),
+                "rng_state" => rng_state = Some(value.parse().map_err(|_| invalid())?),
+                "elapsed_millis" => elapsed_millis = Some(value.parse().map_err(|_| invalid())?),
+                "camera" => {
+                    let parts = value
+                        .split(',')
+                        .map(|part| part.trim().parse::<f32>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| invalid())?;
+                    let [cell_size, offset_x, offset_y] = parts[..] else {
+                        return Err(invalid());
+                    };
+
+                    camera = Some(Camera {
+                        cell_size,
+                        offset_x,
+                        offset_y,
+                    });
+                }
+                other => return Err(format!("line {}: unknown key `{}`", line_number, other)),
+            }
+        }
+
+        match version {
+            Some(1..=SESSION_VERSION) => {}
+            Some(version) => return Err(format!("unsupported session version {}", version)),
+            None => return Err("missing version".to_string()),
+        }
+
+        let missing = |key: &str| format!("missing {}", key);
+        let (history_stride, history) = parse_history(history)?;
+
+        Ok(Self {
+            rule: rule.ok_or_else(|| missing("rule"))?,
+            grid: parse_grid(grid)?,
+            state: state.trim().to_string(),
+            generation: generation.ok_or_else(|| missing("generation"))?,
+            seed: seed.ok_or_else(|| missing("seed"))?,
+            rng: Rng::new(rng_state.ok_or_else(|| missing("rng_state"))?),
+            elapsed_millis: elapsed_millis.ok_or_else(|| missing("elapsed_millis"))?,
+            camera: camera.ok_or_else(|| missing("camera"))?,
+            history_stride,
+            history,
+        })
+    }
+}
+
+/// Parse the grid section, which is RLE with the size of the whole grid in the header
+fn parse_grid(rle: &str) -> Result<Pattern, String> {
+    let pattern = Pattern::parse_rle(rle).map_err(|err| format!("grid: {}", err))?;
+    if pattern.rows == 0 || pattern.columns == 0 {
+        return Err("grid: missing size".to_string());
+    }
+
+    Ok(pattern)
+}
+
+/// Parse the history section, a `stride = N` line followed by `generation,timestamp_millis,values..` rows
+fn parse_history(csv: &str) -> Result<(u64, Vec<HistoryRow>), String> {
+    let mut lines = csv.lines().map(str::trim).filter(|line| !line.is_empty());
+
+    let stride = lines
+        .next()
+        .and_then(|line| line.strip_prefix("stride ="))
+        .and_then(|stride| stride.trim().parse().ok())
+        .ok_or("history: missing or invalid stride")?;
+
+    let rows = lines
+        .map(|line| {
+            let invalid = || format!("history: invalid row `{}`", line);
+            let mut fields = line.split(',');
+
+            Ok(HistoryRow {
+                generation: fields
+                    .next()
+                    .and_then(|field| field.parse().ok())
+                    .ok_or_else(invalid)?,
+                timestamp_millis: fields
+                    .next()
+                    .and_then(|field| field.parse().ok())
+                    .ok_or_else(invalid)?,
+                values: fields
+                    .map(|field| field.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid())?,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok((stride, rows))
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::Registry;
+    use crate::simulations::{epidemic, forest_fire, gray_scott};
+
+    fn session() -> Session {
+        let grid = Pattern {
+            rows: 8,
+            columns: 10,
+            cells: vec![(2, 3, 1), (7, 9, 2)],
+            rule: None,
+        };
+
+        let mut rng = Rng::new(42);
+        rng.next_u64();
+
+        Session {
+            rule: "B2/S/C3".to_string(),
+            grid,
+            state: "ant = 1, 2, North, 0\nant = 3, 4, West, 1".to_string(),
+            generation: 2,
+            seed: 42,
+            rng,
+            elapsed_millis: 1500,
+            camera: Camera {
+                cell_size: 12.5,
+                offset_x: -40.,
+                offset_y: 0.,
+            },
+            history_stride: 1,
+            history: vec![
+                HistoryRow {
+                    generation: 1,
+                    timestamp_millis: 700,
+                    values: vec![3., 0.5],
+                },
+                HistoryRow {
+                    generation: 2,
+                    timestamp_millis: 1400,
+                    values: vec![1., 2.],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn session_round_trips() {
+        let session = session();
+        let restored = Session::parse(&session.to_text()).unwrap();
+
+        assert_eq!(restored.rule, session.rule);
+        assert_eq!(restored.grid, session.grid);
+        assert_eq!(restored.state, session.state);
+        assert_eq!(restored.generation, session.generation);
+        assert_eq!(restored.seed, session.seed);
+        assert_eq!(restored.rng, session.rng);
+        assert_eq!(restored.elapsed_millis, session.elapsed_millis);
+        assert_eq!(restored.camera, session.camera);
+        assert_eq!(restored.history_stride, session.history_stride);
+        assert_eq!(restored.history, session.history);
+    }
+
+    #[test]
+    fn rejects_invalid_sessions() {
+        let text = session().to_text();
+
+        assert_eq!(
+            Session::parse(&text.replace("version = 2", "version = 3")).err(),
+            Some("unsupported session version 3".to_string())
+        );
+        assert_eq!(
+            Session::parse(&text.replace("seed = 42", "seed = lots")).err(),
+            Some("line 5: invalid seed `lots`".to_string())
+        );
+        assert_eq!(
+            Session::parse(&text.replace("x = 10, y = 8", "x = 10, y = many")).err(),
+            Some("grid: invalid size `many`".to_string())
+        );
+        assert!(Session::parse(&text.replace("1,700,3,0.5", "1,700,three")).is_err());
+        assert!(Session::parse("version = 2").is_err());
+    }
+
+    #[test]
+    fn reads_sessions_without_state() {
+        let session = session();
+        let text = session
+            .to_text()
+            .replace("version = 2", "version = 1")
+            .replace(&format!("{}\n{}\n", STATE_SECTION, session.state), "");
+        let restored = Session::parse(&text).unwrap();
+
+        assert_eq!(restored.grid, session.grid);
+        assert_eq!(restored.state, "");
+    }
+
+    #[test]
+    fn restored_simulations_carry_on() {
+        let registry = Registry::with_builtins();
+
+        for rule in [
+            "B3/S23",
+            "RL",
+            "W30",
+            "Lenia/R5/T10/M0.15/S0.015/B1",
+            gray_scott::PRESETS[0].1,
+            forest_fire::DEFAULT,
+            epidemic::DEFAULT,
+        ] {
+            let mut simulation = registry.parse(rule, 48, 48).unwrap();
+            let mut rng = Rng::new(7);
+            simulation.randomize(&mut rng, 0.3);
+            for _ in 0..20 {
+                simulation.step(&mut rng);
+            }
+
+            let mut saved = session();
+            saved.rule = simulation.rulestring();
+            saved.grid = Pattern::from_simulation(simulation.as_ref(), None);
+            assert!(!saved.grid.cells.is_empty(), "{}", rule);
+            saved.state = simulation.session_state();
+            saved.rng = rng;
+            let saved = Session::parse(&saved.to_text()).unwrap();
+
+            let mut restored = registry.parse(&saved.rule, 48, 48).unwrap();
+            saved.restore(restored.as_mut()).unwrap();
+            let mut restored_rng = saved.rng;
+            for _ in 0..5 {
+                simulation.step(&mut rng);
+                restored.step(&mut restored_rng);
+            }
+
+            assert_eq!(
+                Pattern::from_simulation(restored.as_ref(), None),
+                Pattern::from_simulation(simulation.as_ref(), None),
+                "{}",
+                rule
+            );
+        }
+    }
+}
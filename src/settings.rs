@@ -0,0 +1,327 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::charts::{DEFAULT_BUCKET_SIZE_GENERATIONS, DEFAULT_CHART_WINDOW_GENERATIONS};
+
+/// Settings file that is picked up from the working directory when no `--config` is given
+pub const DEFAULT_SETTINGS_PATH: &str = "automata.toml";
+const DEFAULT_ROWS: usize = 256;
+const DEFAULT_COLUMNS: usize = 256;
+// fraction of cells that are alive in a random soup
+const DEFAULT_DENSITY: f64 = 0.1;
+const DEFAULT_WINDOW_WIDTH: f32 = 1024.;
+const DEFAULT_WINDOW_HEIGHT: f32 = 1024.;
+const DEFAULT_FONT_SIZE: f32 = 24.;
+const DEFAULT_HEADLESS_GENERATIONS: u64 = 1000;
+const DEFAULT_EXPORT_PATH: &str = "automata-history";
+const DEFAULT_SESSION_PATH: &str = "automata.session";
+// anything bigger is almost certainly a typo, and would take gigabytes of memory
+const MAX_GRID_SIZE: usize = 4096;
+
+pub const USAGE: &str = "Usage: automata [OPTIONS]
+
+Options:
+  --config FILE          Load settings from FILE (default: automata.toml, if it exists)
+  --rule RULE            Initial rule, e.g. B36/S23 or B2/S34H
+  --size WxH             Grid size in cells, e.g. 512x512
+  --seed N               Seed for the random number generator
+  --density D            Fraction of cells alive in a random soup, between 0 and 1
+  --pattern FILE         Start from an .rle or .cells pattern instead of a random soup
+  --window WxH           Window size in pixels
+  --font-size N          Font size of the HUD
+  --chart-window N       Number of generations shown in the chart
+  --chart-bucket N       Number of generations averaged into each chart point
+  --theme FILE           Load a custom color theme
+  --session FILE         Continue a saved session, and save sessions to FILE (default: automata.session)
+  --headless             Run without a window and export the history
+  --generations N        Number of generations to run in headless mode
+  --export PATH          Where to export the history in headless mode
+  --help                 Show this message
+
+Every option except --config, --headless and --help can also be set in the settings file as `key = value`, with
+dashes replaced by underscores, e.g. `font_size = 18`.";
+
+/// Everything that can be configured from the settings file or the command line
+#[derive(Clone, PartialEq, Debug)]
+pub struct Settings {
+    pub rows: usize,
+    pub columns: usize,
+    /// Initial rule as written by the user, the default mode is used if not set
+    pub rule: Option<String>,
+    pub density: f64,
+    /// Taken from the clock if not set
+    pub seed: Option<u64>,
+    pub pattern: Option<PathBuf>,
+    pub window_width: f32,
+    pub window_height: f32,
+    pub font_size: f32,
+    pub chart_window: u64,
+    pub chart_bucket: u64,
+    pub theme: Option<PathBuf>,
+    /// Where sessions are saved to and loaded from
+    pub session_path: PathBuf,
+    /// Whether to continue the saved session instead of starting a new run
+    pub resume_session: bool,
+    pub headless: bool,
+    pub generations: u64,
+    pub export_path: PathBuf,
+    pub help: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            rows: DEFAULT_ROWS,
+            columns: DEFAULT_COLUMNS,
+            rule: None,
+            density: DEFAULT_DENSITY,
+            seed: None,
+            pattern: None,
+            window_width: DEFAULT_WINDOW_WIDTH,
+            window_height: DEFAULT_WINDOW_HEIGHT,
+            font_size: DEFAULT_FONT_SIZE,
+            chart_window: DEFAULT_CHART_WINDOW_GENERATIONS,
+            chart_bucket: DEFAULT_BUCKET_SIZE_GENERATIONS,
+            theme: None,
+            session_path: PathBuf::from(DEFAULT_SESSION_PATH),
+            resume_session: false,
+            headless: false,
+            generations: DEFAULT_HEADLESS_GENERATIONS,
+            export_path: PathBuf::from(DEFAULT_EXPORT_PATH),
+            help: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Load the settings file, then apply the command line arguments on top of it
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let config_path = match args.iter().position(|arg| arg == "--config") {
+            Some(idx) => Some(PathBuf::from(
+                args.get(idx + 1).ok_or("--config requires a value")?,
+            )),
+            None => {
+                let default_path = PathBuf::from(DEFAULT_SETTINGS_PATH);
+                (!cfg!(target_arch = "wasm32") && default_path.exists()).then_some(default_path)
+            }
+        };
+
+        let mut settings = match config_path {
+            Some(path) => Settings::from_file(&path)?,
+            None => Settings::default(),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let key = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("Unexpected argument `{}`, see --help", arg))?;
+
+            match key {
+                "config" => {
+                    // already loaded
+                    args.next();
+                }
+                "headless" => settings.headless = true,
+                "help" => settings.help = true,
+                _ => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("{} requires a value", arg))?;
+                    settings
+                        .apply(&key.replace('-', "_"), value)
+                        .map_err(|err| format!("{}: {}", arg, err))?;
+                }
+            }
+        }
+
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    /// Load settings from a file, see [Settings::parse] for the format
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read settings {}: {}", path.display(), err))?;
+
+        Settings::parse(&contents)
+            .map_err(|err| format!("Invalid settings {}: {}", path.display(), err))
+    }
+
+    /// Parse settings from `key = value` lines. Values may be quoted, lines starting with `#` are comments and
+    /// `[section]` headers are ignored, so that the file can be read as TOML too. Anything not specified keeps its
+    /// default value.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut settings = Settings::default();
+
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+
+            let line_number = idx + 1;
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", line_number))?;
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+
+            settings
+                .apply(key.trim(), value)
+                .map_err(|err| format!("line {}: {}", line_number, err))?;
+        }
+
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    /// Set a single setting from its textual value
+    fn apply(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "rule" => self.rule = Some(value.to_string()),
+            "size" => (self.columns, self.rows) = parse_size(value)?,
+            "seed" => self.seed = Some(parse_number(value)?),
+            "density" => self.density = parse_number(value)?,
+            "pattern" => self.pattern = Some(PathBuf::from(value)),
+            "window" => (self.window_width, self.window_height) = parse_size(value)?,
+            "font_size" => self.font_size = parse_number(value)?,
+            "chart_window" => self.chart_window = parse_number(value)?,
+            "chart_bucket" => self.chart_bucket = parse_number(value)?,
+            "theme" => self.theme = Some(PathBuf::from(value)),
+            "session" => {
+                self.session_path = PathBuf::from(value);
+                self.resume_session = true;
+            }
+            "generations" => self.generations = parse_number(value)?,
+            "export" => self.export_path = PathBuf::from(value),
+            other => return Err(format!("unknown setting `{}`", other)),
+        }
+
+        Ok(())
+    }
+
+    /// Check the settings that are valid numbers but make no sense
+    fn validate(&self) -> Result<(), String> {
+        if !(3..=MAX_GRID_SIZE).contains(&self.rows) || !(3..=MAX_GRID_SIZE).contains(&self.columns)
+        {
+            return Err(format!(
+                "Grid size must be between 3 and {} cells on each side",
+                MAX_GRID_SIZE
+            ));
+        }
+
+        if !(0. ..=1.).contains(&self.density) {
+            return Err("Density must be between 0 and 1".to_string());
+        }
+
+        if self.window_width <= 0. || self.window_height <= 0. || self.font_size <= 0. {
+            return Err("Window and font sizes must be positive".to_string());
+        }
+
+        if self.chart_bucket == 0 || self.chart_window < self.chart_bucket {
+            return Err(
+                "Chart bucket must be at least 1 and no bigger than the chart window".to_string(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{}` is not a valid number", value))
+}
+
+/// Parse a size written as `WIDTHxHEIGHT`
+fn parse_size<T: std::str::FromStr>(value: &str) -> Result<(T, T), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("`{}` is not a valid size, expected WIDTHxHEIGHT", value))?;
+
+    Ok((parse_number(width)?, parse_number(height)?))
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_settings_files() {
+        let settings = Settings::parse(
+            "# bigger grid\n\
+             [grid]\n\
+             size = 512x384\n\
+             rule = \"B36/S23\"\n\
+             density = 0.25\n\
+             font_size = 18\n",
+        )
+        .unwrap();
+
+        assert_eq!((settings.columns, settings.rows), (512, 384));
+        assert_eq!(settings.rule.as_deref(), Some("B36/S23"));
+        assert_eq!(settings.density, 0.25);
+        assert_eq!(settings.font_size, 18.);
+        assert_eq!(settings.chart_window, DEFAULT_CHART_WINDOW_GENERATIONS);
+    }
+
+    #[test]
+    fn parses_command_line() {
+        let settings = Settings::from_args(&args(&[
+            "--rule",
+            "B36/S23",
+            "--size",
+            "512x512",
+            "--seed",
+            "42",
+            "--pattern",
+            "glider.rle",
+            "--chart-window",
+            "500",
+            "--headless",
+        ]))
+        .unwrap();
+
+        assert_eq!(settings.rule.as_deref(), Some("B36/S23"));
+        assert_eq!((settings.rows, settings.columns), (512, 512));
+        assert_eq!(settings.seed, Some(42));
+        assert_eq!(settings.pattern, Some(PathBuf::from("glider.rle")));
+        assert_eq!(settings.chart_window, 500);
+        assert!(settings.headless);
+    }
+
+    #[test]
+    fn reports_invalid_settings() {
+        assert_eq!(
+            Settings::parse("size = 512x512\nsparkles = 3"),
+            Err("line 2: unknown setting `sparkles`".to_string())
+        );
+        assert_eq!(
+            Settings::parse("size = 512"),
+            Err("line 1: `512` is not a valid size, expected WIDTHxHEIGHT".to_string())
+        );
+        assert_eq!(
+            Settings::from_args(&args(&["--seed", "forty-two"])),
+            Err("--seed: `forty-two` is not a valid number".to_string())
+        );
+        assert_eq!(
+            Settings::from_args(&args(&["--density", "2"])),
+            Err("Density must be between 0 and 1".to_string())
+        );
+        assert!(Settings::from_args(&args(&["--seed"])).is_err());
+        assert!(Settings::from_args(&args(&["glider.rle"])).is_err());
+        assert!(Settings::from_args(&args(&["--size", "0x0"])).is_err());
+    }
+}
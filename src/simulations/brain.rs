@@ -1,3 +1,5 @@
+use crate::automaton::Automaton;
+use crate::neighborhood::MOORE;
 use crate::util::count_cells;
 use crate::{CellState, SimulationState};
 
@@ -14,10 +16,35 @@ pub fn get_brain_next_cell_state(state: &SimulationState, row: usize, column: us
     }
 
     // if there are
-    let target_count = count_cells(state, row, column, |target| target == CellState::Alive);
+    let target_count = count_cells(state, &MOORE, row, column, |target| {
+        target == CellState::Alive
+    });
     if target_count == 2 {
         CellState::Alive
     } else {
         CellState::Dead
     }
 }
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BriansBrain;
+
+impl Automaton for BriansBrain {
+    type State = CellState;
+
+    fn name(&self) -> String {
+        "Brian's Brain".to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        "B2/S/C3".to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        3
+    }
+
+    fn next_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        get_brain_next_cell_state(state, row, column)
+    }
+}
@@ -0,0 +1,550 @@
+use std::fmt::Display;
+use std::fmt::Write as _;
+
+use crate::automaton::{GenerationCounts, Parameter, Rgba, Simulation};
+use crate::fft::{Complex, Fft2d};
+use crate::grid::Grid;
+use crate::patterns::Pattern;
+use crate::util::Rng;
+
+/// Number of levels that values are rounded to when they are passed around as cell states, e.g. in patterns and
+/// sessions. State 0 is a value of 0, and the last state a value of 1.
+pub const LEVELS: usize = 256;
+
+/// Cells with at least this value count as alive
+const ALIVE_THRESHOLD: f32 = 0.5;
+
+/// A convolution kernel: weights of the cells around a cell, which add up to 1, so that convolving a grid with it
+/// gives a weighted average of the neighborhood of every cell
+#[derive(Clone, PartialEq, Debug)]
+pub struct Kernel {
+    /// Furthest offset from the center in rows or columns
+    reach: usize,
+    /// Offsets with a weight above 0, as (rows, columns, weight)
+    weights: Vec<(isize, isize, f32)>,
+}
+
+impl Kernel {
+    /// A kernel out to the given number of cells from the center in every direction, where the weight of a cell is
+    /// a function of its distance from the center, in cells. Panics if every weight is 0.
+    pub fn new<F>(reach: usize, weight: F) -> Self
+    where
+        F: Fn(f32) -> f32,
+    {
+        let reach_offset = reach as isize;
+        let mut weights: Vec<(isize, isize, f32)> = (-reach_offset..=reach_offset)
+            .flat_map(|row| (-reach_offset..=reach_offset).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let distance = ((row * row + column * column) as f32).sqrt();
+                (row, column, weight(distance).max(0.))
+            })
+            .filter(|&(.., weight)| weight > 0.)
+            .collect();
+
+        let total: f32 = weights.iter().map(|&(.., weight)| weight).sum();
+        assert!(total > 0., "Kernel must have a weight above 0");
+        for (.., weight) in weights.iter_mut() {
+            *weight /= total;
+        }
+
+        Self { reach, weights }
+    }
+
+    pub fn reach(&self) -> usize {
+        self.reach
+    }
+
+    pub fn weights(&self) -> &[(isize, isize, f32)] {
+        &self.weights
+    }
+
+    /// The weighted average around a single cell, wrapping around the edges of the grid
+    pub fn apply_at(&self, grid: &Grid<f32>, row: usize, column: usize) -> f32 {
+        let (rows, columns) = (grid.rows() as isize, grid.columns() as isize);
+
+        self.weights
+            .iter()
+            .map(|&(row_offset, column_offset, weight)| {
+                let r = (row as isize + row_offset).rem_euclid(rows) as usize;
+                let c = (column as isize + column_offset).rem_euclid(columns) as usize;
+                grid[r][c] * weight
+            })
+            .sum()
+    }
+}
+
+/// Convolves a grid with a set of kernels at once, through the Fourier transform, which takes the same time however
+/// large the kernels are. The grid wraps around its edges.
+///
+/// The FFT works on sizes that are powers of two and wraps around at that size. Grids that have such a size are
+/// transformed as they are, while other grids are copied into a larger power of two with the cells from across the
+/// edges around them, deep enough for the largest kernel.
+pub struct Convolution {
+    rows: usize,
+    columns: usize,
+    /// Cells from across the edges that are copied around the grid, 0 for grids that are powers of two
+    margin: usize,
+    fft: Fft2d,
+    padded_rows: usize,
+    padded_columns: usize,
+    /// The transform of every kernel
+    spectra: Vec<Vec<Complex>>,
+    /// Reused between generations
+    buffer: Vec<Complex>,
+    product: Vec<Complex>,
+}
+
+impl Convolution {
+    pub fn new(rows: usize, columns: usize, kernels: &[Kernel]) -> Self {
+        let margin = if rows.is_power_of_two() && columns.is_power_of_two() {
+            0
+        } else {
+            kernels.iter().map(Kernel::reach).max().unwrap_or(0)
+        };
+        let (padded_rows, padded_columns) = (
+            (rows + 2 * margin).next_power_of_two(),
+            (columns + 2 * margin).next_power_of_two(),
+        );
+        let fft = Fft2d::new(padded_rows, padded_columns);
+
+        let spectra = kernels
+            .iter()
+            .map(|kernel| {
+                let mut spectrum = vec![Complex::default(); padded_rows * padded_columns];
+                for &(row, column, weight) in kernel.weights() {
+                    let r = row.rem_euclid(padded_rows as isize) as usize;
+                    let c = column.rem_euclid(padded_columns as isize) as usize;
+                    spectrum[r * padded_columns + c].re += weight;
+                }
+                fft.transform(&mut spectrum, false);
+                spectrum
+            })
+            .collect();
+
+        Self {
+            rows,
+            columns,
+            margin,
+            fft,
+            padded_rows,
+            padded_columns,
+            spectra,
+            buffer: vec![Complex::default(); padded_rows * padded_columns],
+            product: vec![Complex::default(); padded_rows * padded_columns],
+        }
+    }
+
+    /// The grid convolved with every kernel, in the order the kernels were given in
+    pub fn apply(&mut self, grid: &Grid<f32>) -> Vec<Grid<f32>> {
+        assert!(
+            grid.rows() == self.rows && grid.columns() == self.columns,
+            "Expected a {}x{} grid",
+            self.rows,
+            self.columns
+        );
+
+        // the grid, with its margin, in the upper left corner
+        let (rows, columns, margin) = (self.rows, self.columns, self.margin);
+        self.buffer.fill(Complex::default());
+        for r in 0..(rows + 2 * margin).min(self.padded_rows) {
+            let row = &grid[(r + rows - margin % rows) % rows];
+            let padded_row = &mut self.buffer[r * self.padded_columns..];
+            for c in 0..(columns + 2 * margin).min(self.padded_columns) {
+                padded_row[c].re = row[(c + columns - margin % columns) % columns];
+            }
+        }
+        self.fft.transform(&mut self.buffer, false);
+
+        self.spectra
+            .iter()
+            .map(|spectrum| {
+                for ((product, &value), &weight) in
+                    self.product.iter_mut().zip(&self.buffer).zip(spectrum)
+                {
+                    *product = value * weight;
+                }
+                self.fft.transform(&mut self.product, true);
+
+                let mut potential = Grid::new(rows, columns, 0.);
+                for (r, row) in potential.iter_rows_mut().enumerate() {
+                    let start = (r + margin) * self.padded_columns + margin;
+                    for (cell, value) in row.iter_mut().zip(&self.product[start..start + columns]) {
+                        *cell = value.re;
+                    }
+                }
+                potential
+            })
+            .collect()
+    }
+}
+
+/// A rule over cells that hold a value between 0 and 1 instead of one of a few states. Every generation, the grid is
+/// convolved with the rule's kernels, and each cell's next value is computed from its value and the weighted averages
+/// around it. The rulestring is the rule's [Display], and has the current values of its parameters in it.
+pub trait ContinuousRule: Display {
+    fn name(&self) -> String;
+
+    /// Kernels to convolve the grid with. They only change when a parameter does.
+    fn kernels(&self) -> Vec<Kernel>;
+
+    /// What a cell turns into, given its value and its potentials: the weighted averages around it, one for each
+    /// kernel
+    fn next_value(&self, value: f32, potentials: &[f32]) -> f32;
+
+    /// See [Simulation::parameters]
+    fn parameters(&self) -> Vec<Parameter>;
+
+    /// See [Simulation::set_parameter]
+    fn set_parameter(&mut self, idx: usize, value: f64);
+
+    /// Replace the grid with a random soup, see [Simulation::randomize]
+    fn randomize(&self, grid: &mut Grid<f32>, rng: &mut Rng, density: f64);
+}
+
+/// Runs a [ContinuousRule] on a grid of values between 0 and 1. Cells count as alive from a value of 0.5, and as dying
+/// below it, so that statistics and analysis work like they do for discrete rules. As cell states, values are rounded
+/// to one of [LEVELS] levels.
+pub struct ContinuousRunner<R: ContinuousRule> {
+    rule: R,
+    grid: Grid<f32>,
+    buffer: Grid<f32>,
+    kernels: Vec<Kernel>,
+    convolution: Convolution,
+}
+
+impl<R: ContinuousRule> ContinuousRunner<R> {
+    /// Create a runner with every cell at 0
+    pub fn new(rule: R, rows: usize, columns: usize) -> Self {
+        let kernels = rule.kernels();
+
+        Self {
+            convolution: Convolution::new(rows, columns, &kernels),
+            kernels,
+            rule,
+            grid: Grid::new(rows, columns, 0.),
+            buffer: Grid::new(rows, columns, 0.),
+        }
+    }
+
+    pub fn rule(&self) -> &R {
+        &self.rule
+    }
+
+    pub fn grid(&self) -> &Grid<f32> {
+        &self.grid
+    }
+
+    pub fn grid_mut(&mut self) -> &mut Grid<f32> {
+        &mut self.grid
+    }
+}
+
+impl<R: ContinuousRule> Simulation for ContinuousRunner<R> {
+    fn name(&self) -> String {
+        self.rule.name()
+    }
+
+    fn rulestring(&self) -> String {
+        self.rule.to_string()
+    }
+
+    fn rows(&self) -> usize {
+        self.grid.rows()
+    }
+
+    fn columns(&self) -> usize {
+        self.grid.columns()
+    }
+
+    fn state_count(&self) -> usize {
+        LEVELS
+    }
+
+    fn step(&mut self, _rng: &mut Rng) -> GenerationCounts {
+        let potentials = self.convolution.apply(&self.grid);
+
+        let mut counts = GenerationCounts::default();
+        let mut cell_potentials = vec![0.; potentials.len()];
+        for row in 0..self.grid.rows() {
+            for column in 0..self.grid.columns() {
+                for (cell_potential, potential) in cell_potentials.iter_mut().zip(&potentials) {
+                    *cell_potential = potential[row][column];
+                }
+
+                let value = self.grid[row][column];
+                let next_value = self.rule.next_value(value, &cell_potentials).clamp(0., 1.);
+                self.buffer[row][column] = next_value;
+
+                let (was_alive, is_alive) =
+                    (value >= ALIVE_THRESHOLD, next_value >= ALIVE_THRESHOLD);
+                if is_alive {
+                    counts.alive += 1;
+                } else if to_state(next_value) != 0 {
+                    counts.dying += 1;
+                }
+                match (was_alive, is_alive) {
+                    (false, true) => counts.births += 1,
+                    (true, false) => counts.deaths += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        std::mem::swap(&mut self.grid, &mut self.buffer);
+
+        counts
+    }
+
+    fn state(&self, row: usize, column: usize) -> u8 {
+        to_state(self.grid[row][column])
+    }
+
+    fn write_state(&mut self, row: usize, column: usize, state: u8) {
+        self.grid[row][column] = state as f32 / (LEVELS - 1) as f32;
+    }
+
+    fn clear(&mut self) {
+        self.grid.fill(0.);
+    }
+
+    fn randomize(&mut self, rng: &mut Rng, density: f64) {
+        self.rule.randomize(&mut self.grid, rng, density);
+    }
+
+    fn is_alive(&self, row: usize, column: usize) -> bool {
+        self.grid[row][column] >= ALIVE_THRESHOLD
+    }
+
+    fn next_state(&self, row: usize, column: usize) -> u8 {
+        let potentials: Vec<f32> = self
+            .kernels
+            .iter()
+            .map(|kernel| kernel.apply_at(&self.grid, row, column))
+            .collect();
+
+        to_state(
+            self.rule
+                .next_value(self.grid[row][column], &potentials)
+                .clamp(0., 1.),
+        )
+    }
+
+    /// Live cells that the first kernel gives a weight to
+    fn count_neighbors(&self, row: usize, column: usize) -> u32 {
+        let (rows, columns) = (self.grid.rows() as isize, self.grid.columns() as isize);
+
+        self.kernels.first().map_or(0, |kernel| {
+            kernel
+                .weights()
+                .iter()
+                .filter(|&&(row_offset, column_offset, _)| {
+                    let r = (row as isize + row_offset).rem_euclid(rows) as usize;
+                    let c = (column as isize + column_offset).rem_euclid(columns) as usize;
+                    (r, c) != (row, column) && self.grid[r][c] >= ALIVE_THRESHOLD
+                })
+                .count() as u32
+        })
+    }
+
+    fn state_name(&self, state: u8) -> String {
+        format!("{:.2}", state as f32 / (LEVELS - 1) as f32)
+    }
+
+    /// Drawn with the theme's colormap instead
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        None
+    }
+
+    fn starts_empty(&self) -> bool {
+        false
+    }
+
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        Vec::new()
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        self.rule.parameters()
+    }
+
+    /// Kernels are rebuilt, since most parameters change them
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        self.rule.set_parameter(idx, value);
+
+        self.kernels = self.rule.kernels();
+        self.convolution = Convolution::new(self.grid.rows(), self.grid.columns(), &self.kernels);
+    }
+
+    /// The values of the cells, which the cell states only keep to a level
+    fn session_state(&self) -> String {
+        grid_session_state("cells", &self.grid)
+    }
+
+    fn restore_session_state(&mut self, state: &str) -> Result<(), String> {
+        if state.trim().is_empty() {
+            return Ok(());
+        }
+
+        restore_grid(state, "cells", &mut self.grid)
+    }
+
+    fn is_continuous(&self) -> bool {
+        true
+    }
+}
+
+/// Round a value between 0 and 1 to the nearest level
+pub(crate) fn to_state(value: f32) -> u8 {
+    (value * (LEVELS - 1) as f32).round() as u8
+}
+
+/// Every value of the grid at full precision, since cell states are rounded to levels, as a `key = ` line of comma
+/// separated values for every row. See [Simulation::session_state].
+pub fn grid_session_state(key: &str, grid: &Grid<f32>) -> String {
+    let mut state = String::new();
+    for row in grid.iter_rows() {
+        let values: Vec<String> = row.iter().map(f32::to_string).collect();
+        let _ = writeln!(state, "{} = {}", key, values.join(","));
+    }
+
+    state
+}
+
+/// Read the values of a grid back from the lines that [grid_session_state] wrote with the given key
+pub fn restore_grid(state: &str, key: &str, grid: &mut Grid<f32>) -> Result<(), String> {
+    let rows: Vec<&str> = state
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(line_key, _)| line_key.trim() == key)
+        .map(|(_, values)| values)
+        .collect();
+    if rows.len() != grid.rows() {
+        return Err(format!(
+            "expected {} rows of {}, found {}",
+            grid.rows(),
+            key,
+            rows.len()
+        ));
+    }
+
+    for (values, row) in rows.into_iter().zip(grid.iter_rows_mut()) {
+        let values = values
+            .split(',')
+            .map(|value| {
+                value
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|value| (0. ..=1.).contains(value))
+            })
+            .collect::<Option<Vec<_>>>()
+            .filter(|values| values.len() == row.len())
+            .ok_or_else(|| format!("invalid row of {} `{}`", key, values.trim()))?;
+        row.copy_from_slice(&values);
+    }
+
+    Ok(())
+}
+
+/// Clear the grid and scatter square patches of the given side over it, at random places, until about `density` of
+/// the grid is covered. Every cell of a patch gets a value from the given function. Patches wrap around the edges.
+pub fn scatter_patches<F>(
+    grid: &mut Grid<f32>,
+    rng: &mut Rng,
+    density: f64,
+    side: usize,
+    mut value: F,
+) where
+    F: FnMut(&mut Rng) -> f32,
+{
+    grid.fill(0.);
+
+    let (rows, columns) = (grid.rows(), grid.columns());
+    let side = side.clamp(1, rows.min(columns));
+    let patches = (density * (rows * columns) as f64 / (side * side) as f64).round() as usize;
+    for _ in 0..patches {
+        let (top, left) = (
+            rng.next_u64() as usize % rows,
+            rng.next_u64() as usize % columns,
+        );
+        for row in top..top + side {
+            for column in left..left + side {
+                grid[row % rows][column % columns] = value(rng);
+            }
+        }
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring() -> Kernel {
+        Kernel::new(2, |distance| if distance > 1.5 { 1. } else { 0. })
+    }
+
+    #[test]
+    fn kernels_are_normalized() {
+        let kernel = ring();
+        let total: f32 = kernel.weights().iter().map(|&(.., weight)| weight).sum();
+
+        assert!((total - 1.).abs() < 1e-6);
+        // the corners of the 5x5 square and the cells 2 away along the axes, but nothing closer
+        assert_eq!(kernel.weights().len(), 4 + 4 + 8);
+        assert!(!kernel
+            .weights()
+            .iter()
+            .any(|&(row, column, _)| (row, column) == (1, 1)));
+    }
+
+    #[test]
+    fn convolution_matches_the_direct_sum() {
+        // a power of two, and a size that needs a margin
+        for (rows, columns) in [(16, 16), (12, 10)] {
+            let mut grid = Grid::new(rows, columns, 0.);
+            let mut rng = Rng::new(7);
+            for row in grid.iter_rows_mut() {
+                for cell in row.iter_mut() {
+                    *cell = rng.next_f64() as f32;
+                }
+            }
+
+            let kernels = [ring(), Kernel::new(3, |distance| 3. - distance)];
+            let potentials = Convolution::new(rows, columns, &kernels).apply(&grid);
+
+            for (kernel, potential) in kernels.iter().zip(&potentials) {
+                for (row, column) in [(0, 0), (5, 9), (rows - 1, columns - 1)] {
+                    let expected = kernel.apply_at(&grid, row, column);
+                    assert!(
+                        (potential[row][column] - expected).abs() < 1e-4,
+                        "{}x{} at ({}, {}): {} != {}",
+                        rows,
+                        columns,
+                        row,
+                        column,
+                        potential[row][column],
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scatters_patches() {
+        let mut grid = Grid::new(64, 64, 1.);
+        scatter_patches(&mut grid, &mut Rng::new(1), 0.25, 8, |_| 0.5);
+
+        let covered = grid
+            .iter_rows()
+            .flatten()
+            .filter(|&&cell| cell == 0.5)
+            .count();
+        assert!(covered > 0 && covered <= 64 * 64 / 4, "{}", covered);
+        assert!(grid
+            .iter_rows()
+            .flatten()
+            .all(|&cell| cell == 0. || cell == 0.5));
+    }
+}
@@ -1,12 +1,14 @@
+use crate::automaton::Automaton;
+use crate::neighborhood::MOORE;
 use crate::util::count_cells;
 use crate::{CellState, SimulationState};
 
 // Conway's Game of Life
 pub fn get_conway_next_cell_state(state: &SimulationState, row: usize, column: usize) -> CellState {
-    let live_cell_count = count_cells(state, row, column, |cell| cell == CellState::Alive);
+    let live_cell_count = count_cells(state, &MOORE, row, column, |cell| cell == CellState::Alive);
 
     // underpopulated or overpopulated
-    if live_cell_count < 2 || live_cell_count > 3 {
+    if !(2..=3).contains(&live_cell_count) {
         return CellState::Dead;
     }
 
@@ -18,3 +20,26 @@ pub fn get_conway_next_cell_state(state: &SimulationState, row: usize, column: u
     // unchanged otherwise
     state[row][column]
 }
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConwaysLife;
+
+impl Automaton for ConwaysLife {
+    type State = CellState;
+
+    fn name(&self) -> String {
+        "Conway's Game of Life".to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        "B3/S23".to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        2
+    }
+
+    fn next_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        get_conway_next_cell_state(state, row, column)
+    }
+}
@@ -0,0 +1,306 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Automaton, Rgba, Runner, Simulation};
+use crate::grid::Grid;
+use crate::neighborhood::{Neighborhood, MAX_RADIUS};
+use crate::registry::Notation;
+use crate::simulations::larger_than_life::{parse_count, RangeParts};
+use crate::util::{count_cells, Rng};
+
+/// Well known cyclic rules: "313" turns a soup into spirals on the Moore neighborhood, the 14 state rule on the von
+/// Neumann neighborhood is the one Griffeath made famous, and rock-paper-scissors-lizard-Spock has five species that
+/// each beat two others
+pub const PRESETS: [(&str, &str); 3] = [
+    ("Cyclic 313", "R1/T3/C3/NM"),
+    ("Cyclic 14 colors", "R1/T1/C14/NN"),
+    ("Rock-paper-scissors", "RPS/R1/T3/C5/NM"),
+];
+
+/// A cyclic cellular automaton, written in MCell's notation, e.g. `R1/T3/C3/NM`:
+/// - `R` is the range of the neighborhood
+/// - `T` the threshold, the number of neighbors in the successor state it takes for a cell to advance to it
+/// - `C` the number of states, which cells cycle through from 0 to the last one and back to 0
+/// - `NM` is the Moore (square) neighborhood and `NN` the von Neumann (diamond) one
+///
+/// With an `RPS/` prefix, it is the rock-paper-scissors variant instead: every state is beaten by the `(C - 1) / 2`
+/// states after it, and a cell is taken over by the one of those with the most neighbors, as long as there are at
+/// least as many as the threshold. With 3 states, both are the same.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CyclicRule {
+    parameters: RangeParameters,
+    rock_paper_scissors: bool,
+    /// Offsets of the neighborhood, kept around so that they aren't computed for every cell
+    offsets: Vec<(isize, isize)>,
+}
+
+impl Notation for CyclicRule {
+    const PRESETS: &'static [(&'static str, &'static str)] = &PRESETS;
+
+    /// Whether the rulestring is meant to be in this notation. Larger than Life rules also start with `R` and a
+    /// digit, but separate their parts with commas.
+    fn matches(rulestring: &str) -> bool {
+        let rulestring = rulestring.trim();
+        let mut chars = rulestring.chars();
+
+        rulestring.starts_with("RPS/")
+            || (chars.next() == Some('R')
+                && chars.next().is_some_and(|c| c.is_ascii_digit())
+                && rulestring.contains('/'))
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let trimmed = rulestring.trim();
+        let (rock_paper_scissors, parts) = match trimmed.strip_prefix("RPS/") {
+            Some(parts) => (true, parts),
+            None => (false, trimmed),
+        };
+
+        let parameters = RangeParameters::parse(rulestring, parts, 2)?;
+        Ok(Self {
+            offsets: parameters.neighborhood().offsets(),
+            parameters,
+            rock_paper_scissors,
+        })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(Runner::new(self, rows, columns))
+    }
+}
+
+impl CyclicRule {
+    /// States that take over cells in the given state, nearest first
+    fn predators(&self, state: u8) -> impl Iterator<Item = u8> + '_ {
+        let states = self.parameters.states;
+        let count = if self.rock_paper_scissors {
+            ((states - 1) / 2).max(1)
+        } else {
+            1
+        };
+
+        (1..=count).map(move |step| ((state as usize + step) % states) as u8)
+    }
+}
+
+impl Automaton for CyclicRule {
+    type State = u8;
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        self.to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        self.parameters.states
+    }
+
+    fn next_state(&self, grid: &Grid<u8>, row: usize, column: usize) -> u8 {
+        let state = grid[row][column];
+
+        // the predator with the most neighbors wins, and the nearest one breaks ties
+        let (count, predator) = self
+            .predators(state)
+            .map(|predator| {
+                let count = count_cells(grid, &self.offsets, row, column, |cell| cell == predator);
+                (count, predator)
+            })
+            .fold((0, state), |best, candidate| {
+                if candidate.0 > best.0 {
+                    candidate
+                } else {
+                    best
+                }
+            });
+
+        if count >= self.parameters.threshold {
+            predator
+        } else {
+            state
+        }
+    }
+
+    fn neighborhood(&self) -> Neighborhood {
+        self.parameters.neighborhood()
+    }
+
+    /// Neighbors in the successor state, which is what decides whether the cell advances
+    fn count_neighbors(&self, grid: &Grid<u8>, row: usize, column: usize) -> u32 {
+        let successor = ((grid[row][column] as usize + 1) % self.parameters.states) as u8;
+
+        count_cells(grid, &self.offsets, row, column, |cell| cell == successor)
+    }
+
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        Some(Rgba::rainbow(self.parameters.states))
+    }
+
+    /// Soups need every state in equal measure, so the density is ignored
+    fn random_state(&self, rng: &mut Rng, _density: f64) -> u8 {
+        (rng.next_u64() % self.parameters.states as u64) as u8
+    }
+}
+
+impl Display for CyclicRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.rock_paper_scissors {
+            write!(f, "RPS/")?;
+        }
+
+        write!(f, "{}", self.parameters)
+    }
+}
+
+/// The parts of MCell's notation that cyclic rules share with other excitable media, such as
+/// [crate::simulations::greenberg_hastings::GreenbergHastings]: `R<range>/T<threshold>/C<states>/N<M or N>`
+#[derive(Clone, PartialEq, Debug)]
+pub struct RangeParameters {
+    pub range: usize,
+    pub threshold: u32,
+    pub states: usize,
+    pub von_neumann: bool,
+}
+
+impl RangeParameters {
+    /// Parse the parts of the rulestring, which is only used for error messages. Rules need at least `min_states`
+    /// states.
+    pub fn parse(rulestring: &str, parts: &str, min_states: usize) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+
+        let mut threshold = None;
+        let parts = RangeParts::parse(rulestring, parts.split('/'), |key, value| match key {
+            'T' => {
+                threshold = Some(parse_count(value, 'T')? as u32);
+                Ok(true)
+            }
+            _ => Ok(false),
+        })?;
+
+        let range = parts.radius.ok_or_else(|| invalid("missing range"))?;
+        if !(1..=MAX_RADIUS).contains(&range) {
+            return Err(invalid(&format!(
+                "the range must be between 1 and {}",
+                MAX_RADIUS
+            )));
+        }
+        let states = parts
+            .states
+            .ok_or_else(|| invalid("missing number of states"))?;
+        if !(min_states..=256).contains(&states) {
+            return Err(invalid(&format!(
+                "the number of states must be between {} and 256",
+                min_states
+            )));
+        }
+
+        let parameters = Self {
+            range,
+            threshold: threshold.ok_or_else(|| invalid("missing threshold"))?,
+            states,
+            von_neumann: parts.von_neumann,
+        };
+        let size = parameters.neighborhood().offsets().len();
+        if !(1..=size as u32).contains(&parameters.threshold) {
+            return Err(invalid(&format!(
+                "the threshold must be between 1 and the {} cells of the neighborhood",
+                size
+            )));
+        }
+
+        Ok(parameters)
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        if self.von_neumann {
+            Neighborhood::VonNeumann(self.range)
+        } else {
+            Neighborhood::Moore(self.range)
+        }
+    }
+}
+
+impl Display for RangeParameters {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "R{}/T{}/C{}/N{}",
+            self.range,
+            self.threshold,
+            self.states,
+            if self.von_neumann { 'N' } else { 'M' }
+        )
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rulestrings() {
+        for (_, rulestring) in PRESETS {
+            assert_eq!(
+                CyclicRule::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        assert!(CyclicRule::parse("R1/T9/C3/NM").is_err());
+        assert!(CyclicRule::parse("R1/T1/C1/NM").is_err());
+        assert!(CyclicRule::parse("R1/C3/NM").is_err());
+        assert!(CyclicRule::parse("R1/T3/C3/é").is_err());
+        assert!(CyclicRule::matches("R2/T5/C8/NN"));
+        assert!(!CyclicRule::matches("R5,C0,M1,S34..58,B34..45,NM"));
+    }
+
+    #[test]
+    fn cells_advance_at_the_threshold() {
+        let rule = CyclicRule::parse("R1/T2/C4/NN").unwrap();
+        let mut grid = Grid::new(3, 3, 3);
+        grid[0][1] = 0;
+
+        // the last state is followed by the first one
+        assert_eq!(rule.next_state(&grid, 1, 1), 3);
+        grid[1][0] = 0;
+        assert_eq!(rule.next_state(&grid, 1, 1), 0);
+        assert_eq!(rule.count_neighbors(&grid, 1, 1), 2);
+    }
+
+    #[test]
+    fn rock_paper_scissors_picks_the_strongest_predator() {
+        let rule = CyclicRule::parse("RPS/R1/T2/C5/NM").unwrap();
+        let mut grid = Grid::new(3, 3, 0);
+        // 1 and 2 both beat 0, 3 doesn't
+        for (row, column, state) in [
+            (0, 0, 1),
+            (0, 1, 2),
+            (0, 2, 2),
+            (2, 0, 3),
+            (2, 1, 3),
+            (2, 2, 3),
+        ] {
+            grid[row][column] = state;
+        }
+
+        assert_eq!(rule.next_state(&grid, 1, 1), 2);
+        grid[0][2] = 1;
+        assert_eq!(rule.next_state(&grid, 1, 1), 1);
+    }
+
+    #[test]
+    fn soups_keep_cycling() {
+        for (_, rulestring) in PRESETS {
+            let mut simulation = Runner::new(CyclicRule::parse(rulestring).unwrap(), 64, 64);
+            simulation.randomize(&mut Rng::new(3), 0.5);
+
+            let changes = (0..100)
+                .map(|_| simulation.step(&mut Rng::new(0)))
+                .last()
+                .unwrap();
+            assert!(changes.births + changes.deaths > 0, "{}", rulestring);
+        }
+    }
+}
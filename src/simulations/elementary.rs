@@ -0,0 +1,410 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{GenerationCounts, Rgba, Simulation};
+use crate::grid::Grid;
+use crate::patterns::Pattern;
+use crate::registry::Notation;
+use crate::util::Rng;
+
+/// Well known elementary automata: Rule 30 is chaotic, Rule 90 draws a Sierpinski triangle and Rule 110 is Turing
+/// complete
+pub const PRESETS: [(&str, &str); 3] =
+    [("Rule 30", "W30"), ("Rule 90", "W90"), ("Rule 110", "W110")];
+
+/// Most colors of a totalistic rule, so that every code fits in 64 bits
+pub const MAX_COLORS: usize = 7;
+
+/// A one-dimensional rule, where every cell of a row is computed from the cell above it and the two cells next to
+/// that one. Written either as a Wolfram code like `W30`, whose bits give the next state of each of the 8 possible
+/// neighborhoods of two states, or as a totalistic code with a number of colors, like `T777,K3`, whose digits in base
+/// `K` give the next color for each sum of the colors of the three cells, starting from the lowest digit.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ElementaryRule {
+    code: u64,
+    /// Colors of a totalistic rule, or `None` for a Wolfram code
+    totalistic_colors: Option<usize>,
+    /// Next state, indexed by the neighborhood read as binary for Wolfram codes, or by its sum for totalistic codes
+    table: Vec<u8>,
+}
+
+impl Notation for ElementaryRule {
+    const PRESETS: &'static [(&'static str, &'static str)] = &PRESETS;
+
+    /// Whether the rulestring is meant to be a one-dimensional rule
+    fn matches(rulestring: &str) -> bool {
+        let mut chars = rulestring.trim().chars();
+        matches!(chars.next(), Some('W' | 'T')) && chars.next().is_some_and(|c| c.is_ascii_digit())
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+        let number = |digits: &str, what: &str| {
+            digits
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| invalid(&format!("the {} must be a number", what)))
+        };
+
+        let rulestring = rulestring.trim();
+        if let Some(code) = rulestring.strip_prefix('W') {
+            let code = number(code, "Wolfram code")?;
+            if code > 255 {
+                return Err(invalid("Wolfram codes go from 0 to 255"));
+            }
+
+            let table = (0..8)
+                .map(|neighborhood| ((code >> neighborhood) & 1) as u8)
+                .collect();
+            return Ok(Self {
+                code,
+                totalistic_colors: None,
+                table,
+            });
+        }
+
+        let (code, colors) = rulestring
+            .strip_prefix('T')
+            .and_then(|rest| rest.split_once(",K"))
+            .ok_or_else(|| {
+                invalid(
+                    "expected W followed by a Wolfram code, or T followed by a code and K colors",
+                )
+            })?;
+        let (code, colors) = (
+            number(code, "code")?,
+            number(colors, "number of colors")? as usize,
+        );
+        if !(2..=MAX_COLORS).contains(&colors) {
+            return Err(invalid(&format!(
+                "the number of colors must be between 2 and {}",
+                MAX_COLORS
+            )));
+        }
+
+        // one digit for every sum of three colors
+        let sums = 3 * (colors - 1) + 1;
+        if (colors as u64)
+            .checked_pow(sums as u32)
+            .is_some_and(|limit| code >= limit)
+        {
+            return Err(invalid(&format!(
+                "codes with {} colors have at most {} digits",
+                colors, sums
+            )));
+        }
+
+        let table = (0..sums as u32)
+            .map(|sum| (code / (colors as u64).pow(sum) % colors as u64) as u8)
+            .collect();
+
+        Ok(Self {
+            code,
+            totalistic_colors: Some(colors),
+            table,
+        })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(OneDimensional::new(self, rows, columns))
+    }
+}
+
+impl ElementaryRule {
+    pub fn colors(&self) -> usize {
+        self.totalistic_colors.unwrap_or(2)
+    }
+
+    /// Next state of a cell below the given three cells
+    pub fn next_cell_state(&self, left: u8, center: u8, right: u8) -> u8 {
+        match self.totalistic_colors {
+            Some(_) => self.table[(left + center + right) as usize],
+            None => self.table[((left << 2) | (center << 1) | right) as usize],
+        }
+    }
+}
+
+impl Display for ElementaryRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.totalistic_colors {
+            Some(colors) => write!(f, "T{},K{}", self.code, colors),
+            None => write!(f, "W{}", self.code),
+        }
+    }
+}
+
+/// A one-dimensional automaton drawn as its history: the first generation is the top row and each generation is
+/// drawn below the last one. Once the grid is full, the rows scroll up to make room for the next generation.
+/// The row wraps around at its ends.
+pub struct OneDimensional {
+    rule: ElementaryRule,
+    grid: Grid<u8>,
+    /// Row of the current generation
+    current: usize,
+}
+
+impl OneDimensional {
+    /// Start from a single live cell in the middle of the top row
+    pub fn new(rule: ElementaryRule, rows: usize, columns: usize) -> Self {
+        let mut simulation = Self {
+            rule,
+            grid: Grid::new(rows, columns, 0),
+            current: 0,
+        };
+        simulation.clear();
+
+        simulation
+    }
+
+    /// The state the cell in the given column of the current generation has in the next one
+    fn next_cell_state(&self, column: usize) -> u8 {
+        let (row, columns) = (&self.grid[self.current], self.grid.columns());
+
+        self.rule.next_cell_state(
+            row[(column + columns - 1) % columns],
+            row[column],
+            row[(column + 1) % columns],
+        )
+    }
+}
+
+impl Simulation for OneDimensional {
+    fn name(&self) -> String {
+        self.rule.to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        self.rule.to_string()
+    }
+
+    fn rows(&self) -> usize {
+        self.grid.rows()
+    }
+
+    fn columns(&self) -> usize {
+        self.grid.columns()
+    }
+
+    fn state_count(&self) -> usize {
+        self.rule.colors()
+    }
+
+    /// Counts compare the new generation with the previous row
+    fn step(&mut self, _rng: &mut Rng) -> GenerationCounts {
+        let next: Vec<u8> = (0..self.grid.columns())
+            .map(|column| self.next_cell_state(column))
+            .collect();
+
+        let mut counts = GenerationCounts::default();
+        for (&cell, &next_cell) in self.grid[self.current].iter().zip(next.iter()) {
+            match (cell != 0, next_cell != 0) {
+                (false, true) => counts.births += 1,
+                (true, false) => counts.deaths += 1,
+                _ => {}
+            }
+        }
+        counts.alive = next.iter().filter(|&&cell| cell != 0).count();
+
+        if self.current + 1 < self.grid.rows() {
+            self.current += 1;
+        } else {
+            self.grid.scroll_up(0);
+        }
+        self.grid[self.current].copy_from_slice(&next);
+
+        counts
+    }
+
+    fn state(&self, row: usize, column: usize) -> u8 {
+        self.grid[row][column]
+    }
+
+    fn write_state(&mut self, row: usize, column: usize, state: u8) {
+        self.grid[row][column] = state;
+    }
+
+    /// Start over from a single live cell in the middle of the top row, since an empty row stays empty in most rules
+    fn clear(&mut self) {
+        self.grid.fill(0);
+        self.current = 0;
+
+        let middle = self.grid.columns() / 2;
+        self.grid[0][middle] = 1;
+    }
+
+    /// Start over from a random top row
+    fn randomize(&mut self, rng: &mut Rng, density: f64) {
+        self.grid.fill(0);
+        self.current = 0;
+
+        let colors = self.rule.colors() as u64;
+        for cell in self.grid[0].iter_mut() {
+            if rng.next_f64() < density {
+                *cell = (1 + rng.next_u64() % (colors - 1)) as u8;
+            }
+        }
+    }
+
+    fn is_alive(&self, row: usize, column: usize) -> bool {
+        self.grid[row][column] != 0
+    }
+
+    /// Only the row below the current generation changes, until the rows start scrolling up
+    fn next_state(&self, row: usize, column: usize) -> u8 {
+        let rows = self.grid.rows();
+
+        if self.current + 1 < rows {
+            if row == self.current + 1 {
+                self.next_cell_state(column)
+            } else {
+                self.grid[row][column]
+            }
+        } else if row + 1 < rows {
+            self.grid[row + 1][column]
+        } else {
+            self.next_cell_state(column)
+        }
+    }
+
+    /// Live cells among the three cells above, which the cell was computed from
+    fn count_neighbors(&self, row: usize, column: usize) -> u32 {
+        let Some(above) = row.checked_sub(1) else {
+            return 0;
+        };
+        let columns = self.grid.columns();
+
+        [
+            (column + columns - 1) % columns,
+            column,
+            (column + 1) % columns,
+        ]
+        .into_iter()
+        .filter(|&column| self.grid[above][column] != 0)
+        .count() as u32
+    }
+
+    fn state_name(&self, state: u8) -> String {
+        format!("Color {}", state)
+    }
+
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        None
+    }
+
+    /// Runs start from a single cell rather than a random row
+    fn starts_empty(&self) -> bool {
+        true
+    }
+
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        Vec::new()
+    }
+
+    /// The row of the current generation, as `current = row`
+    fn session_state(&self) -> String {
+        format!("current = {}\n", self.current)
+    }
+
+    fn restore_session_state(&mut self, state: &str) -> Result<(), String> {
+        let state = state.trim();
+        if state.is_empty() {
+            return Ok(());
+        }
+
+        self.current = state
+            .strip_prefix("current")
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+            .and_then(|row| row.trim().parse().ok())
+            .filter(|&row| row < self.grid.rows())
+            .ok_or_else(|| format!("invalid current row `{}`", state))?;
+        Ok(())
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live_columns(simulation: &OneDimensional, row: usize) -> Vec<usize> {
+        (0..simulation.columns())
+            .filter(|&column| simulation.is_alive(row, column))
+            .collect()
+    }
+
+    #[test]
+    fn parses_rulestrings() {
+        for rulestring in ["W30", "W0", "W255", "T777,K3", "T6,K2"] {
+            assert_eq!(
+                ElementaryRule::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        assert!(ElementaryRule::parse("W256").is_err());
+        assert!(ElementaryRule::parse("T16,K2").is_err());
+        assert!(ElementaryRule::parse("T1,K8").is_err());
+        assert!(ElementaryRule::matches("W110"));
+        assert!(!ElementaryRule::matches("B3/S23"));
+    }
+
+    #[test]
+    fn rule_90_draws_a_sierpinski_triangle() {
+        let mut simulation = OneDimensional::new(ElementaryRule::parse("W90").unwrap(), 4, 11);
+
+        for _ in 0..3 {
+            simulation.step(&mut Rng::new(0));
+        }
+
+        assert_eq!(live_columns(&simulation, 0), [5]);
+        assert_eq!(live_columns(&simulation, 1), [4, 6]);
+        assert_eq!(live_columns(&simulation, 2), [3, 7]);
+        assert_eq!(live_columns(&simulation, 3), [2, 4, 6, 8]);
+        assert_eq!(simulation.count_neighbors(2, 5), 2);
+    }
+
+    #[test]
+    fn totalistic_codes_use_the_sum_of_colors() {
+        // 2 colors, where sums of 1 and 2 come alive
+        let rule = ElementaryRule::parse("T6,K2").unwrap();
+        assert_eq!(rule.next_cell_state(0, 1, 0), 1);
+        assert_eq!(rule.next_cell_state(1, 1, 0), 1);
+        assert_eq!(rule.next_cell_state(1, 1, 1), 0);
+
+        // 777 is 1001210 in base 3
+        let rule = ElementaryRule::parse("T777,K3").unwrap();
+        assert_eq!(rule.next_cell_state(0, 0, 0), 0);
+        assert_eq!(rule.next_cell_state(0, 1, 0), 1);
+        assert_eq!(rule.next_cell_state(1, 0, 1), 2);
+        assert_eq!(rule.next_cell_state(2, 2, 2), 1);
+    }
+
+    #[test]
+    fn rows_scroll_once_the_grid_is_full() {
+        let mut simulation = OneDimensional::new(ElementaryRule::parse("W90").unwrap(), 2, 11);
+        assert_eq!(simulation.next_state(1, 4), 1);
+
+        simulation.step(&mut Rng::new(0));
+        assert_eq!(simulation.next_state(0, 4), 1);
+        simulation.step(&mut Rng::new(0));
+
+        assert_eq!(live_columns(&simulation, 0), [4, 6]);
+        assert_eq!(live_columns(&simulation, 1), [3, 7]);
+    }
+
+    #[test]
+    fn restores_the_current_row_from_session_state() {
+        let rule = ElementaryRule::parse("W90").unwrap();
+        let mut simulation = OneDimensional::new(rule.clone(), 6, 11);
+        simulation.step(&mut Rng::new(0));
+        simulation.step(&mut Rng::new(0));
+
+        let mut restored = OneDimensional::new(rule, 6, 11);
+        restored
+            .restore_session_state(&simulation.session_state())
+            .unwrap();
+        assert_eq!(restored.current, 2);
+
+        assert!(restored.restore_session_state("current = 6").is_err());
+        assert!(restored.restore_session_state("row = 1").is_err());
+    }
+}
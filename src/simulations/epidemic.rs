@@ -0,0 +1,191 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Parameter, Rgba, Simulation};
+use crate::grid::Grid;
+use crate::neighborhood::MOORE;
+use crate::registry::Notation;
+use crate::simulations::stochastic::{self, StochasticRule, StochasticRunner};
+use crate::util::{count_cells, has_prefix, Rng};
+
+/// An outbreak that spreads as a ring of infections, with a wave of recovered cells behind it
+pub const DEFAULT: &str = "SIR/B0.25/G0.05";
+
+const PREFIX: &str = "SIR";
+const STATE_NAMES: [&str; 3] = ["Susceptible", "Infected", "Recovered"];
+const SUSCEPTIBLE: u8 = 0;
+const INFECTED: u8 = 1;
+const RECOVERED: u8 = 2;
+
+/// An SIR epidemic, written as `SIR/B<infection>/G<recovery>` after the usual beta and gamma. Every generation, each
+/// infected neighbor of a susceptible cell infects it with probability `B`, and infected cells recover with
+/// probability `G`. Recovered cells are immune for good. Neighbors are the 8 cells around a cell.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Epidemic {
+    infection: f64,
+    recovery: f64,
+}
+
+impl Notation for Epidemic {
+    const PRESETS: &'static [(&'static str, &'static str)] = &[("Epidemic (SIR)", DEFAULT)];
+
+    fn matches(rulestring: &str) -> bool {
+        has_prefix(rulestring, PREFIX)
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let [infection, recovery] =
+            stochastic::parse_probabilities(rulestring, PREFIX, ['B', 'G'])?;
+
+        Ok(Self {
+            infection,
+            recovery,
+        })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(StochasticRunner::new(self, rows, columns))
+    }
+}
+
+impl Epidemic {
+    /// Chance of a susceptible cell with the given number of infected neighbors being infected, when every one of
+    /// them gets a chance to infect it
+    fn infection_chance(&self, infected_neighbors: u32) -> f64 {
+        1. - (1. - self.infection).powi(infected_neighbors as i32)
+    }
+}
+
+impl StochasticRule for Epidemic {
+    fn name(&self) -> String {
+        "Epidemic (SIR)".to_string()
+    }
+
+    fn state_names(&self) -> &'static [&'static str] {
+        &STATE_NAMES
+    }
+
+    fn next_state(&self, grid: &Grid<u8>, row: usize, column: usize, roll: f64) -> u8 {
+        match grid[row][column] {
+            SUSCEPTIBLE => {
+                let infected_neighbors = self.count_neighbors(grid, row, column);
+                if roll < self.infection_chance(infected_neighbors) {
+                    INFECTED
+                } else {
+                    SUSCEPTIBLE
+                }
+            }
+            INFECTED if roll < self.recovery => RECOVERED,
+            state => state,
+        }
+    }
+
+    /// Infected neighbors of the cell
+    fn count_neighbors(&self, grid: &Grid<u8>, row: usize, column: usize) -> u32 {
+        count_cells(grid, &MOORE, row, column, |cell| cell == INFECTED)
+    }
+
+    fn palette(&self) -> Vec<Rgba> {
+        vec![
+            Rgba::rgb(40, 60, 90),
+            Rgba::rgb(220, 40, 40),
+            Rgba::rgb(200, 200, 200),
+        ]
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::real("infection", self.infection),
+            Parameter::real("recovery", self.recovery),
+        ]
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        let value = value.clamp(0., 1.);
+        match idx {
+            0 => self.infection = value,
+            1 => self.recovery = value,
+            _ => {}
+        }
+    }
+
+    /// Soups are susceptible populations where the density is the fraction of cells that start out infected
+    fn random_state(&self, rng: &mut Rng, density: f64) -> u8 {
+        u8::from(rng.next_f64() < density)
+    }
+
+    /// Everyone is susceptible but a single infected cell in the middle
+    fn reset(&self, grid: &mut Grid<u8>) {
+        grid.fill(SUSCEPTIBLE);
+
+        let (row, column) = (grid.rows() / 2, grid.columns() / 2);
+        grid[row][column] = INFECTED;
+    }
+
+    /// Outbreaks start from a single case rather than a soup
+    fn starts_empty(&self) -> bool {
+        true
+    }
+}
+
+impl Display for Epidemic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/B{}/G{}", PREFIX, self.infection, self.recovery)
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rulestrings() {
+        for rulestring in [DEFAULT, "SIR/B1/G0"] {
+            assert_eq!(
+                Epidemic::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        assert!(Epidemic::parse("SIR/B0.5/G0.1/X1").is_err());
+        assert!(Epidemic::parse("SIR/B-0.5/G0.1").is_err());
+        assert!(Epidemic::matches("sir/b0.5/g0.1"));
+        assert!(!Epidemic::matches("SIRS/B0.5/G0.1"));
+    }
+
+    #[test]
+    fn more_infected_neighbors_are_more_contagious() {
+        let rule = Epidemic::parse("SIR/B0.5/G0.1").unwrap();
+
+        assert_eq!(rule.infection_chance(0), 0.);
+        assert_eq!(rule.infection_chance(1), 0.5);
+        assert_eq!(rule.infection_chance(2), 0.75);
+
+        // the inspector shows the outcome that is more likely than not
+        let mut grid = Grid::new(3, 3, SUSCEPTIBLE);
+        grid[0][0] = INFECTED;
+        assert_eq!(rule.next_state(&grid, 1, 1, 0.5), SUSCEPTIBLE);
+        grid[0][1] = INFECTED;
+        assert_eq!(rule.next_state(&grid, 1, 1, 0.5), INFECTED);
+        assert_eq!(rule.next_state(&grid, 0, 0, 0.5), INFECTED);
+    }
+
+    #[test]
+    fn outbreaks_burn_out() {
+        let mut simulation = StochasticRunner::new(Epidemic::parse(DEFAULT).unwrap(), 32, 32);
+        let mut rng = Rng::new(1);
+        simulation.set_parameter(0, 0.6);
+
+        for _ in 0..500 {
+            simulation.step(&mut rng);
+        }
+
+        let compartments = simulation.measurements();
+        assert_eq!(compartments[1], ("Infected", 0.));
+        assert!(compartments[2].1 > 900., "{:?}", compartments);
+        assert_eq!(
+            compartments.iter().map(|(_, count)| count).sum::<f32>(),
+            (32 * 32) as f32
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Parameter, Rgba, Simulation};
+use crate::grid::Grid;
+use crate::neighborhood::VON_NEUMANN;
+use crate::registry::Notation;
+use crate::simulations::stochastic::{self, StochasticRule, StochasticRunner};
+use crate::util::{count_cells, has_prefix, Rng};
+
+/// A forest that grows back about 500 times faster than lightning strikes, which gives fires of every size
+pub const DEFAULT: &str = "ForestFire/P0.05/F0.0001";
+
+const PREFIX: &str = "ForestFire";
+const STATE_NAMES: [&str; 3] = ["Empty", "Tree", "Burning"];
+const EMPTY: u8 = 0;
+const TREE: u8 = 1;
+const BURNING: u8 = 2;
+
+/// The Drossel-Schwabl forest-fire model, written as `ForestFire/P<p>/F<f>`. Every generation, burning trees burn
+/// down, trees next to a burning tree catch fire, other trees are struck by lightning with probability `f`, and trees
+/// grow on empty cells with probability `p`. Fire spreads to the 4 cells that share an edge with a burning tree.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ForestFire {
+    growth: f64,
+    lightning: f64,
+}
+
+impl Notation for ForestFire {
+    const PRESETS: &'static [(&'static str, &'static str)] = &[("Forest fire", DEFAULT)];
+
+    fn matches(rulestring: &str) -> bool {
+        has_prefix(rulestring, PREFIX)
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let [growth, lightning] = stochastic::parse_probabilities(rulestring, PREFIX, ['P', 'F'])?;
+
+        Ok(Self { growth, lightning })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(StochasticRunner::new(self, rows, columns))
+    }
+}
+
+impl StochasticRule for ForestFire {
+    fn name(&self) -> String {
+        "Forest fire".to_string()
+    }
+
+    fn state_names(&self) -> &'static [&'static str] {
+        &STATE_NAMES
+    }
+
+    fn next_state(&self, grid: &Grid<u8>, row: usize, column: usize, roll: f64) -> u8 {
+        match grid[row][column] {
+            TREE if self.count_neighbors(grid, row, column) > 0 || roll < self.lightning => BURNING,
+            TREE => TREE,
+            EMPTY if roll < self.growth => TREE,
+            _ => EMPTY,
+        }
+    }
+
+    /// Burning trees next to the cell
+    fn count_neighbors(&self, grid: &Grid<u8>, row: usize, column: usize) -> u32 {
+        count_cells(grid, &VON_NEUMANN, row, column, |cell| cell == BURNING)
+    }
+
+    fn palette(&self) -> Vec<Rgba> {
+        vec![
+            Rgba::rgb(48, 32, 16),
+            Rgba::rgb(34, 139, 34),
+            Rgba::rgb(255, 96, 0),
+        ]
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::real("p", self.growth),
+            Parameter::real("f", self.lightning),
+        ]
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        let value = value.clamp(0., 1.);
+        match idx {
+            0 => self.growth = value,
+            1 => self.lightning = value,
+            _ => {}
+        }
+    }
+
+    /// Soups are forests with trees at the given density and no fires
+    fn random_state(&self, rng: &mut Rng, density: f64) -> u8 {
+        u8::from(rng.next_f64() < density)
+    }
+}
+
+impl Display for ForestFire {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/P{}/F{}", PREFIX, self.growth, self.lightning)
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rulestrings() {
+        for rulestring in [DEFAULT, "ForestFire/P1/F0"] {
+            assert_eq!(
+                ForestFire::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        assert!(ForestFire::parse("ForestFire/P0.5").is_err());
+        assert!(ForestFire::parse("ForestFire/P2/F0.1").is_err());
+        assert!(ForestFire::parse("ForestFire/F0.1/P0.5").is_err());
+        assert!(ForestFire::matches("forestfire/p0.1/f0.01"));
+        assert!(!ForestFire::matches("B3/S23"));
+    }
+
+    #[test]
+    fn fire_spreads_to_neighboring_trees() {
+        let rule = ForestFire::parse("ForestFire/P0/F0").unwrap();
+        let mut simulation = StochasticRunner::new(rule, 1, 5);
+        for column in 0..4 {
+            simulation.set_state(0, column, TREE);
+        }
+        simulation.set_state(0, 0, BURNING);
+
+        let mut rng = Rng::new(0);
+        simulation.step(&mut rng);
+        assert_eq!(
+            (0..5)
+                .map(|column| simulation.state(0, column))
+                .collect::<Vec<_>>(),
+            [EMPTY, BURNING, TREE, TREE, EMPTY]
+        );
+
+        for _ in 0..3 {
+            simulation.step(&mut rng);
+        }
+        assert_eq!(
+            simulation.measurements(),
+            [("Empty", 5.), ("Tree", 0.), ("Burning", 0.)]
+        );
+    }
+
+    #[test]
+    fn seeded_runs_are_reproducible() {
+        let run = |seed| {
+            let mut simulation = StochasticRunner::new(ForestFire::parse(DEFAULT).unwrap(), 32, 32);
+            let mut rng = Rng::new(seed);
+            simulation.set_parameter(1, 0.01);
+            (0..50).for_each(|_| {
+                simulation.step(&mut rng);
+            });
+
+            simulation.grid().clone()
+        };
+
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+}
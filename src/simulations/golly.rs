@@ -0,0 +1,868 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::automaton::{Automaton, Rgba, Runner, Simulation};
+use crate::grid::Grid;
+use crate::neighborhood::Neighborhood;
+use crate::registry::Notation;
+
+/// Highest number of neighbors of the supported neighborhoods
+const MAX_NEIGHBORS: usize = 8;
+
+/// A multi-state rule loaded from a Golly `.rule` file, with the transitions in either an `@TABLE` or an `@TREE`
+/// section, and optionally the colors of the states in an `@COLORS` section. Other sections, like `@ICONS`, are
+/// ignored.
+#[derive(Clone, Debug)]
+pub struct GollyRule {
+    /// Name given in the `@RULE` line
+    name: String,
+    /// Path of the file, which is what the rule is known by outside of Golly
+    source: String,
+    states: usize,
+    neighborhood: GollyNeighborhood,
+    transitions: Transitions,
+    palette: Option<Vec<Rgba>>,
+}
+
+/// The neighborhoods Golly rule files can use, each with the order its neighbors are listed in
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum GollyNeighborhood {
+    Moore,
+    VonNeumann,
+    Hexagonal,
+}
+
+impl GollyNeighborhood {
+    /// Offsets of the neighbors in the order of `@TABLE` lines, clockwise from the north
+    fn table_offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            GollyNeighborhood::Moore => &[
+                (-1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+                (1, 0),
+                (1, -1),
+                (0, -1),
+                (-1, -1),
+            ],
+            GollyNeighborhood::VonNeumann => &[(-1, 0), (0, 1), (1, 0), (0, -1)],
+            GollyNeighborhood::Hexagonal => &[(-1, 0), (0, 1), (1, 1), (1, 0), (0, -1), (-1, -1)],
+        }
+    }
+
+    /// Offsets of the neighbors in the order `@TREE` sections look them up, followed by the cell itself
+    fn tree_offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            GollyNeighborhood::Moore => &[
+                (-1, -1),
+                (-1, 1),
+                (1, -1),
+                (1, 1),
+                (-1, 0),
+                (0, -1),
+                (0, 1),
+                (1, 0),
+                (0, 0),
+            ],
+            _ => &[(-1, 0), (0, -1), (0, 1), (1, 0), (0, 0)],
+        }
+    }
+
+    /// Permutations of the neighbor positions for the symmetries of a table, or `None` for `permute`, where every
+    /// order of the neighbors is allowed
+    fn symmetries(&self, symmetries: &str) -> Result<Option<Vec<Vec<usize>>>, String> {
+        let count = self.table_offsets().len();
+        let rotate = |steps: usize| -> Vec<Vec<usize>> {
+            (0..count)
+                .step_by(count / steps)
+                .map(|shift| (0..count).map(|idx| (idx + shift) % count).collect())
+                .collect()
+        };
+        let reflect = |permutations: Vec<Vec<usize>>| -> Vec<Vec<usize>> {
+            let reflected: Vec<Vec<usize>> = permutations
+                .iter()
+                .map(|permutation| {
+                    (0..count)
+                        .map(|idx| permutation[(count - idx) % count])
+                        .collect()
+                })
+                .collect();
+            permutations.into_iter().chain(reflected).collect()
+        };
+
+        let permutations = match (self, symmetries) {
+            (_, "none") => vec![(0..count).collect()],
+            (_, "permute") => return Ok(None),
+            (GollyNeighborhood::Moore, "rotate4") | (GollyNeighborhood::VonNeumann, "rotate4") => {
+                rotate(4)
+            }
+            (GollyNeighborhood::Moore, "rotate8") => rotate(8),
+            (GollyNeighborhood::Moore | GollyNeighborhood::VonNeumann, "reflect_horizontal") => {
+                reflect(rotate(1))
+            }
+            (GollyNeighborhood::Moore | GollyNeighborhood::VonNeumann, "rotate4reflect") => {
+                reflect(rotate(4))
+            }
+            (GollyNeighborhood::Moore, "rotate8reflect") => reflect(rotate(8)),
+            (GollyNeighborhood::Hexagonal, "rotate2") => rotate(2),
+            (GollyNeighborhood::Hexagonal, "rotate3") => rotate(3),
+            (GollyNeighborhood::Hexagonal, "rotate6") => rotate(6),
+            (GollyNeighborhood::Hexagonal, "rotate6reflect") => reflect(rotate(6)),
+            _ => return Err(format!("unsupported symmetries `{}`", symmetries)),
+        };
+
+        Ok(Some(permutations))
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Transitions {
+    Table {
+        /// Values each variable can take, indexed like [Term::Variable] and [Term::Set]
+        variables: Vec<Vec<u8>>,
+        /// Transitions with every symmetric version spelled out, tried in order
+        transitions: Vec<Transition>,
+        /// Next state of every neighborhood seen so far, keyed by the cell followed by its neighbors
+        cache: RefCell<HashMap<[u8; MAX_NEIGHBORS + 1], u8>>,
+    },
+    Tree {
+        /// Children of every node, or the next states for nodes at the lowest level. The root is the last node.
+        nodes: Vec<Vec<usize>>,
+    },
+}
+
+/// A state or a variable in a transition of a table
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Term {
+    State(u8),
+    /// Variables are bound: if one appears more than once in a transition, it has the same value everywhere
+    Variable(usize),
+    /// Any of the values of a variable, for variables used only once and inline sets, which need no binding
+    Set(usize),
+}
+
+/// A line of a table: the cell, its neighbors and the state the cell turns into
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Transition {
+    inputs: Vec<Term>,
+    output: Term,
+}
+
+impl Transition {
+    /// The next state if the transition applies to the cell followed by its neighbors
+    fn apply(&self, variables: &[Vec<u8>], cells: &[u8]) -> Option<u8> {
+        let mut bound: Vec<(usize, u8)> = Vec::new();
+
+        for (term, &cell) in self.inputs.iter().zip(cells) {
+            match *term {
+                Term::State(state) if state == cell => {}
+                Term::State(_) => return None,
+                Term::Set(variable) if variables[variable].contains(&cell) => {}
+                Term::Set(_) => return None,
+                Term::Variable(variable) => {
+                    match bound.iter().find(|(bound, _)| *bound == variable) {
+                        Some(&(_, value)) if value == cell => {}
+                        Some(_) => return None,
+                        None if variables[variable].contains(&cell) => bound.push((variable, cell)),
+                        None => return None,
+                    }
+                }
+            }
+        }
+
+        match self.output {
+            Term::State(state) => Some(state),
+            Term::Variable(variable) => bound
+                .iter()
+                .find(|(bound, _)| *bound == variable)
+                .map(|&(_, value)| value),
+            Term::Set(_) => None,
+        }
+    }
+}
+
+impl Notation for GollyRule {
+    /// Whether the rulestring is a path to a rule file
+    fn matches(rulestring: &str) -> bool {
+        rulestring.trim().to_ascii_lowercase().ends_with(".rule")
+    }
+
+    /// Rulestrings name the file to load the rule from
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        GollyRule::from_file(Path::new(rulestring.trim()))
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(Runner::new(self, rows, columns))
+    }
+}
+
+impl GollyRule {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read rule {}: {}", path.display(), err))?;
+
+        GollyRule::parse(&contents, &path.display().to_string())
+    }
+
+    /// Parse the contents of a rule file, where `source` is what the rule is to be known by, usually the path
+    pub fn parse(contents: &str, source: &str) -> Result<Self, String> {
+        let invalid = |line: usize, reason: &str| {
+            format!("Invalid rule file {}: line {}: {}", source, line, reason)
+        };
+
+        // group the lines by section, keeping their line numbers for errors
+        let mut name = None;
+        let mut sections: HashMap<&str, Vec<(usize, &str)>> = HashMap::new();
+        let mut section = "";
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('@') {
+                let (header, argument) = header.split_once(' ').unwrap_or((header, ""));
+                section = header;
+                if section == "RULE" {
+                    name = Some(argument.trim().to_string());
+                }
+                sections.entry(section).or_default();
+            } else {
+                sections.entry(section).or_default().push((idx + 1, line));
+            }
+        }
+
+        let name = name
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| invalid(1, "missing @RULE name"))?;
+
+        let (states, neighborhood, transitions) =
+            match (sections.get("TABLE"), sections.get("TREE")) {
+                (Some(table), None) => {
+                    parse_table(table).map_err(|(line, err)| invalid(line, &err))?
+                }
+                (None, Some(tree)) => {
+                    parse_tree(tree).map_err(|(line, err)| invalid(line, &err))?
+                }
+                _ => return Err(invalid(1, "expected either a @TABLE or a @TREE section")),
+            };
+
+        let palette = match sections.get("COLORS") {
+            Some(colors) => {
+                Some(parse_colors(colors, states).map_err(|(line, err)| invalid(line, &err))?)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            name,
+            source: source.to_string(),
+            states,
+            neighborhood,
+            transitions,
+            palette,
+        })
+    }
+
+    /// States of the cell and its neighbors at the given offsets. Cells beyond the edges of the grid are empty.
+    fn cells(
+        &self,
+        grid: &Grid<u8>,
+        row: usize,
+        column: usize,
+        offsets: &[(isize, isize)],
+    ) -> Vec<u8> {
+        offsets
+            .iter()
+            .map(|&(row_offset, column_offset)| {
+                match (
+                    row.checked_add_signed(row_offset),
+                    column.checked_add_signed(column_offset),
+                ) {
+                    (Some(row), Some(column)) if row < grid.rows() && column < grid.columns() => {
+                        grid[row][column]
+                    }
+                    _ => 0,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Automaton for GollyRule {
+    type State = u8;
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn rulestring(&self) -> String {
+        self.source.clone()
+    }
+
+    fn state_count(&self) -> usize {
+        self.states
+    }
+
+    fn next_state(&self, grid: &Grid<u8>, row: usize, column: usize) -> u8 {
+        match &self.transitions {
+            Transitions::Table {
+                variables,
+                transitions,
+                cache,
+            } => {
+                let mut key = [0; MAX_NEIGHBORS + 1];
+                key[0] = grid[row][column];
+                let neighbors = self.cells(grid, row, column, self.neighborhood.table_offsets());
+                key[1..=neighbors.len()].copy_from_slice(&neighbors);
+
+                if let Some(&next) = cache.borrow().get(&key) {
+                    return next;
+                }
+
+                let cells = &key[..=neighbors.len()];
+                // cells that no transition applies to stay the same
+                let next = transitions
+                    .iter()
+                    .find_map(|transition| transition.apply(variables, cells))
+                    .unwrap_or(key[0]);
+                cache.borrow_mut().insert(key, next);
+
+                next
+            }
+            Transitions::Tree { nodes } => {
+                let cells = self.cells(grid, row, column, self.neighborhood.tree_offsets());
+                let mut node = nodes.len() - 1;
+                for (idx, cell) in cells.iter().enumerate() {
+                    let next = nodes[node][*cell as usize];
+                    if idx == cells.len() - 1 {
+                        return next as u8;
+                    }
+                    node = next;
+                }
+
+                unreachable!("the tree has a level for every neighbor")
+            }
+        }
+    }
+
+    fn neighborhood(&self) -> Neighborhood {
+        match self.neighborhood {
+            GollyNeighborhood::Moore => Neighborhood::Moore(1),
+            GollyNeighborhood::VonNeumann => Neighborhood::VonNeumann(1),
+            GollyNeighborhood::Hexagonal => Neighborhood::Hexagonal,
+        }
+    }
+
+    /// Every state but the empty one counts as alive, since rule files don't say which states are meant to be
+    fn is_alive(&self, state: u8) -> bool {
+        state != 0
+    }
+
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        self.palette.clone()
+    }
+}
+
+type ParseResult<T> = Result<T, (usize, String)>;
+
+fn parse_states(line: usize, value: &str) -> ParseResult<usize> {
+    match value.trim().parse() {
+        Ok(states) if (2..=256).contains(&states) => Ok(states),
+        _ => Err((
+            line,
+            "the number of states must be between 2 and 256".to_string(),
+        )),
+    }
+}
+
+fn parse_table(lines: &[(usize, &str)]) -> ParseResult<(usize, GollyNeighborhood, Transitions)> {
+    let mut states = None;
+    let mut neighborhood = None;
+    let mut symmetries = None;
+    let mut names: Vec<String> = Vec::new();
+    let mut variables: Vec<Vec<u8>> = Vec::new();
+    let mut transitions = Vec::new();
+
+    for &(line, text) in lines {
+        if let Some((key, value)) = text.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" => states = Some(parse_states(line, value)?),
+                "neighborhood" => {
+                    neighborhood = Some(match value {
+                        "Moore" => GollyNeighborhood::Moore,
+                        "vonNeumann" => GollyNeighborhood::VonNeumann,
+                        "hexagonal" => GollyNeighborhood::Hexagonal,
+                        _ => return Err((line, format!("unsupported neighborhood `{}`", value))),
+                    })
+                }
+                "symmetries" => symmetries = Some((line, value.to_string())),
+                other => return Err((line, format!("unknown key `{}`", other))),
+            }
+            continue;
+        }
+
+        let states = states.ok_or((line, "n_states must come first".to_string()))?;
+        let neighborhood =
+            neighborhood.ok_or((line, "neighborhood must come first".to_string()))?;
+
+        // terms are a state, a variable or an inline set of states like `{1,2}`
+        let parse_set = |set: &str,
+                         variables: &[Vec<u8>],
+                         names: &[String]|
+         -> ParseResult<Vec<u8>> {
+            let mut values = Vec::new();
+            for item in set.split(',').map(str::trim) {
+                match (
+                    item.parse::<usize>(),
+                    names.iter().position(|name| name == item),
+                ) {
+                    (Ok(state), _) if state < states => values.push(state as u8),
+                    (_, Some(variable)) => values.extend(&variables[variable]),
+                    _ => return Err((line, format!("`{}` is not a state or a variable", item))),
+                }
+            }
+            Ok(values)
+        };
+
+        if let Some(definition) = text.strip_prefix("var ") {
+            let (name, set) = definition
+                .split_once('=')
+                .ok_or((line, "expected `var name={..}`".to_string()))?;
+            let set = set
+                .trim()
+                .strip_prefix('{')
+                .and_then(|set| set.strip_suffix('}'))
+                .ok_or((line, "expected a set of states like {0,1,2}".to_string()))?;
+
+            let values = parse_set(set, &variables, &names)?;
+            names.push(name.trim().to_string());
+            variables.push(values);
+            continue;
+        }
+
+        let terms: Vec<String> = if text.contains(',') || text.contains('{') {
+            split_terms(text)
+        } else {
+            // all single digit states, written without commas
+            text.chars()
+                .filter(|c| !c.is_whitespace())
+                .map(String::from)
+                .collect()
+        };
+
+        let expected = neighborhood.table_offsets().len() + 2;
+        if terms.len() != expected {
+            return Err((
+                line,
+                format!("expected {} states, found {}", expected, terms.len()),
+            ));
+        }
+
+        let mut parsed = Vec::new();
+        for term in terms.iter() {
+            if let Some(set) = term.strip_prefix('{').and_then(|set| set.strip_suffix('}')) {
+                // inline sets are variables that are never bound to another place
+                variables.push(parse_set(set, &variables, &names)?);
+                names.push(String::new());
+                parsed.push(Term::Set(variables.len() - 1));
+            } else if let Some(variable) = names.iter().position(|name| name == term) {
+                parsed.push(Term::Variable(variable));
+            } else {
+                match term.parse::<usize>() {
+                    Ok(state) if state < states => parsed.push(Term::State(state as u8)),
+                    _ => return Err((line, format!("`{}` is not a state or a variable", term))),
+                }
+            }
+        }
+
+        let output = parsed.pop().expect("the length was checked");
+        transitions.push((
+            line,
+            Transition {
+                inputs: parsed,
+                output,
+            },
+        ));
+    }
+
+    let states = states.ok_or((1, "missing n_states".to_string()))?;
+    let neighborhood = neighborhood.ok_or((1, "missing neighborhood".to_string()))?;
+    let (symmetries_line, symmetries) = symmetries.ok_or((1, "missing symmetries".to_string()))?;
+    let permutations = neighborhood
+        .symmetries(&symmetries)
+        .map_err(|err| (symmetries_line, err))?;
+
+    // spell out every symmetric version of the transitions, so that stepping only has to compare
+    let mut expanded: Vec<Transition> = Vec::new();
+    for (line, transition) in transitions {
+        if let Term::Variable(variable) = transition.output {
+            if !transition.inputs.contains(&Term::Variable(variable)) {
+                return Err((
+                    line,
+                    "the new state can only be a variable used before it".to_string(),
+                ));
+            }
+        }
+
+        // variables used only once don't need binding, and ones with the same values are interchangeable, which
+        // keeps permute from spelling out orders that only swap them
+        let inputs: Vec<Term> = transition
+            .inputs
+            .iter()
+            .map(|&term| match term {
+                Term::Variable(variable)
+                    if transition.output != term
+                        && transition
+                            .inputs
+                            .iter()
+                            .filter(|&&other| other == term)
+                            .count()
+                            == 1 =>
+                {
+                    let same = variables
+                        .iter()
+                        .position(|values| *values == variables[variable])
+                        .expect("the variable has its own values");
+                    Term::Set(same)
+                }
+                Term::Set(variable) => Term::Set(
+                    variables
+                        .iter()
+                        .position(|values| *values == variables[variable])
+                        .expect("the set has its own values"),
+                ),
+                term => term,
+            })
+            .collect();
+
+        let (center, neighbors) = inputs.split_first().expect("transitions have a cell");
+        let orders = match &permutations {
+            Some(permutations) => permutations
+                .iter()
+                .map(|permutation| permutation.iter().map(|&idx| neighbors[idx]).collect())
+                .collect(),
+            None => distinct_permutations(neighbors.to_vec()),
+        };
+
+        for order in orders {
+            let symmetric = Transition {
+                inputs: std::iter::once(*center).chain(order).collect(),
+                output: transition.output,
+            };
+            if !expanded.contains(&symmetric) {
+                expanded.push(symmetric);
+            }
+        }
+    }
+
+    Ok((
+        states,
+        neighborhood,
+        Transitions::Table {
+            variables,
+            transitions: expanded,
+            cache: RefCell::new(HashMap::new()),
+        },
+    ))
+}
+
+/// Split a table line at the commas, except for the ones in inline sets
+fn split_terms(text: &str) -> Vec<String> {
+    let mut terms = vec![String::new()];
+    let mut in_set = false;
+
+    for character in text.chars() {
+        match character {
+            ',' if !in_set => terms.push(String::new()),
+            character if character.is_whitespace() => {}
+            character => {
+                in_set = (in_set || character == '{') && character != '}';
+                terms.last_mut().unwrap().push(character);
+            }
+        }
+    }
+
+    terms
+}
+
+/// Every order of the terms, without repeating the ones that are the same because some terms are equal
+fn distinct_permutations(mut terms: Vec<Term>) -> Vec<Vec<Term>> {
+    terms.sort();
+    let mut permutations = vec![terms.clone()];
+
+    // step to the next permutation in lexicographic order until there is none
+    loop {
+        let Some(pivot) = (0..terms.len().saturating_sub(1))
+            .rev()
+            .find(|&idx| terms[idx] < terms[idx + 1])
+        else {
+            return permutations;
+        };
+        let swap = (pivot + 1..terms.len())
+            .rev()
+            .find(|&idx| terms[pivot] < terms[idx])
+            .expect("the term after the pivot is larger");
+
+        terms.swap(pivot, swap);
+        terms[pivot + 1..].reverse();
+        permutations.push(terms.clone());
+    }
+}
+
+fn parse_tree(lines: &[(usize, &str)]) -> ParseResult<(usize, GollyNeighborhood, Transitions)> {
+    let mut states = None;
+    let mut neighbors = None;
+    let mut nodes: Vec<Vec<usize>> = Vec::new();
+    let mut levels: Vec<usize> = Vec::new();
+
+    for &(line, text) in lines {
+        if let Some((key, value)) = text.split_once('=') {
+            match key.trim() {
+                "num_states" => states = Some(parse_states(line, value)?),
+                "num_neighbors" => {
+                    neighbors = Some(match value.trim() {
+                        "4" => GollyNeighborhood::VonNeumann,
+                        "8" => GollyNeighborhood::Moore,
+                        _ => return Err((line, "num_neighbors must be 4 or 8".to_string())),
+                    })
+                }
+                "num_nodes" => {}
+                other => return Err((line, format!("unknown key `{}`", other))),
+            }
+            continue;
+        }
+
+        let states = states.ok_or((line, "num_states must come first".to_string()))?;
+        let numbers = text
+            .split_whitespace()
+            .map(str::parse::<usize>)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| (line, "expected a node as numbers".to_string()))?;
+        let Some((&level, children)) = numbers.split_first() else {
+            continue;
+        };
+
+        if children.len() != states {
+            return Err((line, format!("expected {} entries after the level", states)));
+        }
+        let valid = children.iter().all(|&child| {
+            if level == 1 {
+                child < states
+            } else {
+                child < nodes.len() && levels[child] == level - 1
+            }
+        });
+        if level == 0 || !valid {
+            return Err((line, "node refers to a missing state or node".to_string()));
+        }
+
+        nodes.push(children.to_vec());
+        levels.push(level);
+    }
+
+    let states = states.ok_or((1, "missing num_states".to_string()))?;
+    let neighborhood = neighbors.ok_or((1, "missing num_neighbors".to_string()))?;
+    let root_level = neighborhood.tree_offsets().len();
+    if levels.last() != Some(&root_level) {
+        return Err((
+            1,
+            format!("the last node must be the root, at level {}", root_level),
+        ));
+    }
+
+    Ok((states, neighborhood, Transitions::Tree { nodes }))
+}
+
+/// Parse the colors of states, given as `state r g b`, or as `r g b r g b` for a gradient over all states but 0.
+/// States without a color get a shade of gray.
+fn parse_colors(lines: &[(usize, &str)], states: usize) -> ParseResult<Vec<Rgba>> {
+    let mut palette: Vec<Rgba> = (0..states)
+        .map(|state| {
+            let shade = (48 + state * 207 / (states - 1)) as u8;
+            Rgba::rgb(shade, shade, shade)
+        })
+        .collect();
+
+    for &(line, text) in lines {
+        let numbers = text
+            .split_whitespace()
+            .map(str::parse::<usize>)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| (line, "expected numbers".to_string()))?;
+        let component = |value: usize| {
+            u8::try_from(value).map_err(|_| (line, format!("`{}` is not a color component", value)))
+        };
+
+        match numbers[..] {
+            [state, r, g, b] if state < states => {
+                palette[state] = Rgba::rgb(component(r)?, component(g)?, component(b)?)
+            }
+            [r1, g1, b1, r2, g2, b2] => {
+                let (from, to) = ([r1, g1, b1], [r2, g2, b2]);
+                for (state, color) in palette.iter_mut().enumerate().skip(1) {
+                    let t = if states > 2 {
+                        (state - 1) as f32 / (states - 2) as f32
+                    } else {
+                        0.
+                    };
+                    let mix = |idx: usize| -> ParseResult<u8> {
+                        let (from, to) = (component(from[idx])? as f32, component(to[idx])? as f32);
+                        Ok((from + (to - from) * t).round() as u8)
+                    };
+                    *color = Rgba::rgb(mix(0)?, mix(1)?, mix(2)?);
+                }
+            }
+            _ => return Err((line, "expected `state r g b` or `r g b r g b`".to_string())),
+        }
+    }
+
+    Ok(palette)
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Rng;
+
+    /// Wireworld written with variables and permute symmetry, the way Golly ships it
+    const WIREWORLD: &str = "@RULE WireWorld
+# empty, electron head, electron tail, conductor
+
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+
+var a={0,1,2,3}
+var b={0,1,2,3}
+var c={0,1,2,3}
+var d={0,1,2,3}
+var e={0,1,2,3}
+var f={0,1,2,3}
+var g={0,1,2,3}
+var h={0,1,2,3}
+var i={0,2,3}
+var j={i}
+var k={i}
+var l={i}
+var m={i}
+var n={i}
+var o={i}
+
+1,a,b,c,d,e,f,g,h,2 # heads turn into tails
+2,a,b,c,d,e,f,g,h,3 # tails turn into conductors
+3,1,i,j,k,l,m,n,o,1 # conductors next to one or two heads turn into heads
+3,1,1,i,j,k,l,m,n,1
+
+@COLORS
+0 48 48 48
+1 0 128 255
+2 255 255 255
+3 255 128 0
+";
+
+    /// Every cell takes the state of its northern neighbor
+    const FALLING: &str = "@RULE Falling
+@TREE
+num_states=2
+num_neighbors=4
+num_nodes=9
+1 0 0
+1 1 1
+2 0 0
+2 1 1
+3 2 2
+3 3 3
+4 4 4
+4 5 5
+5 6 7
+";
+
+    fn wire(simulation: &mut dyn Simulation, row: usize, columns: std::ops::Range<usize>) {
+        for column in columns {
+            simulation.set_state(row, column, 3);
+        }
+    }
+
+    #[test]
+    fn runs_tables() {
+        let rule = GollyRule::parse(WIREWORLD, "WireWorld.rule").unwrap();
+        assert_eq!(rule.name(), "WireWorld");
+        assert_eq!(rule.palette().unwrap()[3], Rgba::rgb(255, 128, 0));
+
+        // an electron travelling down a wire
+        let mut simulation = Runner::new(rule, 3, 6);
+        wire(&mut simulation, 1, 0..6);
+        simulation.set_state(1, 0, 2);
+        simulation.set_state(1, 1, 1);
+
+        simulation.step(&mut Rng::new(0));
+        assert_eq!(
+            (0..6).map(|c| simulation.state(1, c)).collect::<Vec<_>>(),
+            [3, 2, 1, 3, 3, 3]
+        );
+        simulation.step(&mut Rng::new(0));
+        assert_eq!(
+            (0..6).map(|c| simulation.state(1, c)).collect::<Vec<_>>(),
+            [3, 3, 2, 1, 3, 3]
+        );
+    }
+
+    #[test]
+    fn runs_trees() {
+        let rule = GollyRule::parse(FALLING, "Falling.rule").unwrap();
+        let mut simulation = Runner::new(rule, 3, 3);
+        simulation.set_state(0, 1, 1);
+
+        simulation.step(&mut Rng::new(0));
+
+        assert_eq!(simulation.state(0, 1), 0);
+        assert_eq!(simulation.state(1, 1), 1);
+        assert_eq!(simulation.state(1, 0), 0);
+    }
+
+    #[test]
+    fn expands_symmetries() {
+        assert_eq!(
+            distinct_permutations(vec![Term::State(1), Term::State(0), Term::State(0)]).len(),
+            3
+        );
+        assert_eq!(
+            GollyNeighborhood::Moore
+                .symmetries("rotate4reflect")
+                .unwrap()
+                .unwrap()
+                .len(),
+            8
+        );
+        assert_eq!(
+            GollyNeighborhood::Hexagonal
+                .symmetries("rotate3")
+                .unwrap()
+                .unwrap(),
+            [[0, 1, 2, 3, 4, 5], [2, 3, 4, 5, 0, 1], [4, 5, 0, 1, 2, 3]]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_rule_files() {
+        let err = |contents: &str| GollyRule::parse(contents, "test.rule").unwrap_err();
+
+        assert_eq!(
+            err(&WIREWORLD.replace("n_states:4", "n_states:300")),
+            "Invalid rule file test.rule: line 5: the number of states must be between 2 and 256"
+        );
+        assert!(err(&WIREWORLD.replace("1,a,b,c,d,e,f,g,h,2", "1,a,b,2")).contains("line 25"));
+        assert!(err(&WIREWORLD.replace("permute", "rotate6")).contains("unsupported symmetries"));
+        assert!(err(&FALLING.replace("5 6 7", "5 6 9")).contains("missing state or node"));
+        assert!(err("@TABLE\n").contains("missing @RULE name"));
+        assert!(GollyRule::matches("rules/WireWorld.rule"));
+    }
+}
@@ -0,0 +1,478 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{GenerationCounts, Parameter, Rgba, Simulation};
+use crate::grid::Grid;
+use crate::neighborhood::MOORE;
+use crate::patterns::Pattern;
+use crate::registry::Notation;
+use crate::simulations::continuous::{self, LEVELS};
+use crate::util::{has_prefix, parse_parts, Rng};
+
+/// Feed and kill rates from Pearson's and Karl Sims's maps of the model, each giving a different kind of pattern
+pub const PRESETS: [(&str, &str); 4] = [
+    ("Gray-Scott mitosis", "GrayScott/F0.0367/K0.0649/S10"),
+    ("Gray-Scott coral", "GrayScott/F0.0545/K0.062/S10"),
+    ("Gray-Scott spots", "GrayScott/F0.03/K0.062/S10"),
+    ("Gray-Scott worms", "GrayScott/F0.078/K0.061/S10"),
+];
+
+const PREFIX: &str = "GrayScott";
+/// How fast each chemical spreads, per integration step. V spreads slower than U, which is what makes patterns form.
+const DIFFUSION_U: f32 = 1.;
+const DIFFUSION_V: f32 = 0.5;
+/// Weights of the neighbors in the discrete Laplacian, which add up to the weight of the cell itself
+const EDGE_WEIGHT: f32 = 0.2;
+const CORNER_WEIGHT: f32 = 0.05;
+const MAX_RATE: f64 = 0.1;
+const MAX_STEPS: usize = 50;
+/// Smaller amounts are rounded down to 0, see [settle]
+const MIN_AMOUNT: f32 = 1e-6;
+/// Cells with at least this much V count as alive
+const ALIVE_THRESHOLD: f32 = 0.2;
+/// Side of the square of V that runs start from, and of the squares in random soups
+const SEED_SIDE: usize = 20;
+/// U and V in the squares, strong enough to grow a pattern with any of the presets
+const SEED_AMOUNT: f32 = 0.5;
+const MEASUREMENT_LABELS: [&str; 2] = ["mean U", "mean V"];
+
+/// The Gray-Scott model of two chemicals reacting and diffusing, written as `GrayScott/F<feed>/K<kill>/S<steps>`, e.g.
+/// `GrayScott/F0.0367/K0.0649/S10`. U is fed into the grid at rate `F` and turned into V where V is present
+/// (U + 2V -> 3V), while V is removed at rate `F + k`. Every generation is `S` integration steps.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GrayScott {
+    feed: f64,
+    kill: f64,
+    steps: usize,
+}
+
+impl Notation for GrayScott {
+    const PRESETS: &'static [(&'static str, &'static str)] = &PRESETS;
+
+    fn matches(rulestring: &str) -> bool {
+        has_prefix(rulestring, PREFIX)
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+        let [feed, kill, steps] = parse_parts(rulestring, PREFIX, ['F', 'K', 'S'])?;
+
+        let rate = |value: &str, letter: char| {
+            value
+                .parse()
+                .ok()
+                .filter(|value| (0. ..=MAX_RATE).contains(value))
+                .ok_or_else(|| invalid(&format!("{} must be between 0 and {}", letter, MAX_RATE)))
+        };
+
+        Ok(Self {
+            feed: rate(feed, 'F')?,
+            kill: rate(kill, 'K')?,
+            steps: steps
+                .parse()
+                .ok()
+                .filter(|steps| (1..=MAX_STEPS).contains(steps))
+                .ok_or_else(|| invalid(&format!("S must be between 1 and {}", MAX_STEPS)))?,
+        })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(ReactionDiffusion::new(self, rows, columns))
+    }
+}
+
+impl GrayScott {
+    /// U and V of a cell after an integration step, given the Laplacians of U and V around it
+    fn react(&self, u: f32, v: f32, laplacian_u: f32, laplacian_v: f32) -> (f32, f32) {
+        let (feed, kill) = (self.feed as f32, self.kill as f32);
+        let reaction = u * v * v;
+
+        (
+            settle(u + DIFFUSION_U * laplacian_u - reaction + feed * (1. - u)),
+            settle(v + DIFFUSION_V * laplacian_v + reaction - (feed + kill) * v),
+        )
+    }
+}
+
+impl Display for GrayScott {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/F{}/K{}/S{}",
+            PREFIX, self.feed, self.kill, self.steps
+        )
+    }
+}
+
+/// Runs the [GrayScott] model on a grid that wraps around at the edges. Cells are drawn and painted by their
+/// amount of V, rounded to one of [LEVELS] levels, and the chart follows the mean amounts of U and V.
+pub struct ReactionDiffusion {
+    rule: GrayScott,
+    u: Grid<f32>,
+    v: Grid<f32>,
+    /// Double buffers for the integration steps
+    next_u: Grid<f32>,
+    next_v: Grid<f32>,
+}
+
+impl ReactionDiffusion {
+    /// Create a runner with a square of V in the middle of the grid
+    pub fn new(rule: GrayScott, rows: usize, columns: usize) -> Self {
+        let mut simulation = Self {
+            rule,
+            u: Grid::new(rows, columns, 1.),
+            v: Grid::new(rows, columns, 0.),
+            next_u: Grid::new(rows, columns, 1.),
+            next_v: Grid::new(rows, columns, 0.),
+        };
+        simulation.clear();
+
+        simulation
+    }
+
+    pub fn rule(&self) -> &GrayScott {
+        &self.rule
+    }
+
+    pub fn u(&self) -> &Grid<f32> {
+        &self.u
+    }
+
+    pub fn v(&self) -> &Grid<f32> {
+        &self.v
+    }
+
+    /// The weighted sum of the cells around a cell, minus the cell itself, which is how much flows into it
+    fn laplacian(grid: &Grid<f32>, row: usize, column: usize) -> f32 {
+        let (rows, columns) = (grid.rows() as isize, grid.columns() as isize);
+        let cell = |row_offset: isize, column_offset: isize| {
+            let r = (row as isize + row_offset).rem_euclid(rows) as usize;
+            let c = (column as isize + column_offset).rem_euclid(columns) as usize;
+            grid[r][c]
+        };
+
+        let edges = cell(-1, 0) + cell(1, 0) + cell(0, -1) + cell(0, 1);
+        let corners = cell(-1, -1) + cell(-1, 1) + cell(1, -1) + cell(1, 1);
+        EDGE_WEIGHT * edges + CORNER_WEIGHT * corners - grid[row][column]
+    }
+
+    /// Advance both chemicals by one integration step
+    fn integrate(&mut self) {
+        let (rows, columns) = (self.u.rows(), self.u.columns());
+
+        for row in 0..rows {
+            let (up, down) = ((row + rows - 1) % rows, (row + 1) % rows);
+            let u_rows = [&self.u[up], &self.u[row], &self.u[down]];
+            let v_rows = [&self.v[up], &self.v[row], &self.v[down]];
+            let (next_u, next_v) = (&mut self.next_u[row], &mut self.next_v[row]);
+
+            for column in 0..columns {
+                let left = if column == 0 { columns - 1 } else { column - 1 };
+                let right = if column == columns - 1 { 0 } else { column + 1 };
+                // the same as [ReactionDiffusion::laplacian], without wrapping every offset on its own
+                let laplacian = |[up, row, down]: [&[f32]; 3]| {
+                    let edges = up[column] + down[column] + row[left] + row[right];
+                    let corners = up[left] + up[right] + down[left] + down[right];
+                    EDGE_WEIGHT * edges + CORNER_WEIGHT * corners - row[column]
+                };
+
+                let (u, v) = self.rule.react(
+                    u_rows[1][column],
+                    v_rows[1][column],
+                    laplacian(u_rows),
+                    laplacian(v_rows),
+                );
+                next_u[column] = u;
+                next_v[column] = v;
+            }
+        }
+
+        std::mem::swap(&mut self.u, &mut self.next_u);
+        std::mem::swap(&mut self.v, &mut self.next_v);
+    }
+}
+
+impl Simulation for ReactionDiffusion {
+    fn name(&self) -> String {
+        "Gray-Scott".to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        self.rule.to_string()
+    }
+
+    fn rows(&self) -> usize {
+        self.v.rows()
+    }
+
+    fn columns(&self) -> usize {
+        self.v.columns()
+    }
+
+    fn state_count(&self) -> usize {
+        LEVELS
+    }
+
+    /// Runs as many integration steps as the rule asks for
+    fn step(&mut self, _rng: &mut Rng) -> GenerationCounts {
+        let previous = self.v.clone();
+        for _ in 0..self.rule.steps {
+            self.integrate();
+        }
+
+        let mut counts = GenerationCounts::default();
+        for (&before, &after) in previous
+            .iter_rows()
+            .flatten()
+            .zip(self.v.iter_rows().flatten())
+        {
+            let (was_alive, is_alive) = (before >= ALIVE_THRESHOLD, after >= ALIVE_THRESHOLD);
+            if is_alive {
+                counts.alive += 1;
+            } else if continuous::to_state(after) != 0 {
+                counts.dying += 1;
+            }
+            match (was_alive, is_alive) {
+                (false, true) => counts.births += 1,
+                (true, false) => counts.deaths += 1,
+                _ => {}
+            }
+        }
+
+        counts
+    }
+
+    fn state(&self, row: usize, column: usize) -> u8 {
+        continuous::to_state(self.v[row][column])
+    }
+
+    /// Sets the amount of V, and leaves U as it is
+    fn write_state(&mut self, row: usize, column: usize, state: u8) {
+        self.v[row][column] = state as f32 / (LEVELS - 1) as f32;
+    }
+
+    /// Fill the grid with U, and put a square of V in the middle for it to react with
+    fn clear(&mut self) {
+        self.u.fill(1.);
+        self.v.fill(0.);
+
+        let (top, left) = (
+            self.v.rows().saturating_sub(SEED_SIDE) / 2,
+            self.v.columns().saturating_sub(SEED_SIDE) / 2,
+        );
+        for row in top..(top + SEED_SIDE).min(self.v.rows()) {
+            for column in left..(left + SEED_SIDE).min(self.v.columns()) {
+                self.u[row][column] = SEED_AMOUNT;
+                self.v[row][column] = SEED_AMOUNT;
+            }
+        }
+    }
+
+    /// Soups are squares of V scattered over a grid full of U, that cover `density` of it
+    fn randomize(&mut self, rng: &mut Rng, density: f64) {
+        self.u.fill(1.);
+        continuous::scatter_patches(&mut self.v, rng, density, SEED_SIDE, |_| SEED_AMOUNT);
+        for (u, &v) in self
+            .u
+            .iter_rows_mut()
+            .flatten()
+            .zip(self.v.iter_rows().flatten())
+        {
+            if v > 0. {
+                *u = SEED_AMOUNT;
+            }
+        }
+    }
+
+    fn is_alive(&self, row: usize, column: usize) -> bool {
+        self.v[row][column] >= ALIVE_THRESHOLD
+    }
+
+    /// V after a single integration step, rather than all the steps of a generation
+    fn next_state(&self, row: usize, column: usize) -> u8 {
+        let (_, v) = self.rule.react(
+            self.u[row][column],
+            self.v[row][column],
+            Self::laplacian(&self.u, row, column),
+            Self::laplacian(&self.v, row, column),
+        );
+
+        continuous::to_state(v)
+    }
+
+    /// Live cells around a cell, wrapping around the edges like the update does
+    fn count_neighbors(&self, row: usize, column: usize) -> u32 {
+        let (rows, columns) = (self.v.rows() as isize, self.v.columns() as isize);
+
+        MOORE
+            .iter()
+            .filter(|&&(row_offset, column_offset)| {
+                let r = (row as isize + row_offset).rem_euclid(rows) as usize;
+                let c = (column as isize + column_offset).rem_euclid(columns) as usize;
+                self.v[r][c] >= ALIVE_THRESHOLD
+            })
+            .count() as u32
+    }
+
+    fn state_name(&self, state: u8) -> String {
+        format!("V = {:.2}", state as f32 / (LEVELS - 1) as f32)
+    }
+
+    /// Drawn with the theme's colormap instead
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        None
+    }
+
+    /// Starts from a single square of V, see [ReactionDiffusion::clear]
+    fn starts_empty(&self) -> bool {
+        true
+    }
+
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        Vec::new()
+    }
+
+    fn measurements(&self) -> Vec<(&'static str, f32)> {
+        let cells = (self.u.rows() * self.u.columns()) as f32;
+        let mean = |grid: &Grid<f32>| grid.iter_rows().flatten().sum::<f32>() / cells;
+
+        MEASUREMENT_LABELS
+            .into_iter()
+            .zip([mean(&self.u), mean(&self.v)])
+            .collect()
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::real("F", self.rule.feed),
+            Parameter::real("k", self.rule.kill),
+            Parameter::integer("steps", self.rule.steps),
+        ]
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        match idx {
+            0 => self.rule.feed = value.clamp(0., MAX_RATE),
+            1 => self.rule.kill = value.clamp(0., MAX_RATE),
+            2 => self.rule.steps = (value.round() as usize).clamp(1, MAX_STEPS),
+            _ => {}
+        }
+    }
+
+    /// Both amounts, since cells only keep V, and only to a level
+    fn session_state(&self) -> String {
+        continuous::grid_session_state("u", &self.u) + &continuous::grid_session_state("v", &self.v)
+    }
+
+    fn restore_session_state(&mut self, state: &str) -> Result<(), String> {
+        if state.trim().is_empty() {
+            return Ok(());
+        }
+
+        continuous::restore_grid(state, "u", &mut self.u)?;
+        continuous::restore_grid(state, "v", &mut self.v)
+    }
+
+    fn is_continuous(&self) -> bool {
+        true
+    }
+}
+
+/// Clamp an amount to between 0 and 1, and drop traces too small to matter. V decays exponentially where it dies out,
+/// and would otherwise end up in subnormal numbers, which are many times slower to compute with.
+fn settle(value: f32) -> f32 {
+    if value < MIN_AMOUNT {
+        0.
+    } else {
+        value.min(1.)
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rulestrings() {
+        for (_, rulestring) in PRESETS {
+            assert_eq!(
+                GrayScott::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        assert!(GrayScott::parse("GrayScott/F0.2/K0.06/S10").is_err());
+        assert!(GrayScott::parse("GrayScott/F0.03/K0.06/S0").is_err());
+        assert!(GrayScott::parse("GrayScott/F0.03/K0.06").is_err());
+        assert!(GrayScott::matches("grayscott/F0.03"));
+    }
+
+    #[test]
+    fn uniform_grids_stay_uniform() {
+        let mut simulation = ReactionDiffusion::new(GrayScott::parse(PRESETS[0].1).unwrap(), 8, 8);
+        simulation.u.fill(1.);
+        simulation.v.fill(0.);
+
+        simulation.step(&mut Rng::new(0));
+        assert_eq!(simulation.measurements(), [("mean U", 1.), ("mean V", 0.)]);
+    }
+
+    #[test]
+    fn integrates_several_steps_per_generation() {
+        let rule = GrayScott::parse("GrayScott/F0.03/K0.062/S1").unwrap();
+        let mut single = ReactionDiffusion::new(rule.clone(), 32, 32);
+        let mut multiple = ReactionDiffusion::new(rule, 32, 32);
+        multiple.set_parameter(2, 4.);
+
+        for _ in 0..4 {
+            single.step(&mut Rng::new(0));
+        }
+        multiple.step(&mut Rng::new(0));
+
+        assert_eq!(single.v(), multiple.v());
+        assert_eq!(multiple.rulestring(), "GrayScott/F0.03/K0.062/S4");
+    }
+
+    #[test]
+    fn presets_grow_patterns_from_the_seed() {
+        for (name, rulestring) in PRESETS {
+            let mut simulation =
+                ReactionDiffusion::new(GrayScott::parse(rulestring).unwrap(), 64, 64);
+            for _ in 0..200 {
+                simulation.step(&mut Rng::new(0));
+            }
+
+            // V spreads out of the seed in the middle instead of dying out
+            let seed = (64 - SEED_SIDE) / 2..(64 + SEED_SIDE) / 2;
+            let spread = (0..64)
+                .flat_map(|row| (0..64).map(move |column| (row, column)))
+                .filter(|(row, column)| !seed.contains(row) || !seed.contains(column))
+                .filter(|&(row, column)| simulation.is_alive(row, column))
+                .count();
+            assert!(spread > 0, "{}", name);
+        }
+    }
+
+    #[test]
+    fn paints_v() {
+        let mut simulation =
+            ReactionDiffusion::new(GrayScott::parse(PRESETS[0].1).unwrap(), 32, 32);
+        simulation.set_state(0, 0, 255);
+
+        assert_eq!(simulation.v()[0][0], 1.);
+        assert_eq!(simulation.u()[0][0], 1.);
+        assert_eq!(simulation.state_name(simulation.state(0, 0)), "V = 1.00");
+    }
+
+    #[test]
+    fn counts_neighbors_across_the_edges() {
+        let mut simulation =
+            ReactionDiffusion::new(GrayScott::parse(PRESETS[0].1).unwrap(), 32, 32);
+        simulation.clear();
+        simulation.set_state(31, 31, 255);
+        simulation.set_state(0, 31, 255);
+        simulation.set_state(31, 0, 255);
+
+        assert_eq!(simulation.count_neighbors(0, 0), 3);
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Automaton, Rgba, Runner, Simulation};
+use crate::grid::Grid;
+use crate::neighborhood::Neighborhood;
+use crate::patterns::Pattern;
+use crate::registry::Notation;
+use crate::simulations::cyclic::RangeParameters;
+use crate::util::{count_cells, Rng};
+
+/// Waves that any excited neighbor sets off, with 3 refractory states, so the arms of spirals are 5 cells apart
+pub const DEFAULT: &str = "GH/R1/T1/C5/NM";
+
+/// Length of the wavefront that the spiral pair preset cuts, in neighborhood ranges
+const SPIRAL_PAIR_LENGTH: usize = 24;
+
+const RESTING: u8 = 0;
+const EXCITED: u8 = 1;
+
+/// The Greenberg-Hastings model of excitable media, written like a cyclic rule with a `GH/` prefix, e.g.
+/// `GH/R1/T1/C5/NM`. Resting cells (state 0) get excited (state 1) when at least `T` of their neighbors within range
+/// `R` are excited. Excited cells then go through the `C - 2` refractory states, where they can't be excited, and
+/// come back to rest. Brian's Brain is a close relative, with one refractory state and exactly 2 excited neighbors.
+///
+/// Waves of excitement annihilate when they meet, and a wave with a free end curls around it into a rotating spiral.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GreenbergHastings {
+    parameters: RangeParameters,
+    /// Offsets of the neighborhood, kept around so that they aren't computed for every cell
+    offsets: Vec<(isize, isize)>,
+}
+
+impl Notation for GreenbergHastings {
+    const PRESETS: &'static [(&'static str, &'static str)] = &[("Greenberg-Hastings", DEFAULT)];
+
+    fn matches(rulestring: &str) -> bool {
+        rulestring.trim().starts_with("GH/")
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let parts = rulestring.trim().strip_prefix("GH/").ok_or_else(|| {
+            format!(
+                "Invalid rule `{}`: expected it to start with GH/",
+                rulestring
+            )
+        })?;
+
+        // waves need a refractory state behind them, or they would excite the cells they came from
+        let parameters = RangeParameters::parse(rulestring, parts, 3)?;
+        Ok(Self {
+            offsets: parameters.neighborhood().offsets(),
+            parameters,
+        })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(Runner::new(self, rows, columns))
+    }
+}
+
+impl GreenbergHastings {
+    /// A straight wavefront of the given length, moving up: a row of excited cells, with rows of refractory cells
+    /// below it, so that the wave can't spread down. Cells are (row, column, state) relative to the upper left end.
+    pub fn wavefront(&self, length: usize) -> Vec<(usize, usize, u8)> {
+        // thick enough that excitement can't jump over a refractory state
+        let (range, refractory_states) = (self.parameters.range, self.parameters.states - 2);
+
+        (0..=refractory_states * range)
+            .flat_map(|row| {
+                let state = match row {
+                    0 => EXCITED,
+                    row => EXCITED + 1 + ((row - 1) / range) as u8,
+                };
+                (0..length).map(move |column| (row, column, state))
+            })
+            .collect()
+    }
+}
+
+impl Automaton for GreenbergHastings {
+    type State = u8;
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        self.to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        self.parameters.states
+    }
+
+    fn next_state(&self, grid: &Grid<u8>, row: usize, column: usize) -> u8 {
+        match grid[row][column] {
+            RESTING if self.count_neighbors(grid, row, column) >= self.parameters.threshold => {
+                EXCITED
+            }
+            RESTING => RESTING,
+            state => ((state as usize + 1) % self.parameters.states) as u8,
+        }
+    }
+
+    fn neighborhood(&self) -> Neighborhood {
+        self.parameters.neighborhood()
+    }
+
+    /// Excited cells in the neighborhood
+    fn count_neighbors(&self, grid: &Grid<u8>, row: usize, column: usize) -> u32 {
+        count_cells(grid, &self.offsets, row, column, |cell| cell == EXCITED)
+    }
+
+    /// Excited cells are white, and fade through orange to dark red as they recover
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        let refractory_states = self.parameters.states - 2;
+        let fade = (0..refractory_states).map(|idx| {
+            let t = idx as f32 / refractory_states as f32;
+            let lerp = |from: f32, to: f32| (from + (to - from) * t).round() as u8;
+            Rgba::rgb(lerp(255., 96.), lerp(160., 0.), lerp(0., 32.))
+        });
+
+        Some(
+            [Rgba::rgb(16, 16, 32), Rgba::rgb(255, 255, 255)]
+                .into_iter()
+                .chain(fade)
+                .collect(),
+        )
+    }
+
+    /// Starts from a spiral seed
+    fn starts_empty(&self) -> bool {
+        true
+    }
+
+    /// A spiral seed: a wavefront cut from the middle of the grid to its right edge, which winds up into a single
+    /// spiral around its free end
+    fn reset(&self, grid: &mut Grid<u8>) {
+        grid.fill(RESTING);
+
+        let (top, left) = (grid.rows() / 2, grid.columns() / 2);
+        for (row, column, state) in self.wavefront(grid.columns() - left) {
+            if top + row < grid.rows() {
+                grid[top + row][left + column] = state;
+            }
+        }
+    }
+
+    /// A wavefront with two free ends, which wind up into a pair of spirals turning opposite ways
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        let cells = self.wavefront(SPIRAL_PAIR_LENGTH * self.parameters.range);
+        let pattern = Pattern {
+            rows: cells.iter().map(|&(row, ..)| row + 1).max().unwrap_or(0),
+            columns: SPIRAL_PAIR_LENGTH * self.parameters.range,
+            cells,
+            rule: Some(self.rulestring()),
+        };
+
+        vec![("Spiral pair".to_string(), pattern)]
+    }
+
+    /// Soups are resting cells, where the density is the fraction of cells that are excited or refractory instead
+    fn random_state(&self, rng: &mut Rng, density: f64) -> u8 {
+        if rng.next_f64() < density {
+            (1 + rng.next_u64() % (self.parameters.states as u64 - 1)) as u8
+        } else {
+            RESTING
+        }
+    }
+}
+
+impl Display for GreenbergHastings {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GH/{}", self.parameters)
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn excited_cells(simulation: &Runner<GreenbergHastings>) -> usize {
+        simulation
+            .grid()
+            .iter_rows()
+            .flatten()
+            .filter(|&&cell| cell == EXCITED)
+            .count()
+    }
+
+    #[test]
+    fn parses_rulestrings() {
+        for rulestring in [DEFAULT, "GH/R2/T4/C10/NN"] {
+            assert_eq!(
+                GreenbergHastings::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        assert!(GreenbergHastings::parse("GH/R1/T1/C2/NM").is_err());
+        assert!(GreenbergHastings::parse("GH/R1/T9/C5/NM").is_err());
+        assert!(GreenbergHastings::matches("GH/R1/T1/C5/NN"));
+        assert!(!GreenbergHastings::matches("R1/T1/C5/NN"));
+    }
+
+    #[test]
+    fn excited_cells_recover_through_the_refractory_states() {
+        let rule = GreenbergHastings::parse("GH/R1/T2/C4/NM").unwrap();
+        let mut grid = Grid::new(3, 3, RESTING);
+        grid[0][0] = EXCITED;
+
+        assert_eq!(rule.next_state(&grid, 1, 1), RESTING);
+        grid[2][2] = EXCITED;
+        assert_eq!(rule.next_state(&grid, 1, 1), EXCITED);
+        // refractory cells ignore their excited neighbors
+        grid[1][1] = 3;
+        assert_eq!(rule.next_state(&grid, 1, 1), RESTING);
+        assert_eq!(rule.next_state(&grid, 0, 0), 2);
+    }
+
+    #[test]
+    fn wavefronts_only_move_forward() {
+        let rule = GreenbergHastings::parse("GH/R2/T1/C4/NM").unwrap();
+        let cells = rule.wavefront(3);
+
+        assert_eq!(cells.len(), 3 * 5);
+        assert!(cells.contains(&(0, 2, EXCITED)));
+        assert!(cells.contains(&(2, 0, 2)));
+        assert!(cells.contains(&(4, 1, 3)));
+
+        let mut simulation = Runner::new(rule, 12, 3);
+        for (row, column, state) in cells {
+            simulation.set_state(row + 5, column, state);
+        }
+        for _ in 0..2 {
+            simulation.step(&mut Rng::new(0));
+        }
+
+        // a range higher on every step, and nothing excited behind
+        assert!((1..3).all(|row| (0..3).all(|column| simulation.state(row, column) == EXCITED)));
+        assert!((3..12).all(|row| (0..3).all(|column| simulation.state(row, column) != EXCITED)));
+    }
+
+    #[test]
+    fn spiral_seeds_keep_turning() {
+        let rule = GreenbergHastings::parse(DEFAULT).unwrap();
+
+        // a wave without free ends leaves the grid
+        let mut simulation = Runner::new(rule.clone(), 48, 48);
+        for (row, column, state) in rule.wavefront(48) {
+            simulation.set_state(row + 24, column, state);
+        }
+        for _ in 0..100 {
+            simulation.step(&mut Rng::new(0));
+        }
+        assert_eq!(excited_cells(&simulation), 0);
+
+        // but a cut one winds up into a spiral
+        simulation.clear();
+        for _ in 0..300 {
+            simulation.step(&mut Rng::new(0));
+        }
+        assert!(excited_cells(&simulation) > 0);
+
+        // once it fills the grid, every cell cycles through the states in step with the spiral
+        let grid = simulation.grid().clone();
+        for _ in 0..5 {
+            simulation.step(&mut Rng::new(0));
+        }
+        assert_eq!(simulation.grid(), &grid);
+    }
+}
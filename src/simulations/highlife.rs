@@ -1,3 +1,5 @@
+use crate::automaton::Automaton;
+use crate::neighborhood::MOORE;
 use crate::util::count_cells;
 use crate::{CellState, SimulationState};
 
@@ -7,18 +9,61 @@ pub fn get_highlife_next_cell_state(
     row: usize,
     column: usize,
 ) -> CellState {
-    let live_cell_count = count_cells(state, row, column, |cell| cell == CellState::Alive);
+    let live_cell_count = count_cells(state, &MOORE, row, column, |cell| cell == CellState::Alive);
 
-    // underpopulated or overpopulated
-    if live_cell_count < 2 || live_cell_count > 3 {
-        return CellState::Dead;
+    match (state[row][column], live_cell_count) {
+        // just right 👌
+        (CellState::Alive, 2..=3) => CellState::Alive,
+        // dead cells come alive with 3 or 6 neighbors, which is what makes replicators possible
+        (CellState::Dead, 3 | 6) => CellState::Alive,
+        // underpopulated or overpopulated
+        _ => CellState::Dead,
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HighLife;
+
+impl Automaton for HighLife {
+    type State = CellState;
+
+    fn name(&self) -> String {
+        "HighLife".to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        "B36/S23".to_string()
     }
 
-    // just right 👌
-    if live_cell_count == 3 || live_cell_count == 6 {
-        return CellState::Alive;
+    fn state_count(&self) -> usize {
+        2
     }
 
-    // unchanged otherwise
-    state[row][column]
+    fn next_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        get_highlife_next_cell_state(state, row, column)
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_cells_are_born_with_3_or_6_neighbors() {
+        let mut state = SimulationState::new(3, 3, CellState::Dead);
+        for (row, column) in [(0, 0), (0, 1), (0, 2)] {
+            state[row][column] = CellState::Alive;
+        }
+        assert_eq!(get_highlife_next_cell_state(&state, 1, 1), CellState::Alive);
+
+        for (row, column) in [(2, 0), (2, 1), (2, 2)] {
+            state[row][column] = CellState::Alive;
+        }
+        assert_eq!(get_highlife_next_cell_state(&state, 1, 1), CellState::Alive);
+
+        // live cells don't survive with 6 neighbors, only with 2 or 3
+        state[1][1] = CellState::Alive;
+        assert_eq!(get_highlife_next_cell_state(&state, 1, 1), CellState::Dead);
+    }
 }
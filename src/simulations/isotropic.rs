@@ -0,0 +1,375 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Automaton, Runner, Simulation};
+use crate::registry::Notation;
+use crate::util::neighborhood_index;
+use crate::{CellState, SimulationState};
+
+/// Letters of the ways to arrange 0 to 4 live neighbors, in the order Hensel notation lists them. Arrangements of
+/// 5 to 8 neighbors use the letter of their complement, e.g. `5a` is `3a` with live and dead neighbors swapped.
+const LETTERS: [&str; 5] = ["", "ce", "cekain", "cekainyqjr", "cekainyqjrtwz"];
+
+/// One arrangement of live neighbors for each letter in [LETTERS], with the neighbors NW, N, NE, W, E, SW, S and SE
+/// as the bits from lowest to highest. The other arrangements with the same letter are its rotations and
+/// reflections.
+const ARRANGEMENTS: [&[u8]; 5] = [
+    &[0x00],
+    &[0x01, 0x02],
+    &[0x05, 0x0a, 0x22, 0x03, 0x18, 0x24],
+    &[0x25, 0x1a, 0x32, 0x0b, 0x07, 0x0d, 0x31, 0x26, 0x0e, 0x19],
+    &[
+        0xa5, 0x5a, 0x8e, 0x0f, 0x1d, 0x27, 0xa3, 0x36, 0x3a, 0x1b, 0x47, 0x93, 0xc3,
+    ],
+];
+
+/// Position of each neighbor bit of [ARRANGEMENTS] in the 3x3 block, as (row, column)
+const NEIGHBOR_POSITIONS: [(usize, usize); 8] = [
+    (0, 0),
+    (0, 1),
+    (0, 2),
+    (1, 0),
+    (1, 2),
+    (2, 0),
+    (2, 1),
+    (2, 2),
+];
+
+/// An isotropic non-totalistic rule: like a Life-like rule, but births and survivals depend on how the live
+/// neighbors are arranged, not only on how many there are. Written in Hensel notation, where a neighbor count can be
+/// followed by letters that pick some of the arrangements (`B2a`) or by a `-` and the letters to leave out (`B2-a`),
+/// e.g. `B3/S2-i34q` for tlife. Like [crate::simulations::lifelike::LifeLikeRule], a third `C` part makes cells decay
+/// for a generation before dying.
+#[derive(Clone, PartialEq, Debug)]
+pub struct IsotropicRule {
+    /// Whether the cell is alive in the next generation, indexed by the 3x3 block around it, see
+    /// [neighborhood_index]
+    table: Vec<bool>,
+    /// 2 for regular rules, 3 if cells decay before dying
+    states: u8,
+}
+
+impl Notation for IsotropicRule {
+    /// Whether the rulestring is meant to be in Hensel notation, which is when any of the neighbor counts is followed
+    /// by letters. Rules with plain neighbor counts are left to [crate::simulations::lifelike::LifeLikeRule].
+    fn matches(rulestring: &str) -> bool {
+        rulestring.as_bytes().windows(2).any(|pair| {
+            pair[0].is_ascii_digit() && (pair[1] == b'-' || LETTERS[4].contains(pair[1] as char))
+        })
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+
+        let parts: Vec<&str> = rulestring.trim().split('/').collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(invalid("expected B/S notation, e.g. B2-a/S12"));
+        }
+
+        let mut table = vec![false; 512];
+        let mut has_birth = false;
+        let mut has_survival = false;
+        let mut states = 2;
+
+        for part in parts {
+            let part = part.trim();
+            let (prefix, conditions) = part.split_at(part.chars().next().map_or(0, char::len_utf8));
+
+            let center = match prefix.to_ascii_uppercase().as_str() {
+                "B" => {
+                    has_birth = true;
+                    0
+                }
+                "S" => {
+                    has_survival = true;
+                    1
+                }
+                "C" | "G" => {
+                    states = conditions
+                        .parse()
+                        .map_err(|_| invalid("the number of states must be a number"))?;
+                    continue;
+                }
+                _ => return Err(invalid("every part must start with B, S or C")),
+            };
+
+            for (count, letter) in parse_conditions(conditions).map_err(|err| invalid(&err))? {
+                for neighbors in symmetries(arrangement(count, letter)) {
+                    table[index(center, neighbors)] = true;
+                }
+            }
+        }
+
+        if !has_birth || !has_survival {
+            return Err(invalid("missing birth or survival conditions"));
+        }
+        if !(2..=3).contains(&states) {
+            return Err(invalid("only rules with 2 or 3 states are supported"));
+        }
+
+        Ok(Self { table, states })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(Runner::new(self, rows, columns))
+    }
+}
+
+impl IsotropicRule {
+    pub fn next_cell_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        match state[row][column] {
+            CellState::Dying => CellState::Dead,
+            _ if self.table[neighborhood_index(state, row, column)] => CellState::Alive,
+            CellState::Alive if self.states == 3 => CellState::Dying,
+            _ => CellState::Dead,
+        }
+    }
+}
+
+impl Automaton for IsotropicRule {
+    type State = CellState;
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        self.to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        self.states as usize
+    }
+
+    fn next_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        self.next_cell_state(state, row, column)
+    }
+}
+
+impl Display for IsotropicRule {
+    /// Each neighbor count is written with the letters it has, or with `-` and the letters it doesn't have if that is
+    /// shorter, like Golly does
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let conditions = |center: usize| -> String {
+            let mut text = String::new();
+
+            for count in 0..=8 {
+                let letters = letters(count);
+                let present: String = (0..letters.len().max(1))
+                    .filter(|&letter| self.table[index(center, arrangement(count, letter))])
+                    .filter_map(|letter| letters.chars().nth(letter))
+                    .collect();
+                let all_present = (0..letters.len().max(1))
+                    .all(|letter| self.table[index(center, arrangement(count, letter))]);
+
+                if all_present {
+                    text.push_str(&count.to_string());
+                } else if !present.is_empty() {
+                    text.push_str(&count.to_string());
+                    if present.len() * 2 <= letters.len() {
+                        text.push_str(&present);
+                    } else {
+                        text.push('-');
+                        text.extend(letters.chars().filter(|letter| !present.contains(*letter)));
+                    }
+                }
+            }
+
+            text
+        };
+
+        write!(f, "B{}/S{}", conditions(0), conditions(1))?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Letters of the arrangements of the given number of live neighbors
+fn letters(count: usize) -> &'static str {
+    LETTERS[count.min(8 - count)]
+}
+
+/// The arrangement of live neighbors with the given count and letter, as bits like in [ARRANGEMENTS]
+fn arrangement(count: usize, letter: usize) -> u8 {
+    if count <= 4 {
+        ARRANGEMENTS[count][letter]
+    } else {
+        !ARRANGEMENTS[8 - count][letter]
+    }
+}
+
+/// All rotations and reflections of an arrangement of live neighbors
+fn symmetries(neighbors: u8) -> Vec<u8> {
+    let transform = |neighbors: u8, map: &dyn Fn(usize, usize) -> (usize, usize)| -> u8 {
+        NEIGHBOR_POSITIONS
+            .iter()
+            .enumerate()
+            .filter(|&(bit, _)| (neighbors >> bit) & 1 == 1)
+            .map(|(_, &(row, column))| {
+                let position = map(row, column);
+                1 << NEIGHBOR_POSITIONS
+                    .iter()
+                    .position(|&p| p == position)
+                    .unwrap()
+            })
+            .fold(0, |bits, bit| bits | bit)
+    };
+
+    let mut arrangements = Vec::new();
+    let mut rotated = neighbors;
+    for _ in 0..4 {
+        rotated = transform(rotated, &|row, column| (column, 2 - row));
+        arrangements.push(rotated);
+        arrangements.push(transform(rotated, &|row, column| (row, 2 - column)));
+    }
+
+    arrangements.sort_unstable();
+    arrangements.dedup();
+    arrangements
+}
+
+/// Index of the 3x3 block with the given center and neighbors in a rule's table, see [neighborhood_index]
+fn index(center: usize, neighbors: u8) -> usize {
+    NEIGHBOR_POSITIONS
+        .iter()
+        .enumerate()
+        .filter(|&(bit, _)| (neighbors >> bit) & 1 == 1)
+        .map(|(_, &(row, column))| 1 << (8 - (row * 3 + column)))
+        .fold(center << 4, |index, bit| index | bit)
+}
+
+/// Parse the conditions of a birth or survival part, such as `2-i34q`, into the neighbor counts and letters they
+/// apply to. A count without letters applies to all of its arrangements.
+fn parse_conditions(conditions: &str) -> Result<Vec<(usize, usize)>, String> {
+    let mut parsed = Vec::new();
+    let mut chars = conditions.chars().peekable();
+
+    while let Some(digit) = chars.next() {
+        let count = match digit.to_digit(10) {
+            Some(count) if count <= 8 => count as usize,
+            _ => {
+                return Err(format!(
+                    "`{}` is not a neighbor count between 0 and 8",
+                    digit
+                ))
+            }
+        };
+
+        let negated = chars.next_if_eq(&'-').is_some();
+        let mut chosen = String::new();
+        while let Some(letter) = chars.next_if(|c| c.is_ascii_lowercase()) {
+            if !letters(count).contains(letter) {
+                return Err(format!(
+                    "`{}` is not an arrangement of {} neighbors",
+                    letter, count
+                ));
+            }
+            chosen.push(letter);
+        }
+        if negated && chosen.is_empty() {
+            return Err(format!("`{}-` must be followed by letters", count));
+        }
+
+        let all_letters = letters(count);
+        if all_letters.is_empty() {
+            parsed.push((count, 0));
+            continue;
+        }
+
+        parsed.extend(
+            all_letters
+                .chars()
+                .enumerate()
+                .filter(|&(_, letter)| chosen.is_empty() || chosen.contains(letter) != negated)
+                .map(|(idx, _)| (count, idx)),
+        );
+    }
+
+    Ok(parsed)
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::simulations::conway::get_conway_next_cell_state;
+
+    #[test]
+    fn letters_split_every_neighbor_count() {
+        for count in 0..=8 {
+            let mut seen = Vec::new();
+
+            for letter in 0..letters(count).len().max(1) {
+                let arrangements = symmetries(arrangement(count, letter));
+                assert!(arrangements
+                    .iter()
+                    .all(|neighbors| neighbors.count_ones() as usize == count));
+                assert!(
+                    arrangements
+                        .iter()
+                        .all(|neighbors| !seen.contains(neighbors)),
+                    "{}{} overlaps another letter",
+                    count,
+                    letters(count).chars().nth(letter).unwrap_or(' ')
+                );
+                seen.extend(arrangements);
+            }
+
+            assert_eq!(
+                seen.len(),
+                (0..=255u8)
+                    .filter(|n| n.count_ones() as usize == count)
+                    .count()
+            );
+        }
+    }
+
+    #[test]
+    fn parses_rulestrings() {
+        for (rulestring, canonical) in [
+            ("B3/S2-i34q", "B3/S2-i34q"),
+            ("B2-a/S12", "B2-a/S12"),
+            ("B2cekin/S12", "B2-a/S12"),
+            ("B3ce/S23/C3", "B3ce/S23/C3"),
+            ("b3/s23", "B3/S23"),
+        ] {
+            assert_eq!(
+                IsotropicRule::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(canonical.to_string()),
+                "{}",
+                rulestring
+            );
+        }
+
+        assert!(IsotropicRule::matches("B2-a/S12"));
+        assert!(IsotropicRule::matches("B3/S23k"));
+        assert!(!IsotropicRule::matches("b2/s/c3"));
+        assert!(!IsotropicRule::matches("B2/S34H"));
+        assert!(IsotropicRule::parse("B3x/S23").is_err());
+        assert!(IsotropicRule::parse("B1k/S23").is_err());
+        assert!(IsotropicRule::parse("B3-/S23").is_err());
+    }
+
+    #[test]
+    fn totalistic_rules_match_life() {
+        let rule = IsotropicRule::parse("B3/S23").unwrap();
+        let mut state = Grid::new(16, 16, CellState::Dead);
+        for (idx, cell) in state.iter_rows_mut().flatten().enumerate() {
+            if idx * 7 % 5 < 2 {
+                *cell = CellState::Alive;
+            }
+        }
+
+        for row in 0..16 {
+            for column in 0..16 {
+                assert_eq!(
+                    rule.next_cell_state(&state, row, column),
+                    get_conway_next_cell_state(&state, row, column)
+                );
+            }
+        }
+    }
+}
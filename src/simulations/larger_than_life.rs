@@ -0,0 +1,360 @@
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+
+use crate::automaton::{Automaton, Runner, Simulation};
+use crate::grid::Grid;
+use crate::neighborhood::Neighborhood;
+use crate::registry::Notation;
+use crate::util::SummedAreaTable;
+
+/// Bosco's Rule, the best known Larger than Life rule, with its bugs (gliders) and oscillators
+pub const BOSCO: &str = "R5,C0,M1,S34..58,B34..45,NM";
+/// Majority voting: cells take the state of most of the cells around them, which smooths the soup into blobs
+pub const MAJORITY: &str = "R4,C0,M1,S41..81,B41..81,NM";
+
+/// Largest radius accepted in rulestrings, same as in Golly
+pub const MAX_RADIUS: usize = 500;
+
+/// A Larger than Life rule: an outer-totalistic rule over a neighborhood of any radius, with ranges of neighbor
+/// counts for birth and survival. Written in Golly's notation, e.g. `R5,C0,M1,S34..58,B34..45,NM`:
+/// - `R` is the radius
+/// - `C` the number of states, where 0 or 2 make a regular rule and more make cells decay like in Generations rules
+/// - `M1` counts the cell itself as one of its neighbors, `M0` doesn't
+/// - `S` and `B` are the neighbor counts at which live cells survive and dead cells come alive
+/// - `NM` is the Moore (square) neighborhood and `NN` the von Neumann (diamond) one
+#[derive(Clone, PartialEq, Debug)]
+pub struct LargerThanLife {
+    radius: usize,
+    states: usize,
+    middle: bool,
+    survival: RangeInclusive<u32>,
+    birth: RangeInclusive<u32>,
+    von_neumann: bool,
+}
+
+impl Notation for LargerThanLife {
+    const PRESETS: &'static [(&'static str, &'static str)] =
+        &[("Bosco's Rule", BOSCO), ("Majority", MAJORITY)];
+
+    /// Whether the rulestring is meant to be in this notation, so that other notations can have a go at it if not
+    fn matches(rulestring: &str) -> bool {
+        let mut chars = rulestring.trim().chars();
+        matches!(chars.next(), Some('R' | 'r')) && chars.next().is_some_and(|c| c.is_ascii_digit())
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+
+        let mut middle = false;
+        let mut survival = None;
+        let mut birth = None;
+        let parts = RangeParts::parse(rulestring, rulestring.trim().split(','), |key, value| {
+            match key {
+                'M' => {
+                    middle = match value {
+                        "0" => false,
+                        "1" => true,
+                        _ => return Err("M must be 0 or 1".to_string()),
+                    }
+                }
+                'S' => survival = Some(parse_range(value)?),
+                'B' => birth = Some(parse_range(value)?),
+                _ => return Ok(false),
+            }
+
+            Ok(true)
+        })?;
+
+        let radius = parts.radius.ok_or_else(|| invalid("missing radius"))?;
+        if !(1..=MAX_RADIUS).contains(&radius) {
+            return Err(invalid(&format!(
+                "the radius must be between 1 and {}",
+                MAX_RADIUS
+            )));
+        }
+        let states = parts.states.unwrap_or(2).max(2);
+        if states > 256 {
+            return Err(invalid("rules can have at most 256 states"));
+        }
+
+        Ok(Self {
+            radius,
+            states,
+            middle,
+            survival: survival.ok_or_else(|| invalid("missing survival counts"))?,
+            birth: birth.ok_or_else(|| invalid("missing birth counts"))?,
+            von_neumann: parts.von_neumann,
+        })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(Runner::new(self, rows, columns))
+    }
+}
+
+impl LargerThanLife {
+    /// Number of live cells in the neighborhood, read from a summed-area table of the live cells, see
+    /// [LargerThanLife::live_cell_sums]
+    fn count(&self, sums: &SummedAreaTable, grid: &Grid<u8>, row: usize, column: usize) -> u32 {
+        let (sum_row, sum_column) = if self.von_neumann {
+            turn(grid.columns(), row, column)
+        } else {
+            (row, column)
+        };
+        let mut count = sums.sum_around(sum_row, sum_column, self.radius, self.radius);
+
+        if !self.middle && grid[row][column] == 1 {
+            count -= 1;
+        }
+
+        count
+    }
+
+    /// A summed-area table of the live cells. For the von Neumann neighborhood, the grid is turned by 45 degrees
+    /// first, which makes the diamond around a cell a square that is summed up in one go.
+    fn live_cell_sums(&self, grid: &Grid<u8>) -> SummedAreaTable {
+        let (rows, columns) = (grid.rows(), grid.columns());
+        if !self.von_neumann {
+            return SummedAreaTable::new(rows, columns, |row, column| {
+                u32::from(grid[row][column] == 1)
+            });
+        }
+
+        // positions in between the cells of the turned grid are empty
+        let size = rows + columns - 1;
+        SummedAreaTable::new(size, size, |sum, difference| {
+            let (twice_row, twice_column) = (
+                (sum + difference).checked_sub(columns - 1),
+                (sum + columns - 1).checked_sub(difference),
+            );
+            match (twice_row, twice_column) {
+                (Some(twice_row), Some(twice_column))
+                    if twice_row % 2 == 0 && twice_row / 2 < rows && twice_column / 2 < columns =>
+                {
+                    u32::from(grid[twice_row / 2][twice_column / 2] == 1)
+                }
+                _ => 0,
+            }
+        })
+    }
+
+    fn transition(&self, state: u8, count: u32) -> u8 {
+        match state {
+            0 if self.birth.contains(&count) => 1,
+            0 => 0,
+            1 if self.survival.contains(&count) => 1,
+            // decay through the dying states, if there are any
+            state if (state as usize) < self.states - 1 => state + 1,
+            _ => 0,
+        }
+    }
+}
+
+impl Automaton for LargerThanLife {
+    type State = u8;
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        self.to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        self.states
+    }
+
+    fn next_state(&self, grid: &Grid<u8>, row: usize, column: usize) -> u8 {
+        self.transition(grid[row][column], self.count_neighbors(grid, row, column))
+    }
+
+    fn next_generation(&self, grid: &Grid<u8>, next: &mut Grid<u8>) {
+        let sums = self.live_cell_sums(grid);
+
+        for (r, row) in next.iter_rows_mut().enumerate() {
+            for (c, cell) in row.iter_mut().enumerate() {
+                *cell = self.transition(grid[r][c], self.count(&sums, grid, r, c));
+            }
+        }
+    }
+
+    fn neighborhood(&self) -> Neighborhood {
+        if self.von_neumann {
+            Neighborhood::VonNeumann(self.radius)
+        } else {
+            Neighborhood::Moore(self.radius)
+        }
+    }
+
+    fn count_neighbors(&self, grid: &Grid<u8>, row: usize, column: usize) -> u32 {
+        let neighbors = self
+            .neighborhood()
+            .cells(grid.rows(), grid.columns(), row, column)
+            .filter(|&(row, column)| grid[row][column] == 1)
+            .count() as u32;
+
+        neighbors + u32::from(self.middle && grid[row][column] == 1)
+    }
+}
+
+impl Display for LargerThanLife {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "R{},C{},M{},S{}..{},B{}..{},N{}",
+            self.radius,
+            if self.states == 2 { 0 } else { self.states },
+            u8::from(self.middle),
+            self.survival.start(),
+            self.survival.end(),
+            self.birth.start(),
+            self.birth.end(),
+            if self.von_neumann { 'N' } else { 'M' }
+        )
+    }
+}
+
+/// Position of a cell on the grid turned by 45 degrees, as (row + column, row - column) shifted to start from 0
+fn turn(columns: usize, row: usize, column: usize) -> (usize, usize) {
+    (row + column, row + columns - 1 - column)
+}
+
+/// The parts that Larger than Life rules share with MCell's notation for rules over a range, like
+/// [crate::simulations::cyclic::CyclicRule]: the radius `R`, the number of states `C`, and `NM` or `NN` for the
+/// Moore or von Neumann neighborhood. Every part is a letter followed by its value.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RangeParts {
+    pub radius: Option<usize>,
+    pub states: Option<usize>,
+    pub von_neumann: bool,
+}
+
+impl RangeParts {
+    /// Parse the parts of the rulestring, which is only used for error messages. Parts with other letters are
+    /// passed to `other` with the letter in upper case, which returns false for the ones it doesn't know either.
+    pub fn parse<'a>(
+        rulestring: &str,
+        parts: impl Iterator<Item = &'a str>,
+        mut other: impl FnMut(char, &'a str) -> Result<bool, String>,
+    ) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+
+        let mut range_parts = Self::default();
+        for part in parts {
+            let part = part.trim();
+            let Some(key) = part.chars().next() else {
+                return Err(invalid("empty part"));
+            };
+            let value = &part[key.len_utf8()..];
+
+            match key.to_ascii_uppercase() {
+                'R' => {
+                    range_parts.radius = Some(parse_count(value, 'R').map_err(|err| invalid(&err))?)
+                }
+                'C' => {
+                    range_parts.states = Some(parse_count(value, 'C').map_err(|err| invalid(&err))?)
+                }
+                'N' => {
+                    range_parts.von_neumann = match value.to_ascii_uppercase().as_str() {
+                        "M" => false,
+                        "N" => true,
+                        _ => return Err(invalid("the neighborhood must be NM or NN")),
+                    }
+                }
+                key => {
+                    if !other(key, value).map_err(|err| invalid(&err))? {
+                        return Err(invalid(&format!("unknown part `{}`", part)));
+                    }
+                }
+            }
+        }
+
+        Ok(range_parts)
+    }
+}
+
+/// Parse the value of a part that is a whole number
+pub fn parse_count(value: &str, key: char) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} must be a number", key))
+}
+
+/// Parse a range of neighbor counts such as `34..58`, or a single count
+fn parse_range(range: &str) -> Result<RangeInclusive<u32>, String> {
+    let invalid = || format!("`{}` is not a range of neighbor counts, e.g. 34..58", range);
+    let (start, end) = range.split_once("..").unwrap_or((range, range));
+
+    let start = start.parse().map_err(|_| invalid())?;
+    let end = end.parse().map_err(|_| invalid())?;
+    if start > end {
+        return Err(invalid());
+    }
+
+    Ok(start..=end)
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Rng;
+
+    #[test]
+    fn parses_rulestrings() {
+        for rulestring in [BOSCO, MAJORITY, "R2,C3,M0,S3..3,B3..4,NN"] {
+            assert_eq!(
+                LargerThanLife::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        assert_eq!(
+            LargerThanLife::parse("r10,c2,m0,s5,b4..6,nm").map(|rule| rule.to_string()),
+            Ok("R10,C0,M0,S5..5,B4..6,NM".to_string())
+        );
+        assert!(LargerThanLife::parse("R0,C0,M1,S1..2,B1..2,NM").is_err());
+        assert!(LargerThanLife::parse("R5,C0,M1,S58..34,B34..45,NM").is_err());
+        assert!(LargerThanLife::parse("R5,C0,M1,B34..45,NM").is_err());
+        assert!(LargerThanLife::parse("R5,C0,M1,é").is_err());
+        assert!(LargerThanLife::parse("R5,Cé,M1,S34..58,B34..45,NM").is_err());
+        assert!(LargerThanLife::matches(BOSCO));
+        assert!(!LargerThanLife::matches("B3/S23"));
+    }
+
+    #[test]
+    fn summed_counts_match_direct_counts() {
+        for rulestring in ["R3,C4,M1,S10..20,B8..12,NM", "R3,C0,M0,S6..10,B5..7,NN"] {
+            let rule = LargerThanLife::parse(rulestring).unwrap();
+            let mut simulation = Runner::new(rule.clone(), 20, 30);
+            simulation.randomize(&mut Rng::new(7), 0.4);
+
+            let grid = simulation.grid().clone();
+            let mut next = grid.clone();
+            rule.next_generation(&grid, &mut next);
+
+            for row in 0..grid.rows() {
+                for column in 0..grid.columns() {
+                    assert_eq!(
+                        next[row][column],
+                        rule.next_state(&grid, row, column),
+                        "{} at ({}, {})",
+                        rulestring,
+                        row,
+                        column
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generations_cells_decay() {
+        let rule = LargerThanLife::parse("R1,C4,M0,S9..9,B9..9,NM").unwrap();
+
+        assert_eq!(rule.transition(1, 2), 2);
+        assert_eq!(rule.transition(2, 0), 3);
+        assert_eq!(rule.transition(3, 8), 0);
+    }
+}
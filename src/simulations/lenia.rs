@@ -0,0 +1,269 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Parameter, Simulation};
+use crate::grid::Grid;
+use crate::registry::Notation;
+use crate::simulations::continuous::{self, ContinuousRule, ContinuousRunner, Kernel};
+use crate::util::{has_prefix, parse_parts, Rng};
+
+/// The parameters of Orbium, the first glider found in Lenia
+pub const ORBIUM: &str = "Lenia/R13/T10/M0.15/S0.015/B1";
+
+const PREFIX: &str = "Lenia";
+const MAX_RINGS: usize = 4;
+const MAX_RADIUS: usize = 50;
+const MAX_TIME_STEPS: f64 = 100.;
+/// Index of the weight of the first ring among the parameters, after R, T, mu and sigma
+const FIRST_RING_PARAMETER: usize = 4;
+const RING_NAMES: [&str; MAX_RINGS] = ["ring 1", "ring 2", "ring 3", "ring 4"];
+
+/// Bert Chan's Lenia, written as `Lenia/R<radius>/T<time steps>/M<mu>/S<sigma>/B<ring weights>`, e.g.
+/// `Lenia/R13/T10/M0.15/S0.015/B1`. Cells hold a value between 0 and 1, and the kernel is a set of smooth, concentric
+/// rings reaching out to `R` cells, with one weight for each ring, comma separated, from the inside out. Every
+/// generation, cells grow by `1 / T` of the growth function of the weighted average `u` around them, a bump centered
+/// on `mu` with width `sigma` that goes from -1 far from `mu` to 1 at `mu`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Lenia {
+    radius: usize,
+    time_steps: f64,
+    mu: f64,
+    sigma: f64,
+    rings: Vec<f64>,
+}
+
+impl Notation for Lenia {
+    const PRESETS: &'static [(&'static str, &'static str)] = &[("Lenia (Orbium)", ORBIUM)];
+
+    fn matches(rulestring: &str) -> bool {
+        has_prefix(rulestring, PREFIX)
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+        let [radius, time_steps, mu, sigma, rings] =
+            parse_parts(rulestring, PREFIX, ['R', 'T', 'M', 'S', 'B'])?;
+
+        let number = |value: &str, letter: char, range: std::ops::RangeInclusive<f64>| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| range.contains(value))
+                .ok_or_else(|| {
+                    invalid(&format!(
+                        "{} must be between {} and {}",
+                        letter,
+                        range.start(),
+                        range.end()
+                    ))
+                })
+        };
+
+        let rings = rings
+            .split(',')
+            .map(|weight| number(weight.trim(), 'B', 0. ..=1.))
+            .collect::<Result<Vec<_>, _>>()?;
+        if rings.len() > MAX_RINGS || rings.iter().all(|&weight| weight == 0.) {
+            return Err(invalid(&format!(
+                "B must have 1 to {} ring weights, not all 0",
+                MAX_RINGS
+            )));
+        }
+
+        Ok(Self {
+            radius: radius
+                .parse()
+                .ok()
+                .filter(|radius| (1..=MAX_RADIUS).contains(radius))
+                .ok_or_else(|| invalid(&format!("R must be between 1 and {}", MAX_RADIUS)))?,
+            time_steps: number(time_steps, 'T', 1. ..=MAX_TIME_STEPS)?,
+            mu: number(mu, 'M', 0. ..=1.)?,
+            sigma: number(sigma, 'S', 0.001..=1.)?,
+            rings,
+        })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(ContinuousRunner::new(self, rows, columns))
+    }
+}
+
+impl Lenia {
+    /// Weight of the kernel at a distance from the center, as a fraction of the radius
+    fn kernel_weight(&self, distance: f32) -> f32 {
+        if distance >= 1. {
+            return 0.;
+        }
+
+        // every ring is a bump that is 0 at its edges and 1 in its middle
+        let position = distance * self.rings.len() as f32;
+        let (ring, x) = (position as usize, position.fract());
+        if x <= 0. {
+            return 0.;
+        }
+
+        self.rings[ring] as f32 * (4. - 1. / (x * (1. - x))).exp()
+    }
+
+    /// How much a cell grows with the given weighted average around it, between -1 and 1
+    pub fn growth(&self, potential: f32) -> f32 {
+        let (mu, sigma) = (self.mu as f32, self.sigma as f32);
+        2. * (-(potential - mu).powi(2) / (2. * sigma * sigma)).exp() - 1.
+    }
+}
+
+impl ContinuousRule for Lenia {
+    fn name(&self) -> String {
+        "Lenia".to_string()
+    }
+
+    fn kernels(&self) -> Vec<Kernel> {
+        vec![Kernel::new(self.radius, |distance| {
+            self.kernel_weight(distance / self.radius as f32)
+        })]
+    }
+
+    fn next_value(&self, value: f32, potentials: &[f32]) -> f32 {
+        value + self.growth(potentials[0]) / self.time_steps as f32
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        let mut parameters = vec![
+            Parameter::integer("R", self.radius),
+            Parameter::real("T", self.time_steps),
+            Parameter::real("mu", self.mu),
+            Parameter::real("sigma", self.sigma),
+        ];
+        parameters.extend(
+            RING_NAMES
+                .into_iter()
+                .zip(self.rings.iter())
+                .map(|(name, &weight)| Parameter::real(name, weight)),
+        );
+
+        parameters
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        match idx {
+            0 => self.radius = (value.round() as usize).clamp(1, MAX_RADIUS),
+            1 => self.time_steps = value.clamp(1., MAX_TIME_STEPS),
+            2 => self.mu = value.clamp(0., 1.),
+            3 => self.sigma = value.clamp(0.001, 1.),
+            idx if idx - FIRST_RING_PARAMETER < self.rings.len() => {
+                let ring = idx - FIRST_RING_PARAMETER;
+                let previous = self.rings[ring];
+                self.rings[ring] = value.clamp(0., 1.);
+
+                // a kernel with no weight anywhere has no average to take
+                if self.rings.iter().all(|&weight| weight == 0.) {
+                    self.rings[ring] = previous;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Soups are square patches of random values, as wide as the kernel, that cover `density` of the grid
+    fn randomize(&self, grid: &mut Grid<f32>, rng: &mut Rng, density: f64) {
+        continuous::scatter_patches(grid, rng, density, 2 * self.radius, |rng| {
+            rng.next_f64() as f32
+        });
+    }
+}
+
+impl Display for Lenia {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rings: Vec<String> = self.rings.iter().map(f64::to_string).collect();
+
+        write!(
+            f,
+            "{}/R{}/T{}/M{}/S{}/B{}",
+            PREFIX,
+            self.radius,
+            self.time_steps,
+            self.mu,
+            self.sigma,
+            rings.join(",")
+        )
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rulestrings() {
+        for rulestring in [ORBIUM, "Lenia/R20/T5/M0.26/S0.036/B0.5,1,0.667"] {
+            assert_eq!(
+                Lenia::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        assert!(Lenia::parse("Lenia/R0/T10/M0.15/S0.015/B1").is_err());
+        assert!(Lenia::parse("Lenia/R13/T10/M0.15/S0.015/B0").is_err());
+        assert!(Lenia::parse("Lenia/R13/T10/M0.15/S0.015/B1,1,1,1,1").is_err());
+        assert!(Lenia::parse("Lenia/R13/T10/M1.5/S0.015/B1").is_err());
+        assert!(Lenia::matches("lenia/R13"));
+        assert!(!Lenia::matches("SmoothLife/R13"));
+    }
+
+    #[test]
+    fn grows_near_mu_and_shrinks_elsewhere() {
+        let rule = Lenia::parse(ORBIUM).unwrap();
+
+        assert_eq!(rule.growth(0.15), 1.);
+        assert!(rule.growth(0.15 + 0.015) > 0.);
+        assert!((rule.growth(0.) + 1.).abs() < 1e-6);
+        assert!((rule.growth(1.) + 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn kernels_are_rings() {
+        let rule = Lenia::parse("Lenia/R10/T10/M0.15/S0.015/B1,0").unwrap();
+        let kernel = &rule.kernels()[0];
+
+        // only the inner ring has weight, and its peak is halfway through it
+        let weight = |row, column| {
+            kernel
+                .weights()
+                .iter()
+                .find(|&&(r, c, _)| (r, c) == (row, column))
+                .map_or(0., |&(.., weight)| weight)
+        };
+        assert_eq!(weight(0, 0), 0.);
+        assert!(weight(0, 2) > weight(0, 1) && weight(0, 2) > weight(0, 4));
+        assert_eq!(weight(0, 5), 0.);
+        assert_eq!(weight(0, 7), 0.);
+    }
+
+    #[test]
+    fn edits_the_kernel_while_running() {
+        let mut simulation = ContinuousRunner::new(Lenia::parse(ORBIUM).unwrap(), 32, 32);
+
+        simulation.set_parameter(0, 8.4);
+        simulation.set_parameter(4, 0.);
+        simulation.set_parameter(5, 0.5);
+        assert_eq!(simulation.rulestring(), "Lenia/R8/T10/M0.15/S0.015/B1");
+        assert_eq!(simulation.parameters()[0], Parameter::integer("R", 8));
+    }
+
+    #[test]
+    fn soups_come_alive() {
+        let mut simulation = ContinuousRunner::new(Lenia::parse(ORBIUM).unwrap(), 128, 128);
+        let mut rng = Rng::new(42);
+        simulation.randomize(&mut rng, 0.2);
+
+        let mut counts = Default::default();
+        for _ in 0..100 {
+            counts = simulation.step(&mut rng);
+        }
+
+        // the noise settles into smooth creatures, which don't fill the grid
+        let mass: f32 = simulation.grid().iter_rows().flatten().sum();
+        assert!(mass > 0. && mass < 128. * 128. / 4., "{}", mass);
+        assert!(counts.alive > 0);
+    }
+}
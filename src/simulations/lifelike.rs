@@ -0,0 +1,250 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Automaton, Runner, Simulation};
+use crate::neighborhood::Neighborhood;
+use crate::registry::Notation;
+use crate::util::count_cells;
+use crate::{CellState, SimulationState};
+
+/// Any outer-totalistic rule, written in B/S notation such as `B36/S23`. Rules with a third `C` part (e.g. `B2/S/C3`)
+/// are "Generations" rules, where cells that fail to survive decay for a generation first. A suffix selects a
+/// neighborhood other than the Moore one, e.g. `B2/S34H` for the hexagonal neighborhood, see
+/// [Neighborhood::parse_suffix].
+#[derive(Clone, PartialEq, Debug)]
+pub struct LifeLikeRule {
+    /// Neighbor counts at which dead cells come alive, indexed by the count
+    birth: Vec<bool>,
+    /// Neighbor counts at which live cells stay alive, indexed by the count
+    survival: Vec<bool>,
+    /// 2 for regular rules, 3 if cells decay before dying
+    states: u8,
+    neighborhood: Neighborhood,
+    /// Offsets of the neighborhood, kept around so that they aren't computed for every cell
+    offsets: Vec<(isize, isize)>,
+}
+
+impl Notation for LifeLikeRule {
+    /// Every rulestring, so that rules in no other notation are reported with what is wrong in B/S notation
+    fn matches(_rulestring: &str) -> bool {
+        true
+    }
+
+    /// Parse a rule in B/S notation (`B3/S23`), the older S/B notation (`23/3`), or either one followed by the number
+    /// of states (`B2/S/C3` or `/2/3`), and optionally by a neighborhood suffix (`B3/S23V`)
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+
+        let (rule, suffix) = split_neighborhood(rulestring.trim());
+        let neighborhood = Neighborhood::parse_suffix(suffix).map_err(|err| invalid(&err))?;
+        let offsets = neighborhood.offsets();
+        let max_count = offsets.len();
+
+        let parts: Vec<&str> = rule.split('/').collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(invalid("expected B/S notation, e.g. B3/S23"));
+        }
+
+        let has_prefix = |part: &str, prefix: char| part.to_ascii_uppercase().starts_with(prefix);
+        let mut birth = None;
+        let mut survival = None;
+        let mut states = 2;
+
+        for (idx, part) in parts.iter().enumerate() {
+            let part = part.trim();
+
+            if has_prefix(part, 'B') {
+                birth = Some(parse_counts(&part[1..], max_count).map_err(|err| invalid(&err))?);
+            } else if has_prefix(part, 'S') {
+                survival = Some(parse_counts(&part[1..], max_count).map_err(|err| invalid(&err))?);
+            } else if has_prefix(part, 'C') || has_prefix(part, 'G') || idx == 2 {
+                let digits = part.trim_start_matches(['C', 'c', 'G', 'g']);
+                states = digits
+                    .parse()
+                    .map_err(|_| invalid("the number of states must be a number"))?;
+            } else if idx == 0 {
+                // S/B notation
+                survival = Some(parse_counts(part, max_count).map_err(|err| invalid(&err))?);
+            } else {
+                birth = Some(parse_counts(part, max_count).map_err(|err| invalid(&err))?);
+            }
+        }
+
+        if !(2..=3).contains(&states) {
+            return Err(invalid("only rules with 2 or 3 states are supported"));
+        }
+
+        Ok(Self {
+            birth: birth.ok_or_else(|| invalid("missing birth conditions"))?,
+            survival: survival.ok_or_else(|| invalid("missing survival conditions"))?,
+            states,
+            neighborhood,
+            offsets,
+        })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(Runner::new(self, rows, columns))
+    }
+}
+
+impl LifeLikeRule {
+    pub fn next_cell_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        let live_cell_count = count_cells(state, &self.offsets, row, column, |cell| {
+            cell == CellState::Alive
+        });
+
+        match state[row][column] {
+            CellState::Alive if self.survival[live_cell_count as usize] => CellState::Alive,
+            CellState::Alive if self.states == 3 => CellState::Dying,
+            CellState::Dead if self.birth[live_cell_count as usize] => CellState::Alive,
+            _ => CellState::Dead,
+        }
+    }
+}
+
+impl Automaton for LifeLikeRule {
+    type State = CellState;
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        self.to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        self.states as usize
+    }
+
+    fn next_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        self.next_cell_state(state, row, column)
+    }
+
+    fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood.clone()
+    }
+}
+
+impl Display for LifeLikeRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let counts = |conditions: &[bool]| -> String {
+            (0..conditions.len())
+                .filter(|&count| conditions[count])
+                .map(|count| count.to_string())
+                .collect()
+        };
+
+        write!(f, "B{}/S{}", counts(&self.birth), counts(&self.survival))?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+
+        write!(f, "{}", self.neighborhood)
+    }
+}
+
+/// Split a rulestring into the rule and the neighborhood suffix, which starts at the first character in the last
+/// part that is neither the part's prefix nor a digit
+fn split_neighborhood(rulestring: &str) -> (&str, &str) {
+    let last_part = rulestring.rfind('/').map_or(0, |idx| idx + 1);
+    let prefix_length = rulestring[last_part..]
+        .chars()
+        .next()
+        .filter(|first| ['B', 'S', 'C', 'G'].contains(&first.to_ascii_uppercase()))
+        .map_or(0, char::len_utf8);
+    let digits_start = last_part + prefix_length;
+
+    let suffix_start = rulestring[digits_start..]
+        .find(|character: char| !character.is_ascii_digit())
+        .map_or(rulestring.len(), |idx| digits_start + idx);
+
+    rulestring.split_at(suffix_start)
+}
+
+/// Parse a list of neighbor counts such as `236`, up to the size of the neighborhood
+fn parse_counts(digits: &str, max_count: usize) -> Result<Vec<bool>, String> {
+    let mut counts = vec![false; max_count + 1];
+
+    for digit in digits.chars() {
+        match digit.to_digit(10) {
+            Some(count) if count as usize <= max_count => counts[count as usize] = true,
+            _ => {
+                return Err(format!(
+                    "`{}` is not a neighbor count between 0 and {}",
+                    digit, max_count
+                ))
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Rng;
+
+    #[test]
+    fn parses_rulestrings() {
+        for (rulestring, canonical) in [
+            ("B3/S23", "B3/S23"),
+            ("b36/s23", "B36/S23"),
+            ("23/3", "B3/S23"),
+            ("B2/S", "B2/S"),
+            ("B2/S/C3", "B2/S/C3"),
+            ("/2/3", "B2/S/C3"),
+            ("S23/B3", "B3/S23"),
+            ("B2/S34H", "B2/S34H"),
+            ("B3/S23v", "B3/S23V"),
+            ("B2/S/C3V", "B2/S/C3V"),
+            ("B3/S2M2", "B3/S2M2"),
+            ("B1/S@550", "B1/S@550"),
+        ] {
+            assert_eq!(
+                LifeLikeRule::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(canonical.to_string()),
+                "{}",
+                rulestring
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_rulestrings() {
+        assert_eq!(
+            LifeLikeRule::parse("B9/S23"),
+            Err("Invalid rule `B9/S23`: `9` is not a neighbor count between 0 and 8".to_string())
+        );
+        assert_eq!(
+            LifeLikeRule::parse("B3/S23/C5"),
+            Err(
+                "Invalid rule `B3/S23/C5`: only rules with 2 or 3 states are supported".to_string()
+            )
+        );
+        assert_eq!(
+            LifeLikeRule::parse("B3/S5V"),
+            Err("Invalid rule `B3/S5V`: `5` is not a neighbor count between 0 and 4".to_string())
+        );
+        assert!(LifeLikeRule::parse("B3/S23X").is_err());
+        assert!(LifeLikeRule::parse("life").is_err());
+        assert!(LifeLikeRule::parse("B3/B3").is_err());
+    }
+
+    #[test]
+    fn counts_neighbors_in_the_rule_neighborhood() {
+        let mut simulation = Runner::new(LifeLikeRule::parse("B1/SV").unwrap(), 5, 5);
+        simulation.set_state(2, 2, 1);
+
+        simulation.step(&mut Rng::new(0));
+
+        let alive: Vec<_> = (0..5)
+            .flat_map(|row| (0..5).map(move |column| (row, column)))
+            .filter(|&(row, column)| simulation.is_alive(row, column))
+            .collect();
+        assert_eq!(alive, [(1, 2), (2, 1), (2, 3), (3, 2)]);
+        assert_eq!(simulation.count_neighbors(1, 1), 2);
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Automaton, Runner, Simulation};
+use crate::registry::Notation;
+use crate::util::neighborhood_index;
+use crate::{CellState, SimulationState};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// Bytes in the table of a MAP rule, one bit for each of the 512 possible 3x3 blocks
+const TABLE_BYTES: usize = 64;
+
+/// Any 2-state rule on the Moore neighborhood, written as `MAP` followed by its whole lookup table in base64, like
+/// in Golly and LifeViewer. Bit `i` of the table, counting from the most significant bit of the first byte, is the
+/// next state of a cell whose 3x3 block reads `i` in binary, see [neighborhood_index]. Unlike the other notations,
+/// MAP rules don't have to be isotropic, so they can also describe rules that treat directions differently.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MapRule {
+    /// Whether the cell is alive in the next generation, indexed by the 3x3 block around it
+    table: Vec<bool>,
+}
+
+impl Notation for MapRule {
+    /// Whether the rulestring is meant to be a MAP rule
+    fn matches(rulestring: &str) -> bool {
+        rulestring.trim().starts_with("MAP")
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+
+        let encoded = rulestring
+            .trim()
+            .strip_prefix("MAP")
+            .ok_or_else(|| invalid("expected MAP followed by base64"))?;
+        let bytes = decode_base64(encoded.trim_end_matches('=')).map_err(|err| invalid(&err))?;
+        if bytes.len() != TABLE_BYTES {
+            return Err(invalid(&format!(
+                "expected {} bytes for the Moore neighborhood, found {}",
+                TABLE_BYTES,
+                bytes.len()
+            )));
+        }
+
+        let table = bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |bit| (byte >> bit) & 1 == 1))
+            .collect();
+
+        Ok(Self { table })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(Runner::new(self, rows, columns))
+    }
+}
+
+impl MapRule {
+    /// The MAP rule that behaves like the given transition function, which is called once for every 3x3 block
+    pub fn from_fn<F>(next_cell_state: F) -> Self
+    where
+        F: Fn(&SimulationState, usize, usize) -> CellState,
+    {
+        let table = (0..512)
+            .map(|index| {
+                let mut block = SimulationState::new(3, 3, CellState::Dead);
+                for (bit, cell) in block.iter_rows_mut().flatten().enumerate() {
+                    if (index >> (8 - bit)) & 1 == 1 {
+                        *cell = CellState::Alive;
+                    }
+                }
+
+                next_cell_state(&block, 1, 1) == CellState::Alive
+            })
+            .collect();
+
+        Self { table }
+    }
+
+    pub fn next_cell_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        if self.table[neighborhood_index(state, row, column)] {
+            CellState::Alive
+        } else {
+            CellState::Dead
+        }
+    }
+}
+
+impl Automaton for MapRule {
+    type State = CellState;
+
+    fn name(&self) -> String {
+        // the whole rulestring is too long to show
+        format!("MAP rule {}..", &self.to_string()[3..11])
+    }
+
+    fn rulestring(&self) -> String {
+        self.to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        2
+    }
+
+    fn next_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        self.next_cell_state(state, row, column)
+    }
+}
+
+impl Display for MapRule {
+    /// Golly leaves out the `==` padding at the end, and so does this
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let bytes: Vec<u8> = self
+            .table
+            .chunks(8)
+            .map(|bits| {
+                bits.iter()
+                    .fold(0, |byte, &bit| (byte << 1) | u8::from(bit))
+            })
+            .collect();
+
+        write!(f, "MAP{}", encode_base64(&bytes))
+    }
+}
+
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let (mut buffer, mut buffered_bits) = (0u32, 0);
+
+    for character in encoded.chars() {
+        let value = BASE64_ALPHABET
+            .iter()
+            .position(|&c| c as char == character)
+            .ok_or_else(|| format!("`{}` is not a base64 character", character))?;
+
+        buffer = (buffer << 6) | value as u32;
+        buffered_bits += 6;
+        if buffered_bits >= 8 {
+            buffered_bits -= 8;
+            bytes.push((buffer >> buffered_bits) as u8);
+            buffer &= (1 << buffered_bits) - 1;
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Encode without padding
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    let (mut buffer, mut buffered_bits) = (0u32, 0);
+
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        buffered_bits += 8;
+        while buffered_bits >= 6 {
+            buffered_bits -= 6;
+            encoded.push(BASE64_ALPHABET[(buffer >> buffered_bits) as usize & 63] as char);
+        }
+        buffer &= (1 << buffered_bits) - 1;
+    }
+    if buffered_bits > 0 {
+        encoded.push(BASE64_ALPHABET[(buffer << (6 - buffered_bits)) as usize & 63] as char);
+    }
+
+    encoded
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::simulations::conway::get_conway_next_cell_state;
+
+    /// Conway's Game of Life, as written by Golly
+    const LIFE: &str =
+        "MAPARYXfhZofugWaH7oaIDogBZofuhogOiAaIDogIAAgAAWaH7oaIDogGiA6ICAAIAAaIDogIAAgACAAIAAAAAAAA";
+
+    #[test]
+    fn parses_life() {
+        let rule = MapRule::parse(LIFE).unwrap();
+
+        assert_eq!(rule, MapRule::from_fn(get_conway_next_cell_state));
+        assert_eq!(rule.to_string(), LIFE);
+        assert_eq!(MapRule::parse(&format!("{}==", LIFE)), Ok(rule));
+    }
+
+    #[test]
+    fn runs_anisotropic_rules() {
+        // every cell moves one column to the right
+        let rule = MapRule::from_fn(|state, row, column| state[row][column - 1]);
+        let rule = MapRule::parse(&rule.to_string()).unwrap();
+
+        let mut state = Grid::new(3, 3, CellState::Dead);
+        state[1][0] = CellState::Alive;
+
+        assert_eq!(rule.next_cell_state(&state, 1, 1), CellState::Alive);
+        assert_eq!(rule.next_cell_state(&state, 1, 0), CellState::Dead);
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(MapRule::parse("MAPABC").is_err());
+        assert!(MapRule::parse(&LIFE.replace('A', "!")).is_err());
+        assert!(!MapRule::matches("B3/S23"));
+    }
+}
@@ -1,4 +1,21 @@
 pub mod brain;
+pub mod continuous;
 pub mod conway;
+pub mod cyclic;
+pub mod elementary;
+pub mod epidemic;
+pub mod forest_fire;
+pub mod golly;
+pub mod gray_scott;
+pub mod greenberg_hastings;
 pub mod highlife;
+pub mod isotropic;
+pub mod larger_than_life;
+pub mod lenia;
+pub mod lifelike;
+pub mod map;
 pub mod seeds;
+pub mod smoothlife;
+pub mod stochastic;
+pub mod turmite;
+pub mod wireworld;
@@ -1,9 +1,11 @@
+use crate::automaton::Automaton;
+use crate::neighborhood::MOORE;
 use crate::util::count_cells;
 use crate::{CellState, SimulationState};
 
 // Seeds variation of the Game of Life
 pub fn get_seeds_next_cell_state(state: &SimulationState, row: usize, column: usize) -> CellState {
-    let live_cell_count = count_cells(state, row, column, |cell| cell == CellState::Alive);
+    let live_cell_count = count_cells(state, &MOORE, row, column, |cell| cell == CellState::Alive);
 
     match state[row][column] {
         CellState::Alive => CellState::Dead,
@@ -17,3 +19,26 @@ pub fn get_seeds_next_cell_state(state: &SimulationState, row: usize, column: us
         _ => CellState::Dead,
     }
 }
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Seeds;
+
+impl Automaton for Seeds {
+    type State = CellState;
+
+    fn name(&self) -> String {
+        "Seeds".to_string()
+    }
+
+    fn rulestring(&self) -> String {
+        "B2/S".to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        2
+    }
+
+    fn next_state(&self, state: &SimulationState, row: usize, column: usize) -> CellState {
+        get_seeds_next_cell_state(state, row, column)
+    }
+}
@@ -0,0 +1,231 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Parameter, Simulation};
+use crate::grid::Grid;
+use crate::registry::Notation;
+use crate::simulations::continuous::{self, ContinuousRule, ContinuousRunner, Kernel};
+use crate::util::{has_prefix, parse_parts, Rng};
+
+/// Stephan Rafler's parameters from the SmoothLife paper, which give gliders among blobs that split and merge
+pub const DEFAULT: &str = "SmoothLife/R21/B0.278..0.365/D0.267..0.445";
+
+const PREFIX: &str = "SmoothLife";
+/// How the inner disc compares to the whole neighborhood, the ratio of a cell to the 3x3 square in Life
+const INNER_RADIUS_RATIO: f32 = 1. / 3.;
+/// Width of the soft edges of the disc and the ring, in cells, so that they don't look like polygons
+const EDGE_WIDTH: f32 = 1.;
+/// Steepness of the step from dead to alive in the neighbor filling, and in the cell's own filling
+const NEIGHBOR_STEEPNESS: f32 = 0.028;
+const CELL_STEEPNESS: f32 = 0.147;
+const MAX_RADIUS: usize = 50;
+/// Sparser soups die out within a few generations, because too few squares overlap
+const MIN_SOUP_DENSITY: f64 = 0.2;
+
+/// Rafler's SmoothLife, Conway's Life on a continuous grid, written as
+/// `SmoothLife/R<radius>/B<low>..<high>/D<low>..<high>`, e.g. `SmoothLife/R21/B0.278..0.365/D0.267..0.445`. A cell
+/// is a disc a third of the radius `R` wide, and its neighbors are the ring around it out to `R`. Like in Life, a cell
+/// comes alive when the filling of the ring is in the birth interval `B`, and stays alive when it is in the death
+/// interval `D`, except that the cell's own filling decides smoothly which interval applies, and the edges of the
+/// intervals are smooth too.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SmoothLife {
+    radius: usize,
+    birth: (f64, f64),
+    survival: (f64, f64),
+}
+
+impl Notation for SmoothLife {
+    const PRESETS: &'static [(&'static str, &'static str)] = &[("SmoothLife", DEFAULT)];
+
+    fn matches(rulestring: &str) -> bool {
+        has_prefix(rulestring, PREFIX)
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+        let [radius, birth, survival] = parse_parts(rulestring, PREFIX, ['R', 'B', 'D'])?;
+
+        let interval = |value: &str, letter: char| {
+            value
+                .split_once("..")
+                .and_then(|(low, high)| Some((low.parse().ok()?, high.parse().ok()?)))
+                .filter(|&(low, high): &(f64, f64)| 0. <= low && low <= high && high <= 1.)
+                .ok_or_else(|| {
+                    invalid(&format!(
+                        "expected {} followed by an interval like 0.25..0.5 between 0 and 1",
+                        letter
+                    ))
+                })
+        };
+
+        Ok(Self {
+            radius: radius
+                .parse()
+                .ok()
+                .filter(|radius| (3..=MAX_RADIUS).contains(radius))
+                .ok_or_else(|| invalid(&format!("R must be between 3 and {}", MAX_RADIUS)))?,
+            birth: interval(birth, 'B')?,
+            survival: interval(survival, 'D')?,
+        })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(ContinuousRunner::new(self, rows, columns))
+    }
+}
+
+impl SmoothLife {
+    /// The next value of a cell with the given filling of its inner disc and of the ring around it
+    pub fn transition(&self, inner: f32, outer: f32) -> f32 {
+        let alive = sigmoid(inner, 0.5, CELL_STEEPNESS);
+        let threshold =
+            |birth: f64, survival: f64| birth as f32 * (1. - alive) + survival as f32 * alive;
+
+        let (low, high) = (
+            threshold(self.birth.0, self.survival.0),
+            threshold(self.birth.1, self.survival.1),
+        );
+        sigmoid(outer, low, NEIGHBOR_STEEPNESS) * (1. - sigmoid(outer, high, NEIGHBOR_STEEPNESS))
+    }
+}
+
+/// A smooth step from 0 to 1 around the threshold, which is steeper the smaller the steepness is
+fn sigmoid(x: f32, threshold: f32, steepness: f32) -> f32 {
+    1. / (1. + (-(x - threshold) * 4. / steepness).exp())
+}
+
+/// How much of a cell at the given distance from the center is inside a disc of the given radius
+fn disc(distance: f32, radius: f32) -> f32 {
+    ((radius + EDGE_WIDTH / 2. - distance) / EDGE_WIDTH).clamp(0., 1.)
+}
+
+impl ContinuousRule for SmoothLife {
+    fn name(&self) -> String {
+        "SmoothLife".to_string()
+    }
+
+    /// The inner disc, then the ring around it
+    fn kernels(&self) -> Vec<Kernel> {
+        let outer_radius = self.radius as f32;
+        let inner_radius = outer_radius * INNER_RADIUS_RATIO;
+        let reach = (outer_radius + EDGE_WIDTH / 2.).ceil() as usize;
+
+        vec![
+            Kernel::new(reach, |distance| disc(distance, inner_radius)),
+            Kernel::new(reach, |distance| {
+                disc(distance, outer_radius) - disc(distance, inner_radius)
+            }),
+        ]
+    }
+
+    fn next_value(&self, _value: f32, potentials: &[f32]) -> f32 {
+        self.transition(potentials[0], potentials[1])
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::integer("R", self.radius),
+            Parameter::real("birth low", self.birth.0),
+            Parameter::real("birth high", self.birth.1),
+            Parameter::real("death low", self.survival.0),
+            Parameter::real("death high", self.survival.1),
+        ]
+    }
+
+    /// Intervals stay in order, by moving the other end along if needed
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        if idx == 0 {
+            self.radius = (value.round() as usize).clamp(3, MAX_RADIUS);
+            return;
+        }
+
+        let value = value.clamp(0., 1.);
+        match idx {
+            1 => self.birth = (value, self.birth.1.max(value)),
+            2 => self.birth = (self.birth.0.min(value), value),
+            3 => self.survival = (value, self.survival.1.max(value)),
+            4 => self.survival = (self.survival.0.min(value), value),
+            _ => {}
+        }
+    }
+
+    /// Soups are live squares as wide as the neighborhood, that cover `density` of the grid, or a fifth of it if
+    /// that is more
+    fn randomize(&self, grid: &mut Grid<f32>, rng: &mut Rng, density: f64) {
+        let density = density.max(MIN_SOUP_DENSITY);
+        continuous::scatter_patches(grid, rng, density, self.radius, |_| 1.);
+    }
+}
+
+impl Display for SmoothLife {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/R{}/B{}..{}/D{}..{}",
+            PREFIX, self.radius, self.birth.0, self.birth.1, self.survival.0, self.survival.1
+        )
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rulestrings() {
+        for rulestring in [DEFAULT, "SmoothLife/R10/B0.25..0.3/D0.2..0.5"] {
+            assert_eq!(
+                SmoothLife::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        assert!(SmoothLife::parse("SmoothLife/R21/B0.365..0.278/D0.267..0.445").is_err());
+        assert!(SmoothLife::parse("SmoothLife/R21/B0.278/D0.267..0.445").is_err());
+        assert!(SmoothLife::parse("SmoothLife/R2/B0.278..0.365/D0.267..0.445").is_err());
+        assert!(SmoothLife::matches("smoothlife/R21"));
+    }
+
+    #[test]
+    fn follows_the_rules_of_life() {
+        let rule = SmoothLife::parse(DEFAULT).unwrap();
+
+        // dead cells are born in the birth interval, live cells survive in the wider death interval
+        assert!(rule.transition(0., 0.32) > 0.9);
+        assert!(rule.transition(0., 0.42) < 0.1);
+        assert!(rule.transition(1., 0.42) > 0.9);
+        // and everything dies of loneliness or overcrowding
+        assert!(rule.transition(1., 0.1) < 0.1);
+        assert!(rule.transition(1., 0.6) < 0.1);
+    }
+
+    #[test]
+    fn keeps_intervals_in_order() {
+        let mut simulation = ContinuousRunner::new(SmoothLife::parse(DEFAULT).unwrap(), 16, 16);
+
+        simulation.set_parameter(1, 0.4);
+        simulation.set_parameter(4, 0.2);
+        assert_eq!(
+            simulation.rulestring(),
+            "SmoothLife/R21/B0.4..0.4/D0.2..0.2"
+        );
+    }
+
+    #[test]
+    fn soups_keep_going() {
+        // the default rule scaled down with the grid, so that the test runs quickly
+        let rule = SmoothLife::parse("SmoothLife/R10/B0.278..0.365/D0.267..0.445").unwrap();
+        let mut simulation = ContinuousRunner::new(rule, 128, 128);
+        let mut rng = Rng::new(42);
+        simulation.randomize(&mut rng, 0.);
+
+        let mut counts = Default::default();
+        for _ in 0..50 {
+            counts = simulation.step(&mut rng);
+        }
+
+        let mass: f32 = simulation.grid().iter_rows().flatten().sum();
+        assert!(counts.alive > 0 && mass < 128. * 128. / 2., "{}", mass);
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::Display;
+
+use crate::automaton::{GenerationCounts, Parameter, Rgba, Simulation};
+use crate::grid::Grid;
+use crate::patterns::Pattern;
+use crate::util::{parse_parts, Rng};
+
+/// A rule where chance decides what happens to a cell, such as a tree catching fire. The rulestring is the rule's
+/// [Display], and has the current values of its parameters in it.
+pub trait StochasticRule: Display {
+    fn name(&self) -> String;
+
+    /// Names of the states, from state 0 up. They label the measurements in the chart too.
+    fn state_names(&self) -> &'static [&'static str];
+
+    /// What the cell turns into in the next generation, given a roll drawn uniformly from [0, 1) for it. Every cell
+    /// gets one roll per generation. A roll of 0.5 gives the outcome that is more likely than not.
+    fn next_state(&self, grid: &Grid<u8>, row: usize, column: usize, roll: f64) -> u8;
+
+    /// See [Simulation::count_neighbors]
+    fn count_neighbors(&self, grid: &Grid<u8>, row: usize, column: usize) -> u32;
+
+    fn palette(&self) -> Vec<Rgba>;
+
+    /// See [Simulation::parameters]
+    fn parameters(&self) -> Vec<Parameter>;
+
+    /// See [Simulation::set_parameter]
+    fn set_parameter(&mut self, idx: usize, value: f64);
+
+    /// State of a cell in a random soup, see [Simulation::randomize]
+    fn random_state(&self, rng: &mut Rng, density: f64) -> u8;
+
+    /// See [crate::automaton::Automaton::reset]
+    fn reset(&self, grid: &mut Grid<u8>) {
+        grid.fill(0);
+    }
+
+    /// See [crate::automaton::Automaton::starts_empty]
+    fn starts_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Runs a [StochasticRule] on a grid, double buffered like [crate::automaton::Runner]. Cells in state 1 count as
+/// alive, and cells in the other states but 0 as dying.
+pub struct StochasticRunner<R: StochasticRule> {
+    rule: R,
+    grid: Grid<u8>,
+    buffer: Grid<u8>,
+}
+
+impl<R: StochasticRule> StochasticRunner<R> {
+    /// Create a runner with the grid where the rule's runs start from
+    pub fn new(rule: R, rows: usize, columns: usize) -> Self {
+        let mut runner = Self {
+            rule,
+            grid: Grid::new(rows, columns, 0),
+            buffer: Grid::new(rows, columns, 0),
+        };
+        runner.clear();
+
+        runner
+    }
+
+    pub fn rule(&self) -> &R {
+        &self.rule
+    }
+
+    pub fn grid(&self) -> &Grid<u8> {
+        &self.grid
+    }
+}
+
+impl<R: StochasticRule> Simulation for StochasticRunner<R> {
+    fn name(&self) -> String {
+        self.rule.name()
+    }
+
+    fn rulestring(&self) -> String {
+        self.rule.to_string()
+    }
+
+    fn rows(&self) -> usize {
+        self.grid.rows()
+    }
+
+    fn columns(&self) -> usize {
+        self.grid.columns()
+    }
+
+    fn state_count(&self) -> usize {
+        self.rule.state_names().len()
+    }
+
+    fn step(&mut self, rng: &mut Rng) -> GenerationCounts {
+        let mut counts = GenerationCounts::default();
+
+        for row in 0..self.grid.rows() {
+            for column in 0..self.grid.columns() {
+                let cell = self.grid[row][column];
+                let next_cell = self
+                    .rule
+                    .next_state(&self.grid, row, column, rng.next_f64());
+                self.buffer[row][column] = next_cell;
+
+                match next_cell {
+                    0 => {}
+                    1 => counts.alive += 1,
+                    _ => counts.dying += 1,
+                }
+                match (cell == 1, next_cell == 1) {
+                    (false, true) => counts.births += 1,
+                    (true, false) => counts.deaths += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        std::mem::swap(&mut self.grid, &mut self.buffer);
+
+        counts
+    }
+
+    fn state(&self, row: usize, column: usize) -> u8 {
+        self.grid[row][column]
+    }
+
+    fn write_state(&mut self, row: usize, column: usize, state: u8) {
+        self.grid[row][column] = state;
+    }
+
+    fn clear(&mut self) {
+        self.rule.reset(&mut self.grid);
+    }
+
+    fn randomize(&mut self, rng: &mut Rng, density: f64) {
+        for row in self.grid.iter_rows_mut() {
+            for cell in row.iter_mut() {
+                *cell = self.rule.random_state(rng, density);
+            }
+        }
+    }
+
+    fn is_alive(&self, row: usize, column: usize) -> bool {
+        self.grid[row][column] == 1
+    }
+
+    /// The more likely outcome, since the actual one is up to chance
+    fn next_state(&self, row: usize, column: usize) -> u8 {
+        self.rule.next_state(&self.grid, row, column, 0.5)
+    }
+
+    fn count_neighbors(&self, row: usize, column: usize) -> u32 {
+        self.rule.count_neighbors(&self.grid, row, column)
+    }
+
+    fn state_name(&self, state: u8) -> String {
+        self.rule.state_names()[state as usize].to_string()
+    }
+
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        Some(self.rule.palette())
+    }
+
+    fn starts_empty(&self) -> bool {
+        self.rule.starts_empty()
+    }
+
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        Vec::new()
+    }
+
+    /// Number of cells in each state
+    fn measurements(&self) -> Vec<(&'static str, f32)> {
+        let mut counts = vec![0.; self.state_count()];
+        for &cell in self.grid.iter_rows().flatten() {
+            counts[cell as usize] += 1.;
+        }
+
+        self.rule
+            .state_names()
+            .iter()
+            .copied()
+            .zip(counts)
+            .collect()
+    }
+
+    fn parameters(&self) -> Vec<Parameter> {
+        self.rule.parameters()
+    }
+
+    fn set_parameter(&mut self, idx: usize, value: f64) {
+        self.rule.set_parameter(idx, value);
+    }
+}
+
+/// Parse the probabilities of a rulestring like `SIR/B0.25/G0.05`, whose parts are split by [parse_parts]
+pub fn parse_probabilities<const N: usize>(
+    rulestring: &str,
+    prefix: &str,
+    letters: [char; N],
+) -> Result<[f64; N], String> {
+    let values = parse_parts(rulestring, prefix, letters)?;
+
+    let mut probabilities = [0.; N];
+    for ((probability, value), letter) in probabilities.iter_mut().zip(values).zip(letters) {
+        *probability = value
+            .parse()
+            .ok()
+            .filter(|value| (0. ..=1.).contains(value))
+            .ok_or_else(|| {
+                format!(
+                    "Invalid rule `{}`: {} must be between 0 and 1",
+                    rulestring, letter
+                )
+            })?;
+    }
+
+    Ok(probabilities)
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_probabilities() {
+        assert_eq!(
+            parse_probabilities("SIR/B0.25/g1", "SIR", ['B', 'G']),
+            Ok([0.25, 1.])
+        );
+        assert!(parse_probabilities("SIR/B1.5/G0", "SIR", ['B', 'G']).is_err());
+        assert!(parse_probabilities("SIR/Bx/G0", "SIR", ['B', 'G']).is_err());
+    }
+}
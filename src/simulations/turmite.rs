@@ -0,0 +1,552 @@
+use std::fmt::{Display, Formatter};
+
+use crate::automaton::{Agent, GenerationCounts, Heading, Rgba, Simulation};
+use crate::grid::Grid;
+use crate::neighborhood::MOORE;
+use crate::patterns::Pattern;
+use crate::registry::Notation;
+use crate::util::{count_cells, Rng};
+
+/// Langton's Ant: turn right on empty cells and left on filled ones, flipping the cell. After about 10000 steps of
+/// chaos, it starts building a diagonal highway.
+pub const LANGTONS_ANT: &str = "RL";
+/// An ant that fills a square around where it started, growing it forever
+pub const SQUARE_BUILDER: &str = "LRRRRRLLR";
+
+/// How an ant turns before moving on, in the order of clockwise quarter turns
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Turn {
+    None,
+    Right,
+    Back,
+    Left,
+}
+
+impl Turn {
+    /// Codes of the turns in Golly's turmite notation
+    const CODES: [(Turn, u8); 4] = [
+        (Turn::None, 1),
+        (Turn::Right, 2),
+        (Turn::Back, 4),
+        (Turn::Left, 8),
+    ];
+    const LETTERS: [(Turn, char); 4] = [
+        (Turn::None, 'N'),
+        (Turn::Right, 'R'),
+        (Turn::Back, 'U'),
+        (Turn::Left, 'L'),
+    ];
+}
+
+/// What an ant in some state does on a cell of some color
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Action {
+    /// Color the cell is painted
+    write: u8,
+    turn: Turn,
+    /// State the ant goes into
+    next: u8,
+}
+
+/// A turmite: an ant with an internal state that walks over a grid of colored cells. At every step, it looks up
+/// what to do from its state and the color of the cell it is on, repaints the cell, turns and moves one cell forward.
+/// Written either as a string of turns, one for each color, like `RL` for Langton's Ant, where cells cycle through
+/// the colors and the ant has a single state, or in Golly's turmite notation, e.g. `{{{1,2,0},{0,8,0}}}`, which
+/// gives `{color to write, turn, next state}` for every state and color, with turns 1 (none), 2 (right), 4 (u-turn)
+/// and 8 (left).
+#[derive(Clone, PartialEq, Debug)]
+pub struct Turmite {
+    colors: usize,
+    /// Actions indexed by the state of the ant and then the color of the cell
+    table: Vec<Vec<Action>>,
+}
+
+impl Notation for Turmite {
+    const PRESETS: &'static [(&'static str, &'static str)] = &[
+        ("Langton's Ant", LANGTONS_ANT),
+        ("Square-building Ant", SQUARE_BUILDER),
+    ];
+
+    /// Whether the rulestring is meant to be a turmite
+    fn matches(rulestring: &str) -> bool {
+        let rulestring = rulestring.trim();
+
+        rulestring.starts_with('{')
+            || (rulestring.len() >= 2 && rulestring.chars().all(|c| "LRNU".contains(c)))
+    }
+
+    fn parse(rulestring: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+
+        let rulestring = rulestring.trim();
+        let table = if rulestring.starts_with('{') {
+            parse_table(rulestring).map_err(|err| invalid(&err))?
+        } else {
+            let turns = rulestring
+                .chars()
+                .map(|letter| {
+                    Turn::LETTERS
+                        .iter()
+                        .find(|&&(_, turn_letter)| turn_letter == letter)
+                        .map(|&(turn, _)| turn)
+                        .ok_or_else(|| invalid(&format!("`{}` is not a turn", letter)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let colors = turns.len();
+            vec![turns
+                .into_iter()
+                .enumerate()
+                .map(|(color, turn)| Action {
+                    write: ((color + 1) % colors) as u8,
+                    turn,
+                    next: 0,
+                })
+                .collect()]
+        };
+
+        let colors = table[0].len();
+        if !(2..=256).contains(&colors) {
+            return Err(invalid("turmites need between 2 and 256 colors"));
+        }
+        if table.len() > 256 {
+            return Err(invalid("turmites can have at most 256 states"));
+        }
+
+        Ok(Self { colors, table })
+    }
+
+    fn into_simulation(self, rows: usize, columns: usize) -> Box<dyn Simulation> {
+        Box::new(Ants::new(self, rows, columns))
+    }
+}
+
+impl Turmite {
+    fn action(&self, state: u8, color: u8) -> Action {
+        self.table[state as usize][color as usize]
+    }
+
+    /// Whether the turmite can be written as a string of turns
+    fn is_ant(&self) -> bool {
+        self.table.len() == 1
+            && self.table[0]
+                .iter()
+                .enumerate()
+                .all(|(color, action)| action.write as usize == (color + 1) % self.colors)
+    }
+}
+
+impl Display for Turmite {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_ant() {
+            for action in self.table[0].iter() {
+                let (_, letter) = Turn::LETTERS
+                    .iter()
+                    .find(|(turn, _)| *turn == action.turn)
+                    .unwrap();
+                write!(f, "{}", letter)?;
+            }
+            return Ok(());
+        }
+
+        let states: Vec<String> = self
+            .table
+            .iter()
+            .map(|actions| {
+                let actions: Vec<String> = actions
+                    .iter()
+                    .map(|action| {
+                        let (_, code) = Turn::CODES
+                            .iter()
+                            .find(|(turn, _)| *turn == action.turn)
+                            .unwrap();
+                        format!("{{{},{},{}}}", action.write, code, action.next)
+                    })
+                    .collect();
+                format!("{{{}}}", actions.join(","))
+            })
+            .collect();
+
+        write!(f, "{{{}}}", states.join(","))
+    }
+}
+
+/// Parse a table in Golly's turmite notation, e.g. `{{{1,2,0},{0,8,0}}}`
+fn parse_table(rulestring: &str) -> Result<Vec<Vec<Action>>, String> {
+    let compact: String = rulestring.chars().filter(|c| !c.is_whitespace()).collect();
+    let inner = compact
+        .strip_prefix("{{{")
+        .and_then(|inner| inner.strip_suffix("}}}"))
+        .ok_or("expected a table like {{{1,2,0},{0,8,0}}}")?;
+
+    let numbers = inner
+        .split("}},{{")
+        .map(|state| {
+            state
+                .split("},{")
+                .map(|action| {
+                    action
+                        .split(',')
+                        .map(|number| number.parse::<usize>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| format!("`{{{}}}` is not an action of numbers", action))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (states, colors) = (numbers.len(), numbers[0].len());
+    numbers
+        .into_iter()
+        .map(|actions| {
+            if actions.len() != colors {
+                return Err("every state needs an action for every color".to_string());
+            }
+
+            actions
+                .into_iter()
+                .map(|action| match action[..] {
+                    [write, code, next] if write < colors && next < states => {
+                        let turn = Turn::CODES
+                            .iter()
+                            .find(|&&(_, turn_code)| turn_code as usize == code)
+                            .map(|&(turn, _)| turn)
+                            .ok_or_else(|| {
+                                format!("`{}` is not a turn, expected 1, 2, 4 or 8", code)
+                            })?;
+
+                        Ok(Action {
+                            write: write as u8,
+                            turn,
+                            next: next as u8,
+                        })
+                    }
+                    [_, _, _] => Err(
+                        "actions can only write existing colors and go to existing states"
+                            .to_string(),
+                    ),
+                    _ => Err("actions are {color to write, turn, next state}".to_string()),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Turmites walking over a grid, which wraps around at the edges so that they never walk off it. Unlike cellular
+/// automata, only the cells under the ants change, one ant at a time in the order they were added.
+pub struct Ants {
+    rule: Turmite,
+    grid: Grid<u8>,
+    /// Every ant along with its state
+    ants: Vec<(Agent, u8)>,
+    /// Number of cells that are not empty
+    painted: usize,
+}
+
+impl Ants {
+    /// Create a grid of empty cells with a single ant in the middle, facing north
+    pub fn new(rule: Turmite, rows: usize, columns: usize) -> Self {
+        let mut ants = Self {
+            rule,
+            grid: Grid::new(rows, columns, 0),
+            ants: Vec::new(),
+            painted: 0,
+        };
+        ants.reset_ants();
+
+        ants
+    }
+
+    fn reset_ants(&mut self) {
+        let agent = Agent {
+            row: self.grid.rows() / 2,
+            column: self.grid.columns() / 2,
+            heading: Heading::North,
+        };
+        self.ants = vec![(agent, 0)];
+    }
+
+    /// Parse an ant written by [Simulation::session_state]
+    fn parse_ant(&self, line: &str) -> Result<(Agent, u8), String> {
+        let invalid = || format!("invalid ant `{}`", line);
+
+        let fields: Vec<&str> = line
+            .strip_prefix("ant")
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+            .ok_or_else(invalid)?
+            .split(',')
+            .map(str::trim)
+            .collect();
+        let [row, column, heading, state] = fields[..] else {
+            return Err(invalid());
+        };
+
+        let agent = Agent {
+            row: row.parse().map_err(|_| invalid())?,
+            column: column.parse().map_err(|_| invalid())?,
+            heading: Heading::parse(heading).ok_or_else(invalid)?,
+        };
+        let state: u8 = state.parse().map_err(|_| invalid())?;
+        if agent.row >= self.grid.rows()
+            || agent.column >= self.grid.columns()
+            || state as usize >= self.rule.table.len()
+        {
+            return Err(invalid());
+        }
+
+        Ok((agent, state))
+    }
+}
+
+impl Simulation for Ants {
+    fn name(&self) -> String {
+        format!("Turmite {}", self.rule)
+    }
+
+    fn rulestring(&self) -> String {
+        self.rule.to_string()
+    }
+
+    fn rows(&self) -> usize {
+        self.grid.rows()
+    }
+
+    fn columns(&self) -> usize {
+        self.grid.columns()
+    }
+
+    fn state_count(&self) -> usize {
+        self.rule.colors
+    }
+
+    fn step(&mut self, _rng: &mut Rng) -> GenerationCounts {
+        let (rows, columns) = (self.grid.rows() as isize, self.grid.columns() as isize);
+        let mut counts = GenerationCounts::default();
+
+        for (agent, state) in self.ants.iter_mut() {
+            let cell = &mut self.grid[agent.row][agent.column];
+            let action = self.rule.action(*state, *cell);
+
+            match (*cell != 0, action.write != 0) {
+                (false, true) => counts.births += 1,
+                (true, false) => counts.deaths += 1,
+                _ => {}
+            }
+
+            *cell = action.write;
+            *state = action.next;
+            agent.heading = agent.heading.turn(action.turn as u8);
+            let (row_offset, column_offset) = agent.heading.offset();
+            agent.row = (agent.row as isize + row_offset).rem_euclid(rows) as usize;
+            agent.column = (agent.column as isize + column_offset).rem_euclid(columns) as usize;
+        }
+
+        self.painted = self.painted + counts.births - counts.deaths;
+        counts.alive = self.painted;
+
+        counts
+    }
+
+    fn state(&self, row: usize, column: usize) -> u8 {
+        self.grid[row][column]
+    }
+
+    fn write_state(&mut self, row: usize, column: usize, state: u8) {
+        let cell = &mut self.grid[row][column];
+        self.painted = self.painted + usize::from(state != 0) - usize::from(*cell != 0);
+        *cell = state;
+    }
+
+    /// Empty the grid and go back to a single ant in the middle
+    fn clear(&mut self) {
+        self.grid.fill(0);
+        self.painted = 0;
+        self.reset_ants();
+    }
+
+    fn randomize(&mut self, rng: &mut Rng, density: f64) {
+        self.clear();
+
+        for row in 0..self.grid.rows() {
+            for column in 0..self.grid.columns() {
+                if rng.next_f64() < density {
+                    let color = 1 + rng.next_u64() % (self.rule.colors as u64 - 1);
+                    self.set_state(row, column, color as u8);
+                }
+            }
+        }
+    }
+
+    fn is_alive(&self, row: usize, column: usize) -> bool {
+        self.grid[row][column] != 0
+    }
+
+    /// The color the first ant on the cell paints it, if there is one
+    fn next_state(&self, row: usize, column: usize) -> u8 {
+        let color = self.grid[row][column];
+
+        self.ants
+            .iter()
+            .find(|(agent, _)| (agent.row, agent.column) == (row, column))
+            .map_or(color, |&(_, state)| self.rule.action(state, color).write)
+    }
+
+    fn count_neighbors(&self, row: usize, column: usize) -> u32 {
+        count_cells(&self.grid, &MOORE, row, column, |color| color != 0)
+    }
+
+    fn state_name(&self, state: u8) -> String {
+        format!("Color {}", state)
+    }
+
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        None
+    }
+
+    /// Ants start on an empty grid
+    fn starts_empty(&self) -> bool {
+        true
+    }
+
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        Vec::new()
+    }
+
+    fn agents(&self) -> Vec<Agent> {
+        self.ants.iter().map(|&(agent, _)| agent).collect()
+    }
+
+    fn add_agent(&mut self, row: usize, column: usize) -> bool {
+        let agent = Agent {
+            row,
+            column,
+            heading: Heading::North,
+        };
+        self.ants.push((agent, 0));
+
+        true
+    }
+
+    /// Every ant as `ant = row, column, heading, state`
+    fn session_state(&self) -> String {
+        self.ants
+            .iter()
+            .map(|(agent, state)| {
+                format!(
+                    "ant = {}, {}, {:?}, {}\n",
+                    agent.row, agent.column, agent.heading, state
+                )
+            })
+            .collect()
+    }
+
+    /// Replace the ants with the saved ones. Sessions without ants keep the ant in the middle.
+    fn restore_session_state(&mut self, state: &str) -> Result<(), String> {
+        let ants = state
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| self.parse_ant(line))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !ants.is_empty() {
+            self.ants = ants;
+        }
+        Ok(())
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rulestrings() {
+        for rulestring in [
+            LANGTONS_ANT,
+            SQUARE_BUILDER,
+            "{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}",
+        ] {
+            assert_eq!(
+                Turmite::parse(rulestring).map(|rule| rule.to_string()),
+                Ok(rulestring.to_string())
+            );
+        }
+
+        // Langton's Ant in Golly's notation
+        assert_eq!(
+            Turmite::parse("{{{1, 2, 0}, {0, 8, 0}}}").map(|rule| rule.to_string()),
+            Ok(LANGTONS_ANT.to_string())
+        );
+        assert!(Turmite::parse("R").is_err());
+        assert!(Turmite::parse("{{{1,2,0},{0,3,0}}}").is_err());
+        assert!(Turmite::parse("{{{1,2,1},{0,8,0}}}").is_err());
+        assert!(Turmite::matches("LLRR"));
+        assert!(!Turmite::matches("R5,C0,M1,S34..58,B34..45,NM"));
+    }
+
+    #[test]
+    fn langtons_ant_walks_a_square() {
+        let mut ants = Ants::new(Turmite::parse(LANGTONS_ANT).unwrap(), 5, 5);
+
+        // right turns around a square of filled cells, back to the start
+        let painted: usize = (0..4).map(|_| ants.step(&mut Rng::new(0)).births).sum();
+        assert_eq!(painted, 4);
+        assert_eq!(
+            ants.agents(),
+            [Agent {
+                row: 2,
+                column: 2,
+                heading: Heading::North
+            }]
+        );
+        assert_eq!(ants.next_state(2, 2), 0);
+
+        // a filled cell turns it left
+        let counts = ants.step(&mut Rng::new(0));
+        assert_eq!((counts.alive, counts.deaths), (3, 1));
+        assert_eq!(ants.agents()[0].column, 1);
+        assert_eq!(ants.agents()[0].heading, Heading::West);
+    }
+
+    #[test]
+    fn ants_wrap_around_edges() {
+        let mut ants = Ants::new(Turmite::parse("NN").unwrap(), 3, 3);
+        ants.add_agent(0, 0);
+
+        ants.step(&mut Rng::new(0));
+
+        assert_eq!(ants.agents()[1].row, 2);
+        assert_eq!(ants.state(0, 0), 1);
+        assert_eq!(ants.state(1, 1), 1);
+
+        ants.clear();
+        assert_eq!(ants.agents().len(), 1);
+    }
+
+    #[test]
+    fn restores_ants_from_session_state() {
+        let rule = Turmite::parse("{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}").unwrap();
+        let mut ants = Ants::new(rule.clone(), 6, 6);
+        ants.add_agent(1, 4);
+        for _ in 0..7 {
+            ants.step(&mut Rng::new(0));
+        }
+
+        let mut restored = Ants::new(rule, 6, 6);
+        restored
+            .restore_session_state(&ants.session_state())
+            .unwrap();
+        assert_eq!(restored.ants, ants.ants);
+
+        assert!(restored.restore_session_state("ant = 1, 2, Up, 0").is_err());
+        assert!(restored
+            .restore_session_state("ant = 6, 2, North, 0")
+            .is_err());
+        assert!(restored
+            .restore_session_state("ant = 1, 2, North, 2")
+            .is_err());
+        assert!(restored.restore_session_state("ant = 1, 2, North").is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::automaton::{Automaton, Rgba};
+use crate::grid::Grid;
+use crate::neighborhood::MOORE;
+use crate::patterns::Pattern;
+use crate::util::count_cells;
+
+/// Circuit parts to build with, in RLE with Golly's numbering of the states. Electrons travel from left to right,
+/// inputs come in on the left and outputs leave on the right.
+const PRESETS: [(&str, &str); 5] = [
+    ("Electron", "x = 12, y = 1, rule = WireWorld\nBA10C!"),
+    (
+        "Clock (period 6)",
+        "x = 10, y = 3, rule = WireWorld\n.2C$C2.7C$.BA!",
+    ),
+    ("Diode", "x = 12, y = 3, rule = WireWorld\n4.2C$5C.6C$4.2C!"),
+    (
+        "OR gate",
+        "x = 13, y = 7, rule = WireWorld\n4.2C$5C.2C$4.2C2.C$9.4C$4.2C2.C$5C.2C$4.2C!",
+    ),
+    (
+        "XOR gate",
+        "x = 22, y = 7, rule = WireWorld\n4.2C$5C.8C$4.2C7.3C$13.C2.6C$4.2C6.C$5C.6C$4.2C!",
+    ),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum WireState {
+    Empty,
+    ElectronHead,
+    ElectronTail,
+    Conductor,
+}
+
+impl From<u8> for WireState {
+    /// States are numbered like in Golly: 0 is empty, 1 an electron head, 2 an electron tail and 3 a conductor
+    fn from(state: u8) -> Self {
+        match state {
+            1 => WireState::ElectronHead,
+            2 => WireState::ElectronTail,
+            3 => WireState::Conductor,
+            _ => WireState::Empty,
+        }
+    }
+}
+
+impl From<WireState> for u8 {
+    fn from(state: WireState) -> Self {
+        match state {
+            WireState::Empty => 0,
+            WireState::ElectronHead => 1,
+            WireState::ElectronTail => 2,
+            WireState::Conductor => 3,
+        }
+    }
+}
+
+// Wireworld
+pub fn get_wireworld_next_cell_state(
+    state: &Grid<WireState>,
+    row: usize,
+    column: usize,
+) -> WireState {
+    match state[row][column] {
+        WireState::Empty => WireState::Empty,
+        // electrons move along conductors, head first
+        WireState::ElectronHead => WireState::ElectronTail,
+        WireState::ElectronTail => WireState::Conductor,
+        WireState::Conductor => {
+            let heads = count_cells(state, &MOORE, row, column, |cell| {
+                cell == WireState::ElectronHead
+            });
+            if heads == 1 || heads == 2 {
+                WireState::ElectronHead
+            } else {
+                WireState::Conductor
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Wireworld;
+
+impl Automaton for Wireworld {
+    type State = WireState;
+
+    fn name(&self) -> String {
+        "Wireworld".to_string()
+    }
+
+    /// Named like the rule that comes with Golly, so that patterns made there load here
+    fn rulestring(&self) -> String {
+        "WireWorld".to_string()
+    }
+
+    fn state_count(&self) -> usize {
+        4
+    }
+
+    fn next_state(&self, state: &Grid<WireState>, row: usize, column: usize) -> WireState {
+        get_wireworld_next_cell_state(state, row, column)
+    }
+
+    /// Same colors as in Golly
+    fn palette(&self) -> Option<Vec<Rgba>> {
+        Some(vec![
+            Rgba::rgb(48, 48, 48),
+            Rgba::rgb(0, 128, 255),
+            Rgba::rgb(255, 255, 255),
+            Rgba::rgb(255, 128, 0),
+        ])
+    }
+
+    /// Random soups of wire don't make circuits
+    fn starts_empty(&self) -> bool {
+        true
+    }
+
+    fn presets(&self) -> Vec<(String, Pattern)> {
+        PRESETS
+            .iter()
+            .map(|(name, rle)| {
+                let pattern = Pattern::parse_rle(rle).expect("presets are valid patterns");
+                (name.to_string(), pattern)
+            })
+            .collect()
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automaton::{Runner, Simulation};
+    use crate::util::Rng;
+
+    /// Place the preset with the given name, start electrons on the inputs in the given rows and count the
+    /// electrons that reach the end of the output in the given row
+    fn output_pulses(preset: &str, inputs: &[usize], output: usize) -> usize {
+        let (_, pattern) = Wireworld
+            .presets()
+            .into_iter()
+            .find(|(name, _)| name == preset)
+            .unwrap();
+        let mut simulation = Runner::new(Wireworld, pattern.rows, pattern.columns);
+        pattern.place(&mut simulation).unwrap();
+        for &row in inputs {
+            simulation.set_state(row, 0, 2);
+            simulation.set_state(row, 1, 1);
+        }
+
+        (0..60)
+            .filter(|_| {
+                simulation.step(&mut Rng::new(0));
+                simulation.state(output, pattern.columns - 1) == 1
+            })
+            .count()
+    }
+
+    #[test]
+    fn electrons_follow_wires() {
+        assert_eq!(output_pulses("Electron", &[], 0), 1);
+        assert_eq!(output_pulses("Clock (period 6)", &[], 1), 9);
+    }
+
+    #[test]
+    fn diodes_only_pass_one_way() {
+        assert_eq!(output_pulses("Diode", &[1], 1), 1);
+
+        let (_, diode) = Wireworld.presets().swap_remove(2);
+        let mut simulation = Runner::new(Wireworld, diode.rows, diode.columns);
+        diode.place(&mut simulation).unwrap();
+        simulation.set_state(1, diode.columns - 1, 2);
+        simulation.set_state(1, diode.columns - 2, 1);
+        let passed = (0..30).any(|_| {
+            simulation.step(&mut Rng::new(0));
+            simulation.state(1, 0) == 1
+        });
+
+        assert!(!passed);
+    }
+
+    #[test]
+    fn gates_compute_their_truth_tables() {
+        for (gate, both) in [("OR gate", 1), ("XOR gate", 0)] {
+            assert_eq!(output_pulses(gate, &[1], 3), 1, "{}", gate);
+            assert_eq!(output_pulses(gate, &[5], 3), 1, "{}", gate);
+            assert_eq!(output_pulses(gate, &[1, 5], 3), both, "{}", gate);
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use std::fs;
+use std::path::Path;
+
+use macroquad::color::{
+    Color, BLACK, BLUE, DARKBLUE, DARKGRAY, GRAY, LIGHTGRAY, LIME, ORANGE, PINK, RED, SKYBLUE,
+    WHITE, YELLOW,
+};
+
+use automata::automaton::Rgba;
+
+/// Every color used to draw the grid, the HUD and the chart
+#[derive(Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub name: String,
+    /// Shows through the gaps between cells
+    pub background: Color,
+    pub alive: Color,
+    pub dying: Color,
+    pub dead: Color,
+    pub text: Color,
+    pub ship: Color,
+    pub inspector: Color,
+    pub neighbor_count: Color,
+    pub chart_border: Color,
+    /// One color per chart series, reused from the start if there are more series than colors
+    pub chart_series: Vec<Color>,
+    /// Fresh births first, long-lived cells last
+    pub age_palette: Vec<Color>,
+    /// Cells that were never alive first, the most active cells last
+    pub heatmap_palette: Vec<Color>,
+    /// Values of continuous rules, from 0 to 1
+    pub colormap: Vec<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_string(),
+            background: BLACK,
+            alive: LIME,
+            dying: LIGHTGRAY,
+            dead: BLACK,
+            text: WHITE,
+            ship: YELLOW,
+            inspector: SKYBLUE,
+            neighbor_count: WHITE,
+            chart_border: LIGHTGRAY,
+            chart_series: vec![WHITE, ORANGE, SKYBLUE, PINK],
+            age_palette: vec![YELLOW, ORANGE, BLUE, DARKBLUE],
+            heatmap_palette: vec![BLACK, RED, YELLOW, WHITE],
+            colormap: vec![BLACK, DARKBLUE, hex(0x00B4FF), YELLOW, RED],
+        }
+    }
+}
+
+impl Theme {
+    /// Pure colors only, for low quality displays and projectors
+    pub fn high_contrast() -> Self {
+        let cyan = Color::new(0., 1., 1., 1.);
+        let magenta = Color::new(1., 0., 1., 1.);
+        let yellow = Color::new(1., 1., 0., 1.);
+
+        Self {
+            name: "High Contrast".to_string(),
+            background: BLACK,
+            alive: WHITE,
+            dying: Color::new(0., 0., 1., 1.),
+            dead: BLACK,
+            text: yellow,
+            ship: magenta,
+            inspector: cyan,
+            neighbor_count: magenta,
+            chart_border: WHITE,
+            chart_series: vec![WHITE, yellow, cyan, magenta],
+            age_palette: vec![WHITE, yellow, magenta, Color::new(0., 0., 1., 1.)],
+            heatmap_palette: vec![BLACK, Color::new(1., 0., 0., 1.), yellow, WHITE],
+            colormap: vec![BLACK, WHITE],
+        }
+    }
+
+    /// Based on the Okabe-Ito palette and viridis, which stay distinguishable with red-green color blindness
+    /// (deuteranopia and protanopia)
+    pub fn color_blind_safe() -> Self {
+        let orange = hex(0xE69F00);
+        let sky_blue = hex(0x56B4E9);
+        let yellow = hex(0xF0E442);
+        let blue = hex(0x0072B2);
+        let reddish_purple = hex(0xCC79A7);
+        let viridis = vec![
+            hex(0x440154),
+            hex(0x3B528B),
+            hex(0x21918C),
+            hex(0x5EC962),
+            hex(0xFDE725),
+        ];
+
+        Self {
+            name: "Color Blind Safe".to_string(),
+            background: BLACK,
+            alive: yellow,
+            dying: blue,
+            dead: BLACK,
+            text: WHITE,
+            ship: orange,
+            inspector: sky_blue,
+            neighbor_count: WHITE,
+            chart_border: LIGHTGRAY,
+            chart_series: vec![WHITE, orange, sky_blue, reddish_purple],
+            age_palette: viridis.iter().rev().copied().collect(),
+            heatmap_palette: viridis.clone(),
+            colormap: viridis,
+        }
+    }
+
+    /// Dark cells on a light background, for printing and screenshots in documents
+    pub fn light() -> Self {
+        Self {
+            name: "Light".to_string(),
+            background: hex(0xDDDDDD),
+            alive: BLACK,
+            dying: GRAY,
+            dead: WHITE,
+            text: BLACK,
+            ship: hex(0xD55E00),
+            inspector: hex(0x0072B2),
+            neighbor_count: hex(0xD55E00),
+            chart_border: DARKGRAY,
+            chart_series: vec![BLACK, hex(0xD55E00), hex(0x0072B2), hex(0x009E73)],
+            age_palette: vec![hex(0xD55E00), hex(0xE69F00), hex(0x0072B2), BLACK],
+            heatmap_palette: vec![WHITE, hex(0xFDE725), hex(0xD55E00), BLACK],
+            colormap: vec![WHITE, BLACK],
+        }
+    }
+
+    /// All the built-in themes, in the order they are cycled through
+    pub fn presets() -> Vec<Theme> {
+        vec![
+            Theme::default(),
+            Theme::high_contrast(),
+            Theme::color_blind_safe(),
+            Theme::light(),
+        ]
+    }
+
+    /// Color of a state for rules without their own palette: state 0 is dead, 1 alive, and any further states fade
+    /// from dying to dead
+    pub fn state_color(&self, state: u8, state_count: usize) -> Color {
+        match state {
+            0 => self.dead,
+            1 => self.alive,
+            state => gradient(
+                &[self.dying, self.dead],
+                (state - 2) as f32 / (state_count.max(3) - 2) as f32,
+            ),
+        }
+    }
+
+    /// Colors for every state of a rule, from the rule's own palette if it has one
+    pub fn palette(&self, palette: Option<Vec<Rgba>>, state_count: usize) -> Vec<Color> {
+        match palette {
+            Some(palette) => palette
+                .into_iter()
+                .map(|color| Color::from_rgba(color.r, color.g, color.b, color.a))
+                .collect(),
+            None => (0..state_count)
+                .map(|state| self.state_color(state as u8, state_count))
+                .collect(),
+        }
+    }
+
+    /// Colors for the given number of levels of a continuous rule, evenly spread over the colormap
+    pub fn colormap_palette(&self, levels: usize) -> Vec<Color> {
+        (0..levels)
+            .map(|level| gradient(&self.colormap, level as f32 / (levels.max(2) - 1) as f32))
+            .collect()
+    }
+
+    /// Color for the chart series at the given index
+    pub fn chart_series_color(&self, idx: usize) -> Color {
+        self.chart_series[idx % self.chart_series.len()]
+    }
+
+    /// Load a theme from a file, see [Theme::parse] for the format
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read theme {}: {}", path.display(), err))?;
+
+        Theme::parse(&contents).map_err(|err| format!("Invalid theme {}: {}", path.display(), err))
+    }
+
+    /// Parse a theme from `key = value` lines, with colors written as `#rrggbb` or `#rrggbbaa` and palettes as comma
+    /// separated lists of colors. Lines starting with `#` are comments. Anything not specified is taken from the
+    /// default theme.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut theme = Theme {
+            name: "Custom".to_string(),
+            ..Theme::default()
+        };
+
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let line_number = idx + 1;
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", line_number))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            let color =
+                || parse_color(value).map_err(|err| format!("line {}: {}", line_number, err));
+            let palette = |min_len: usize| {
+                let colors = value
+                    .split(',')
+                    .map(|color| parse_color(color.trim()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| format!("line {}: {}", line_number, err))?;
+
+                if colors.len() < min_len {
+                    return Err(format!(
+                        "line {}: {} needs at least {} colors",
+                        line_number, key, min_len
+                    ));
+                }
+
+                Ok(colors)
+            };
+
+            match key {
+                "name" => theme.name = value.to_string(),
+                "background" => theme.background = color()?,
+                "alive" => theme.alive = color()?,
+                "dying" => theme.dying = color()?,
+                "dead" => theme.dead = color()?,
+                "text" => theme.text = color()?,
+                "ship" => theme.ship = color()?,
+                "inspector" => theme.inspector = color()?,
+                "neighbor_count" => theme.neighbor_count = color()?,
+                "chart_border" => theme.chart_border = color()?,
+                "chart_series" => theme.chart_series = palette(1)?,
+                "age_palette" => theme.age_palette = palette(2)?,
+                "heatmap_palette" => theme.heatmap_palette = palette(2)?,
+                "colormap" => theme.colormap = palette(2)?,
+                other => return Err(format!("line {}: unknown key `{}`", line_number, other)),
+            }
+        }
+
+        Ok(theme)
+    }
+}
+
+/// Interpolate between evenly spaced colors, with t = 0 being the first color and t = 1 the last one
+pub fn gradient(palette: &[Color], t: f32) -> Color {
+    let position = t.clamp(0., 1.) * (palette.len() - 1) as f32;
+    let idx = (position as usize).min(palette.len() - 2);
+    let t = position - idx as f32;
+    let (from, to) = (palette[idx], palette[idx + 1]);
+
+    Color::new(
+        from.r + (to.r - from.r) * t,
+        from.g + (to.g - from.g) * t,
+        from.b + (to.b - from.b) * t,
+        from.a + (to.a - from.a) * t,
+    )
+}
+
+fn hex(rgb: u32) -> Color {
+    Color::from_rgba((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+}
+
+/// Parse a color written as `#rrggbb` or `#rrggbbaa`
+fn parse_color(value: &str) -> Result<Color, String> {
+    let invalid = || format!("invalid color `{}`, expected #rrggbb or #rrggbbaa", value);
+
+    let digits = value.strip_prefix('#').ok_or_else(invalid)?;
+    if !digits.is_ascii() || (digits.len() != 6 && digits.len() != 8) {
+        return Err(invalid());
+    }
+
+    let component =
+        |idx: usize| u8::from_str_radix(&digits[idx..idx + 2], 16).map_err(|_| invalid());
+    let alpha = if digits.len() == 8 {
+        component(6)?
+    } else {
+        255
+    };
+
+    Ok(Color::from_rgba(
+        component(0)?,
+        component(2)?,
+        component(4)?,
+        alpha,
+    ))
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_interpolates_between_colors() {
+        let red = Color::new(1., 0., 0., 1.);
+        let palette = [BLACK, WHITE, red];
+
+        assert_eq!(gradient(&palette, 0.), BLACK);
+        assert_eq!(gradient(&palette, 0.25), Color::new(0.5, 0.5, 0.5, 1.));
+        assert_eq!(gradient(&palette, 0.5), WHITE);
+        assert_eq!(gradient(&palette, 0.75), Color::new(1., 0.5, 0.5, 1.));
+        assert_eq!(gradient(&palette, 1.), red);
+        assert_eq!(gradient(&palette, 2.), red);
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#ff0000"), Ok(Color::from_rgba(255, 0, 0, 255)));
+        assert_eq!(
+            parse_color("#00ff0080"),
+            Ok(Color::from_rgba(0, 255, 0, 128))
+        );
+        assert!(parse_color("ff0000").is_err());
+        assert!(parse_color("#ff00").is_err());
+        assert!(parse_color("#gg0000").is_err());
+    }
+
+    #[test]
+    fn parses_themes() {
+        let theme = Theme::parse(
+            "# printer friendly\n\
+             name = Paper\n\
+             alive = #000000\n\
+             dead = #ffffff\n\
+             chart_series = #000000, #ff0000\n",
+        )
+        .unwrap();
+
+        assert_eq!(theme.name, "Paper");
+        assert_eq!(theme.state_color(1, 2), BLACK);
+        assert_eq!(theme.state_color(0, 2), WHITE);
+        assert_eq!(
+            theme.chart_series_color(3),
+            Color::from_rgba(255, 0, 0, 255)
+        );
+        // unspecified colors fall back to the default theme
+        assert_eq!(theme.dying, Theme::default().dying);
+    }
+
+    #[test]
+    fn colors_states_without_a_palette() {
+        let theme = Theme::default();
+
+        assert_eq!(
+            theme.palette(None, 3),
+            [theme.dead, theme.alive, theme.dying]
+        );
+        assert_eq!(
+            theme.state_color(4, 5),
+            gradient(&[theme.dying, theme.dead], 2. / 3.)
+        );
+        assert_eq!(
+            theme.palette(Some(vec![Rgba::rgb(255, 0, 0)]), 1),
+            [Color::from_rgba(255, 0, 0, 255)]
+        );
+    }
+
+    #[test]
+    fn colors_levels_with_the_colormap() {
+        let theme = Theme::parse("colormap = #000000, #ffffff").unwrap();
+        let palette = theme.colormap_palette(256);
+
+        assert_eq!(palette.len(), 256);
+        assert_eq!(palette[0], BLACK);
+        assert_eq!(palette[255], WHITE);
+        assert!((palette[51].r - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reports_invalid_themes() {
+        assert_eq!(
+            Theme::parse("alive = #000000\nsparkles = #ffffff"),
+            Err("line 2: unknown key `sparkles`".to_string())
+        );
+        assert_eq!(
+            Theme::parse("alive"),
+            Err("line 1: expected `key = value`".to_string())
+        );
+        assert_eq!(
+            Theme::parse("age_palette = #000000"),
+            Err("line 1: age_palette needs at least 2 colors".to_string())
+        );
+    }
+}
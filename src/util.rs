@@ -1,51 +1,232 @@
-use crate::{CellState, SimulationState, COLUMNS, ROWS};
+use crate::grid::Grid;
+use crate::{CellState, SimulationState};
 
-/// Computes the count of cells of interest surrounding the current cell in the matrix
-pub fn count_cells<F>(state: &SimulationState, row: usize, column: usize, predicate: F) -> u8
+/// Computes the count of cells of interest among the neighbors of the current cell, given as offsets such as
+/// [crate::neighborhood::MOORE]. Neighbors beyond the edges of the grid are not counted.
+pub fn count_cells<T, F>(
+    state: &Grid<T>,
+    offsets: &[(isize, isize)],
+    row: usize,
+    column: usize,
+    predicate: F,
+) -> u32
 where
-    F: Fn(CellState) -> bool,
+    T: Copy,
+    F: Fn(T) -> bool,
 {
+    let (rows, columns) = (state.rows(), state.columns());
     let mut count = 0;
 
-    // up
-    if row > 0 && predicate(state[row - 1][column]) {
-        count += 1;
+    for &(row_offset, column_offset) in offsets {
+        let (Some(neighbor_row), Some(neighbor_column)) = (
+            row.checked_add_signed(row_offset),
+            column.checked_add_signed(column_offset),
+        ) else {
+            continue;
+        };
+
+        if neighbor_row < rows
+            && neighbor_column < columns
+            && predicate(state[neighbor_row][neighbor_column])
+        {
+            count += 1;
+        }
+    }
+
+    count
+}
+
+/// The live cells of the 3x3 block around a cell as a 9-bit number, read row by row with the upper left cell as the
+/// highest bit, the same order as in MAP rulestrings. Cells beyond the edges of the grid count as dead.
+pub fn neighborhood_index(state: &SimulationState, row: usize, column: usize) -> usize {
+    let (rows, columns) = (state.rows(), state.columns());
+    let mut index = 0;
+
+    for neighbor_row in row as isize - 1..=row as isize + 1 {
+        for neighbor_column in column as isize - 1..=column as isize + 1 {
+            let alive = (0..rows as isize).contains(&neighbor_row)
+                && (0..columns as isize).contains(&neighbor_column)
+                && state[neighbor_row as usize][neighbor_column as usize] == CellState::Alive;
+
+            index = (index << 1) | usize::from(alive);
+        }
+    }
+
+    index
+}
+
+/// Sums of a value over every rectangle of the grid, answered in constant time, so that neighbor counts in large
+/// neighborhoods don't have to visit every neighbor
+pub struct SummedAreaTable {
+    columns: usize,
+    /// Sum of the values above and to the left of each cell, with an extra row and column of zeros in front
+    sums: Vec<u32>,
+}
+
+impl SummedAreaTable {
+    pub fn new<F>(rows: usize, columns: usize, value: F) -> Self
+    where
+        F: Fn(usize, usize) -> u32,
+    {
+        let width = columns + 1;
+        let mut sums = vec![0; (rows + 1) * width];
+
+        for row in 0..rows {
+            let mut row_sum = 0;
+            for column in 0..columns {
+                row_sum += value(row, column);
+                sums[(row + 1) * width + column + 1] = sums[row * width + column + 1] + row_sum;
+            }
+        }
+
+        Self { columns, sums }
+    }
+
+    /// Sum over the rows and columns within the given distance of the cell, cut off at the edges of the grid
+    pub fn sum_around(
+        &self,
+        row: usize,
+        column: usize,
+        row_distance: usize,
+        column_distance: usize,
+    ) -> u32 {
+        let rows = self.sums.len() / (self.columns + 1) - 1;
+        let (top, bottom) = (
+            row.saturating_sub(row_distance),
+            (row + row_distance + 1).min(rows),
+        );
+        let (left, right) = (
+            column.saturating_sub(column_distance),
+            (column + column_distance + 1).min(self.columns),
+        );
+
+        let at = |row: usize, column: usize| self.sums[row * (self.columns + 1) + column];
+        at(bottom, right) + at(top, left) - at(top, right) - at(bottom, left)
+    }
+}
+
+/// Split a rulestring like `SIR/B0.25/G0.05` or `Lenia/R13/T10` into the values of its parts: the given prefix,
+/// followed by parts that start with the given letters, in that order
+pub fn parse_parts<'a, const N: usize>(
+    rulestring: &'a str,
+    prefix: &str,
+    letters: [char; N],
+) -> Result<[&'a str; N], String> {
+    let invalid = |reason: &str| format!("Invalid rule `{}`: {}", rulestring, reason);
+
+    if !has_prefix(rulestring, prefix) {
+        return Err(invalid(&format!("expected it to start with {}/", prefix)));
     }
+    let mut parts = rulestring.trim().split('/').skip(1);
 
-    // up left
-    if row > 0 && column > 0 && predicate(state[row - 1][column - 1]) {
-        count += 1;
+    let mut values = [""; N];
+    for (value, letter) in values.iter_mut().zip(letters) {
+        let part = parts.next().unwrap_or_default().trim();
+        *value = part
+            .strip_prefix(letter)
+            .or_else(|| part.strip_prefix(letter.to_ascii_lowercase()))
+            .ok_or_else(|| invalid(&format!("expected a part starting with {}", letter)))?;
     }
 
-    // up right
-    if row > 0 && column < COLUMNS - 1 && predicate(state[row - 1][column + 1]) {
-        count += 1;
+    if let Some(part) = parts.next() {
+        return Err(invalid(&format!("unexpected part `{}`", part)));
     }
 
-    // down
-    if row < ROWS - 1 && predicate(state[row + 1][column]) {
-        count += 1;
+    Ok(values)
+}
+
+/// Whether the rulestring starts with the given prefix, followed by a slash or nothing at all
+pub fn has_prefix(rulestring: &str, prefix: &str) -> bool {
+    rulestring
+        .trim()
+        .split('/')
+        .next()
+        .is_some_and(|part| part.eq_ignore_ascii_case(prefix))
+}
+
+/// A small pseudo random number generator (SplitMix64). Unlike the global generator in macroquad, its state can be
+/// read back, so that a saved session continues with the same random numbers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator from a seed, or from the state of another generator
+    pub fn new(state: u64) -> Self {
+        Self { state }
     }
 
-    // down left
-    if row < ROWS - 1 && column > 0 && predicate(state[row + 1][column - 1]) {
-        count += 1;
+    pub fn state(&self) -> u64 {
+        self.state
     }
 
-    // down right
-    if row < ROWS - 1 && column < COLUMNS - 1 && predicate(state[row + 1][column + 1]) {
-        count += 1;
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
     }
 
-    // left
-    if column > 0 && predicate(state[row][column - 1]) {
-        count += 1;
+    /// A number in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
     }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // right
-    if column < COLUMNS - 1 && predicate(state[row][column + 1]) {
-        count += 1;
+    #[test]
+    fn rng_continues_from_its_state() {
+        let mut rng = Rng::new(42);
+        rng.next_u64();
+
+        let mut restored = Rng::new(rng.state());
+
+        assert_eq!(restored.next_u64(), rng.next_u64());
+        assert!((0. ..1.).contains(&rng.next_f64()));
     }
 
-    count
+    #[test]
+    fn neighborhood_index_reads_rows_from_the_top() {
+        let mut state = crate::grid::Grid::new(3, 3, CellState::Dead);
+        state[0][0] = CellState::Alive;
+        state[1][2] = CellState::Alive;
+        state[2][1] = CellState::Dying;
+
+        assert_eq!(neighborhood_index(&state, 1, 1), 0b100_001_000);
+        assert_eq!(neighborhood_index(&state, 0, 0), 0b000_010_000);
+        assert_eq!(neighborhood_index(&state, 2, 2), 0b010_000_000);
+    }
+
+    #[test]
+    fn parses_parts() {
+        assert_eq!(
+            parse_parts("Lenia/R13/t10", "Lenia", ['R', 'T']),
+            Ok(["13", "10"])
+        );
+        assert!(parse_parts("Lenia/R13", "Lenia", ['R', 'T']).is_err());
+        assert!(parse_parts("Lenia/R13/T10/X", "Lenia", ['R', 'T']).is_err());
+        assert!(parse_parts("SmoothLife/R13/T10", "Lenia", ['R', 'T']).is_err());
+        assert!(parse_parts("Lenia/Ré/T10", "Lenia", ['R', 'T']).is_ok());
+        assert!(parse_parts("é", "Lenia", ['R', 'T']).is_err());
+    }
+
+    #[test]
+    fn summed_area_table_sums_rectangles() {
+        let table = SummedAreaTable::new(4, 5, |row, column| (row * 5 + column) as u32);
+
+        assert_eq!(table.sum_around(0, 0, 0, 0), 0);
+        assert_eq!(
+            table.sum_around(2, 2, 1, 1),
+            6 + 7 + 8 + 11 + 12 + 13 + 16 + 17 + 18
+        );
+        assert_eq!(table.sum_around(3, 4, 1, 2), 12 + 13 + 14 + 17 + 18 + 19);
+        assert_eq!(table.sum_around(1, 1, 10, 10), (0..20).sum());
+    }
 }
@@ -0,0 +1,178 @@
+use std::ops::Range;
+
+use automata::session::Camera;
+
+/// How far in we can zoom, as the size of a single cell in pixels
+const MAX_CELL_SIZE: f32 = 64.;
+
+/// Maps between grid cells and screen coordinates, so that the grid can be zoomed and panned
+pub struct Viewport {
+    /// Size of a single cell on screen, in pixels
+    cell_size: f32,
+    /// Smallest cell size, at which the whole grid fits on screen
+    min_cell_size: f32,
+    /// Screen position of the upper left corner of the grid
+    offset_x: f32,
+    offset_y: f32,
+    screen_width: f32,
+    screen_height: f32,
+    rows: usize,
+    columns: usize,
+}
+
+impl Viewport {
+    /// Create a viewport that fits a grid with the given number of rows and columns on a screen of the given size
+    pub fn new(screen_width: f32, screen_height: f32, rows: usize, columns: usize) -> Self {
+        let min_cell_size = (screen_width / columns as f32).min(screen_height / rows as f32);
+
+        Self {
+            cell_size: min_cell_size,
+            min_cell_size,
+            offset_x: 0.,
+            offset_y: 0.,
+            screen_width,
+            screen_height,
+            rows,
+            columns,
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Screen position of the upper left corner of a cell
+    pub fn cell_position(&self, row: usize, column: usize) -> (f32, f32) {
+        (
+            self.offset_x + column as f32 * self.cell_size,
+            self.offset_y + row as f32 * self.cell_size,
+        )
+    }
+
+    /// The row and column of the cell under a point on the screen, if there is one
+    pub fn cell_at(&self, x: f32, y: f32) -> Option<(usize, usize)> {
+        let column = ((x - self.offset_x) / self.cell_size).floor();
+        let row = ((y - self.offset_y) / self.cell_size).floor();
+
+        if row < 0. || column < 0. || row >= self.rows as f32 || column >= self.columns as f32 {
+            return None;
+        }
+
+        Some((row as usize, column as usize))
+    }
+
+    /// Rows and columns that are at least partially on screen
+    pub fn visible_cells(&self) -> (Range<usize>, Range<usize>) {
+        let first = |offset: f32| (-offset / self.cell_size).floor().max(0.) as usize;
+        let last = |offset: f32, screen_size: f32, count: usize| {
+            (((screen_size - offset) / self.cell_size).ceil().max(0.) as usize).min(count)
+        };
+
+        (
+            first(self.offset_y)..last(self.offset_y, self.screen_height, self.rows),
+            first(self.offset_x)..last(self.offset_x, self.screen_width, self.columns),
+        )
+    }
+
+    pub fn camera(&self) -> Camera {
+        Camera {
+            cell_size: self.cell_size,
+            offset_x: self.offset_x,
+            offset_y: self.offset_y,
+        }
+    }
+
+    /// Move to a saved camera position, as far as it fits on the current screen
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.cell_size = camera.cell_size.clamp(self.min_cell_size, MAX_CELL_SIZE);
+        self.offset_x = camera.offset_x;
+        self.offset_y = camera.offset_y;
+
+        self.clamp_offset();
+    }
+
+    /// Scale the cell size by the given factor, keeping the point under (x, y) in place
+    pub fn zoom(&mut self, factor: f32, x: f32, y: f32) {
+        let cell_size = (self.cell_size * factor).clamp(self.min_cell_size, MAX_CELL_SIZE);
+        let scale = cell_size / self.cell_size;
+
+        self.offset_x = x - (x - self.offset_x) * scale;
+        self.offset_y = y - (y - self.offset_y) * scale;
+        self.cell_size = cell_size;
+
+        self.clamp_offset();
+    }
+
+    /// Move the grid by the given number of pixels
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.offset_x += dx;
+        self.offset_y += dy;
+
+        self.clamp_offset();
+    }
+
+    /// Keep the grid covering the screen, so that we never pan off into the void
+    fn clamp_offset(&mut self) {
+        let min_x = (self.screen_width - self.columns as f32 * self.cell_size).min(0.);
+        let min_y = (self.screen_height - self.rows as f32 * self.cell_size).min(0.);
+
+        self.offset_x = self.offset_x.clamp(min_x, 0.);
+        self.offset_y = self.offset_y.clamp(min_y, 0.);
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewport_fits_grid() {
+        let viewport = Viewport::new(1024., 1024., 256, 256);
+
+        assert_eq!(viewport.cell_size(), 4.);
+        assert_eq!(viewport.cell_at(0., 0.), Some((0, 0)));
+        assert_eq!(viewport.cell_at(1023., 9.), Some((2, 255)));
+        assert_eq!(viewport.cell_at(1024., 9.), None);
+        assert_eq!(viewport.visible_cells(), (0..256, 0..256));
+    }
+
+    #[test]
+    fn viewport_zooms_around_point() {
+        let mut viewport = Viewport::new(1024., 1024., 256, 256);
+        let cell = viewport.cell_at(500., 300.);
+
+        viewport.zoom(4., 500., 300.);
+
+        assert_eq!(viewport.cell_size(), 16.);
+        assert_eq!(viewport.cell_at(500., 300.), cell);
+        // 64 columns fit on screen, but the ones at the edges are only partially visible
+        assert_eq!(viewport.visible_cells().1, 93..158);
+    }
+
+    #[test]
+    fn viewport_stays_on_grid() {
+        let mut viewport = Viewport::new(1024., 1024., 256, 256);
+        viewport.zoom(2., 0., 0.);
+        viewport.pan(100., -10_000.);
+
+        assert_eq!(viewport.cell_position(0, 0), (0., -1024.));
+
+        viewport.zoom(0.1, 0., 0.);
+
+        assert_eq!(viewport.cell_position(0, 0), (0., 0.));
+    }
+
+    #[test]
+    fn viewport_restores_camera() {
+        let mut viewport = Viewport::new(1024., 1024., 256, 256);
+        viewport.zoom(4., 500., 300.);
+        let camera = viewport.camera();
+
+        let mut restored = Viewport::new(1024., 1024., 256, 256);
+        restored.set_camera(camera);
+
+        assert_eq!(restored.camera(), camera);
+        assert_eq!(restored.visible_cells(), viewport.visible_cells());
+    }
+}